@@ -3,9 +3,57 @@
 use bmp::Image;
 use eframe::egui;
 use egui_extras::RetainedImage;
-use std::io::Cursor;
+use std::collections::BTreeMap;
 
-mod bmp_generator;
+mod editor;
+
+use crate::bmp_generator;
+use bmp_generator::array::Arrangement;
+use bmp_generator::bmp_generator::{FillRule, LineCap, Outline, PaintMode, StrokeAlignment};
+use editor::{Drag, EditableShape, PreviewTool};
+use bmp_generator::compare::{self, Comparison, Region};
+use bmp_generator::description::{Operation, PatternDescription};
+use bmp_generator::edid::Edid;
+use bmp_generator::error::PatternError;
+use bmp_generator::generators::{Parameter, ParameterKind, ParameterValue, Registry};
+use bmp_generator::metadata::{self, Metadata};
+use bmp_generator::scopes::{self, LumaCoefficients};
+use bmp_generator::script;
+use bmp_generator::symmetry::Symmetry;
+use bmp_generator::paint::{remap, Gradient, GradientKind, GradientStop, Interpolation, Paint, Pattern, PatternKind};
+use bmp_generator::resolution::{Resolution, PRESETS};
+use bmp_generator::transform::Transform;
+
+/// The shapes from the controls that can be repeated in an array.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+enum ArrayShape {
+    Rect,
+    Ellipse,
+    Triangle,
+    Polygon,
+    Line,
+    Bezier,
+    Arc,
+}
+
+impl ArrayShape {
+    const ALL: [ArrayShape; 7] = [
+        ArrayShape::Rect,
+        ArrayShape::Ellipse,
+        ArrayShape::Triangle,
+        ArrayShape::Polygon,
+        ArrayShape::Line,
+        ArrayShape::Bezier,
+        ArrayShape::Arc,
+    ];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+enum ArrayKind {
+    Linear,
+    Grid,
+    Radial,
+}
 
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)]
@@ -15,60 +63,240 @@ pub struct TestPatternGenerator {
     rounding: f32,
     #[serde(skip)]
     bmp: Option<Image>,
+    /// Operations drawn since the canvas was created, saved as metadata with the image.
+    #[serde(skip)]
+    operations: Vec<Operation>,
+    /// The image currently in the preview, which is the canvas unless a diff is being shown.
+    #[serde(skip)]
+    preview: Option<Image>,
+    /// Why the last image couldn't be shown in the preview.
+    #[serde(skip)]
+    preview_error: Option<String>,
+    #[serde(skip)]
+    hovered_pixel: Option<[u32; 2]>,
+    probes: Vec<[u32; 2]>,
+    preview_tool: PreviewTool,
+    show_scopes: bool,
+    scopes_docked: bool,
+    scope_coefficients: LumaCoefficients,
+    #[serde(skip)]
+    scope_images: Vec<(&'static str, RetainedImage)>,
+    selected_shape: EditableShape,
+    #[serde(skip)]
+    drag: Option<Drag>,
     height: u32,
     width: u32,
+    /// Canvas sizes saved from the app, listed after the built in presets.
+    user_resolutions: Vec<Resolution>,
+    resolution_name: String,
+    edid_path: String,
+    /// The display EDID last loaded, shown under the canvas size.
+    #[serde(skip)]
+    edid: Option<Edid>,
     scale: f32,
-    num_stripe_colors: u32,
-    stripe_spacing: u32,
-    horizontal_stripes: bool,
-    rect_start: [u32; 2],
-    rect_end: [u32; 2],
+    background: Paint,
+    open_path: String,
+    save_path: String,
+    layer_position: [f64; 2],
+    layer_scale: f64,
+    compare_path: String,
+    compare_offset: [i32; 2],
+    compare_align: u32,
+    compare_crop_enabled: bool,
+    compare_crop: [u32; 4],
+    diff_gain: u32,
+    #[serde(skip)]
+    captured: Option<Image>,
+    #[serde(skip)]
+    comparison: Option<Comparison>,
+    script_folder: String,
+    /// The built in generators and the scripts from `script_folder`.
+    #[serde(skip)]
+    generators: Registry,
+    selected_generator: String,
+    /// Parameter values per generator, by generator name.
+    generator_values: BTreeMap<String, BTreeMap<String, ParameterValue>>,
+    #[serde(skip)]
+    generator_error: Option<String>,
+    /// The last operation that failed, shown above the controls until the next one succeeds.
+    #[serde(skip)]
+    error: Option<String>,
+    rect_start: [f64; 2],
+    rect_end: [f64; 2],
     rect_rotation: f64,
-    rect_color: egui::Color32,
-    ellipse_center: [u32; 2],
-    ellipse_size: [u32; 2],
-    ellipse_color: egui::Color32,
+    rect_per_corner_rounding: bool,
+    rect_corner_radii: [f32; 4],
+    rect_paint: Paint,
+    rect_outline: Outline,
+    ellipse_center: [f64; 2],
+    ellipse_size: [f64; 2],
+    ellipse_paint: Paint,
+    ellipse_outline: Outline,
     ellipse_rotation: f64,
+    triangle_points: [[f64; 2]; 3],
+    triangle_paint: Paint,
+    triangle_outline: Outline,
+    polygon_points: String,
+    polygon_fill_rule: FillRule,
+    polygon_paint: Paint,
+    polygon_outline: Outline,
+    line_start: [f64; 2],
+    line_end: [f64; 2],
+    line_thickness: f64,
+    line_cap: LineCap,
+    line_paint: Paint,
+    bezier_points: [[f64; 2]; 4],
+    bezier_cubic: bool,
+    bezier_rotation: f64,
+    bezier_thickness: f64,
+    bezier_filled: bool,
+    bezier_paint: Paint,
+    bezier_outline: Outline,
+    arc_center: [f64; 2],
+    arc_size: [f64; 2],
+    arc_angles: [f64; 2],
+    arc_rotation: f64,
+    arc_thickness: f64,
+    arc_filled: bool,
+    arc_paint: Paint,
+    arc_outline: Outline,
+    /// Moves, turns, scales and skews every shape drawn from the controls, about `transform_pivot`.
+    transform_offset: [f64; 2],
+    transform_rotation: f64,
+    transform_scale: [f64; 2],
+    transform_skew: [f64; 2],
+    transform_pivot: [f64; 2],
+    /// Copies every shape drawn from the controls about the middle of the canvas.
+    symmetry: Option<Symmetry>,
+    /// Folds to use when rotational symmetry is picked, remembered while another mode is.
+    symmetry_folds: u32,
+    array_shape: ArrayShape,
+    array_kind: ArrayKind,
+    /// Copies in a linear or radial array.
+    array_count: u32,
+    array_step: [f64; 2],
+    array_columns: u32,
+    array_rows: u32,
+    array_spacing: [f64; 2],
+    array_center: [f64; 2],
+    array_angle_step: f64,
+    array_radius_step: f64,
+    array_cycle_colors: bool,
+    array_colors: Vec<[u8; 3]>,
 }
 
 impl Default for TestPatternGenerator {
     fn default() -> Self {
-        let bmp_from_file = image::open("assets/test.bmp").unwrap();
-
-        let mut bytes = Vec::new();
-        bmp_from_file
-            .write_to(&mut Cursor::new(&mut bytes), image::ImageOutputFormat::Png)
-            .unwrap();
-
         let bmp = Some(bmp_generator::bmp_generator::BmpGenerator::clear(
             1920, 1080,
         ));
 
-        let mut bytes: Vec<u8> = vec![];
-        let _ = bmp.as_ref().unwrap().to_writer(&mut bytes);
-
-        let image = RetainedImage::from_image_bytes("image.png", bytes.as_slice())
-            .unwrap()
-            .with_options(egui::TextureOptions::NEAREST);
+        let image = to_retained_image("image.png", bmp.as_ref().unwrap()).expect("a blank 1920x1080 image can be shown");
 
         Self {
-            image: image,
-            rounding: 32.0,
-            bmp: bmp,
+            image,
+            rounding: 0.0,
+            preview: bmp.clone(),
+            preview_error: None,
+            bmp,
+            operations: vec![Operation::Background {
+                width: 1920,
+                height: 1080,
+                paint: Paint::Solid([0, 0, 0]),
+            }],
+            hovered_pixel: None,
+            probes: vec![],
+            preview_tool: PreviewTool::Inspect,
+            show_scopes: false,
+            scopes_docked: true,
+            scope_coefficients: LumaCoefficients::Bt709,
+            scope_images: vec![],
+            selected_shape: EditableShape::Rect,
+            drag: None,
             width: 1920,
             height: 1080,
-            num_stripe_colors: 8,
-            stripe_spacing: 1,
+            script_folder: "scripts".to_owned(),
+            generators: Registry::builtin(),
+            selected_generator: "Stripes".to_owned(),
+            generator_values: BTreeMap::new(),
+            generator_error: None,
+            error: None,
+            user_resolutions: Vec::new(),
+            resolution_name: String::new(),
+            edid_path: "/sys/class/drm/card0-HDMI-A-1/edid".to_owned(),
+            edid: None,
             scale: 500.0 / 1080.0,
-            horizontal_stripes: false,
-            rect_start: [760, 340],
-            rect_end: [1160, 740],
+            background: Paint::Solid([0, 0, 0]),
+            open_path: "assets/test.bmp".to_owned(),
+            save_path: "assets/image.png".to_owned(),
+            layer_position: [0.0, 0.0],
+            layer_scale: 1.0,
+            compare_path: "assets/image.bmp".to_owned(),
+            compare_offset: [0, 0],
+            compare_align: 0,
+            compare_crop_enabled: false,
+            compare_crop: [0, 0, 1920, 1080],
+            diff_gain: 1,
+            captured: None,
+            comparison: None,
+            rect_start: [760.0, 340.0],
+            rect_end: [1160.0, 740.0],
             rect_rotation: 0.0,
-            rect_color: egui::Color32::from_rgb(255, 0, 255),
-            ellipse_center: [1920 / 2, 1080 / 2],
-            ellipse_size: [200, 200],
-            ellipse_color: egui::Color32::from_rgb(0, 255, 255),
+            rect_per_corner_rounding: false,
+            rect_corner_radii: [0.0; 4],
+            rect_paint: Paint::Solid([255, 0, 255]),
+            rect_outline: Outline::default(),
+            ellipse_center: [960.0, 540.0],
+            ellipse_size: [200.0, 200.0],
+            ellipse_paint: Paint::Solid([0, 255, 255]),
+            ellipse_outline: Outline::default(),
             ellipse_rotation: 0.0,
+            triangle_points: [[960.0, 240.0], [1260.0, 840.0], [660.0, 840.0]],
+            triangle_paint: Paint::Solid([255, 255, 0]),
+            triangle_outline: Outline::default(),
+            polygon_points: "960,140; 1060,440; 1360,440; 1110,620; 1210,940; 960,740; 710,940; 810,620; 560,440; 860,440".to_owned(),
+            polygon_fill_rule: FillRule::NonZero,
+            polygon_paint: Paint::Solid([255, 128, 0]),
+            polygon_outline: Outline::default(),
+            line_start: [0.0, 0.0],
+            line_end: [1920.0, 1080.0],
+            line_thickness: 1.0,
+            line_cap: LineCap::Butt,
+            line_paint: Paint::Solid([255, 255, 255]),
+            bezier_points: [[460.0, 740.0], [760.0, 140.0], [1160.0, 940.0], [1460.0, 340.0]],
+            bezier_cubic: true,
+            bezier_rotation: 0.0,
+            bezier_thickness: 4.0,
+            bezier_filled: false,
+            bezier_paint: Paint::Solid([0, 255, 0]),
+            bezier_outline: Outline::default(),
+            arc_center: [960.0, 540.0],
+            arc_size: [300.0, 300.0],
+            arc_angles: [0.0, 270.0],
+            arc_rotation: 0.0,
+            arc_thickness: 40.0,
+            arc_filled: false,
+            arc_paint: Paint::Solid([0, 0, 255]),
+            arc_outline: Outline::default(),
+            transform_offset: [0.0, 0.0],
+            transform_rotation: 0.0,
+            transform_scale: [1.0, 1.0],
+            transform_skew: [0.0, 0.0],
+            transform_pivot: [960.0, 540.0],
+            symmetry: None,
+            symmetry_folds: 4,
+            array_shape: ArrayShape::Rect,
+            array_kind: ArrayKind::Radial,
+            array_count: 12,
+            array_step: [100.0, 0.0],
+            array_columns: 16,
+            array_rows: 9,
+            array_spacing: [120.0, 120.0],
+            array_center: [960.0, 540.0],
+            array_angle_step: 30.0,
+            array_radius_step: 0.0,
+            array_cycle_colors: false,
+            array_colors: vec![[255, 0, 0], [0, 255, 0], [0, 0, 255]],
         }
     }
 }
@@ -81,248 +309,1618 @@ impl TestPatternGenerator {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
-        }
+        let mut app: Self = match cc.storage {
+            Some(storage) => eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default(),
+            None => Default::default(),
+        };
+
+        // keep saved values within the ranges the sliders allow
+        app.width = app.width.max(1);
+        app.height = app.height.max(1);
+        app.rounding = app.rounding.clamp(0.0, app.width.max(app.height) as f32 / 2.0);
 
-        Default::default()
+        // scopes and scripts aren't saved, so redraw and reload them
+        app.update_scopes();
+        app.reload_scripts();
+
+        app
     }
 
-    pub fn update_image_with_bmp_file(&mut self, path: &str) {
-        let bmp_from_file = image::open(path).unwrap();
+    /// Applies `operation` to the canvas and records it so the pattern can be saved and reproduced. Operations that
+    /// create a new canvas start the record over.
+    pub fn apply(&mut self, operation: Operation) {
+        let result = self.try_apply(operation);
+        self.show_result(result);
+    }
+
+    /// Shows the error from `result` above the controls, or clears the last one when it succeeded.
+    fn show_result(&mut self, result: Result<(), PatternError>) {
+        self.error = result.err().map(|err| {
+            log::error!("{err}");
+            err.to_string()
+        });
+    }
 
-        let mut bytes = Vec::new();
-        bmp_from_file
-            .write_to(&mut Cursor::new(&mut bytes), image::ImageOutputFormat::Png)
-            .unwrap();
+    /// Applies a shape, drawn through the shape transform when there is one and copied with the symmetry mode.
+    fn apply_shape(&mut self, operation: Operation) {
+        let transform = self.shape_transform();
+        let operation = match transform.is_identity() {
+            true => operation,
+            false => Operation::Group {
+                transform,
+                operations: vec![operation],
+            },
+        };
 
-        self.image = RetainedImage::from_image_bytes("image.png", bytes.as_slice()).unwrap();
+        match self.symmetry {
+            Some(symmetry) => self.apply(Operation::Symmetric {
+                symmetry,
+                operation: Box::new(operation),
+            }),
+            None => self.apply(operation),
+        }
     }
 
-    pub fn update_image_with_bmp(&mut self) {
+    /// Scales, skews and turns about the pivot, then moves by the offset.
+    fn shape_transform(&self) -> Transform {
+        Transform::scale(self.transform_scale[0], self.transform_scale[1])
+            .then(Transform::skew(self.transform_skew[0], self.transform_skew[1]))
+            .then(Transform::rotate(self.transform_rotation))
+            .around(self.transform_pivot)
+            .then(Transform::translate(self.transform_offset[0], self.transform_offset[1]))
+    }
+
+    /// Draws everything since the canvas was created again as one group through the shape transform, so it all
+    /// moves and turns together.
+    pub fn transform_drawn_shapes(&mut self) {
+        let Some((canvas, shapes)) = self.operations.split_first() else {
+            return;
+        };
+        if shapes.is_empty() {
+            return;
+        }
+
+        let pattern = PatternDescription {
+            operations: vec![
+                canvas.clone(),
+                Operation::Group {
+                    transform: self.shape_transform(),
+                    operations: shapes.to_vec(),
+                },
+            ],
+            ..self.pattern_description()
+        };
+        let result = pattern.render().map(|bmp| self.restore_pattern(pattern, bmp));
+        self.show_result(result);
+    }
+
+    /// Like [`Self::apply`], but leaves reporting a failure to the caller.
+    fn try_apply(&mut self, operation: Operation) -> Result<(), PatternError> {
+        operation.apply(self.bmp.as_mut().unwrap())?;
+
+        if operation.creates_canvas() {
+            let bmp = self.bmp.as_ref().unwrap();
+            self.width = bmp.get_width();
+            self.height = bmp.get_height();
+            self.operations.clear();
+        }
+        self.operations.push(operation);
+
         self.update_image();
+        Ok(())
     }
 
-    pub fn update_image_with_bmp_stripes(&mut self) {
-        match self.horizontal_stripes {
-            true => {
-                self.bmp = Some(
-                    bmp_generator::bmp_generator::BmpGenerator::generate_stripes_horizontal(
-                        self.width,
-                        self.height,
-                        self.stripe_spacing,
-                        self.num_stripe_colors,
-                    ),
-                );
-            }
-            false => {
-                self.bmp = Some(
-                    bmp_generator::bmp_generator::BmpGenerator::generate_stripes(
-                        self.width,
-                        self.height,
-                        self.stripe_spacing,
-                        self.num_stripe_colors,
-                    ),
-                );
+    /// Everything drawn on the canvas since it was created.
+    pub fn pattern_description(&self) -> PatternDescription {
+        let bmp = self.bmp.as_ref().unwrap();
+        PatternDescription {
+            width: bmp.get_width(),
+            height: bmp.get_height(),
+            operations: self.operations.clone(),
+        }
+    }
+
+    /// Replaces the canvas with the image at `path`, resizing the canvas to match it. Images saved with pattern
+    /// metadata are redrawn from it instead, so the pattern can be edited further.
+    pub fn update_image_with_bmp_file(&mut self, path: &str) {
+        let mut refused = None;
+        if let Some(metadata) = metadata::read_metadata(path) {
+            // the file may have come from anywhere, so its pattern isn't allowed to run scripts or read files
+            match metadata.pattern.check_untrusted().and_then(|()| metadata.pattern.render()) {
+                Ok(bmp) => {
+                    self.restore_pattern(metadata.pattern, bmp);
+                    return;
+                }
+                Err(err) => {
+                    log::warn!("Failed to restore the pattern saved in {path}, opening it as an image: {err}");
+                    refused = Some(err);
+                }
             }
         }
 
+        self.apply(Operation::OpenImage { path: path.to_owned() });
+        if let (None, Some(err)) = (&self.error, refused) {
+            self.error = Some(format!("Opened as a plain image, the saved pattern wasn't restored: {err}"));
+        }
+    }
+
+    fn restore_pattern(&mut self, pattern: PatternDescription, bmp: Image) {
+        self.operations = pattern.operations;
+        if !self.operations.first().map_or(false, Operation::creates_canvas) {
+            let background = Operation::Background {
+                width: pattern.width,
+                height: pattern.height,
+                paint: Paint::Solid([0, 0, 0]),
+            };
+            self.operations.insert(0, background);
+        }
+
+        self.width = bmp.get_width();
+        self.height = bmp.get_height();
+        self.bmp = Some(bmp);
+
         self.update_image();
     }
 
-    pub fn add_rect(&mut self) {
-        let color = [
-            self.rect_color.r(),
-            self.rect_color.g(),
-            self.rect_color.b(),
+    /// Resizes the canvas to `width` by `height`, redrawing the pattern with every shape moved and stretched to
+    /// keep its place relative to the edges, and moves the shape controls to match.
+    pub fn resize_canvas(&mut self, width: u32, height: u32) {
+        let pattern = self.pattern_description();
+        let scale = [
+            width as f64 / pattern.width.max(1) as f64,
+            height as f64 / pattern.height.max(1) as f64,
         ];
-        self.bmp = Some(bmp_generator::bmp_generator::BmpGenerator::add_rect(
-            &mut self.bmp.as_mut().unwrap(),
-            self.rect_start,
-            self.rect_end,
-            color,
-            self.rect_rotation,
+        let resized = pattern.resized(width, height).and_then(|pattern| {
+            let bmp = pattern.render()?;
+            Ok((pattern, bmp))
+        });
+
+        match resized {
+            Ok((pattern, bmp)) => {
+                self.remap_controls(scale);
+                self.restore_pattern(pattern, bmp);
+                self.error = None;
+            }
+            Err(err) => {
+                (self.width, self.height) = (width, height);
+                self.show_result(Err(err));
+            }
+        }
+    }
+
+    /// Scales the positions, sizes, corner radii and paint points in the shape controls by `scale`.
+    fn remap_controls(&mut self, scale: [f64; 2]) {
+        for point in [
+            &mut self.layer_position,
+            &mut self.transform_offset,
+            &mut self.transform_pivot,
+            &mut self.array_step,
+            &mut self.array_spacing,
+            &mut self.array_center,
+            &mut self.rect_start,
+            &mut self.rect_end,
+            &mut self.ellipse_center,
+            &mut self.ellipse_size,
+            &mut self.line_start,
+            &mut self.line_end,
+            &mut self.arc_center,
+            &mut self.arc_size,
+        ]
+        .into_iter()
+        .chain(&mut self.triangle_points)
+        .chain(&mut self.bezier_points)
+        {
+            *point = remap(*point, scale);
+        }
+        self.array_radius_step *= scale[1];
+
+        // corners stay round, so their radii follow the smaller scale, the same as rects in the pattern
+        let radius_scale = scale[0].min(scale[1]) as f32;
+        self.rounding *= radius_scale;
+        self.rect_corner_radii = self.rect_corner_radii.map(|radius| radius * radius_scale);
+
+        for paint in [
+            &mut self.background,
+            &mut self.rect_paint,
+            &mut self.ellipse_paint,
+            &mut self.triangle_paint,
+            &mut self.polygon_paint,
+            &mut self.line_paint,
+            &mut self.bezier_paint,
+            &mut self.arc_paint,
+        ] {
+            *paint = paint.remapped(scale);
+        }
+
+        let polygon_points: Vec<String> = bmp_generator::bmp_generator::parse_points(&self.polygon_points)
+            .into_iter()
+            .map(|point| {
+                let [x, y] = remap(point, scale).map(|c| (c * 100.0).round() / 100.0);
+                format!("{x},{y}")
+            })
+            .collect();
+        self.polygon_points = polygon_points.join("; ");
+    }
+
+    fn resolution_ui(&mut self, ui: &mut egui::Ui) {
+        let mut picked = None;
+        egui::ComboBox::from_label("Preset")
+            .selected_text(format!("{} × {}", self.width, self.height))
+            .show_ui(ui, |ui| {
+                for (group, sizes) in PRESETS {
+                    ui.strong(*group);
+                    for &(name, width, height) in *sizes {
+                        let selected = [self.width, self.height] == [width, height];
+                        if ui.selectable_label(selected, format!("{name}  {width} × {height}")).clicked() {
+                            picked = Some((width, height));
+                        }
+                    }
+                    ui.separator();
+                }
+
+                ui.strong("Saved");
+                for resolution in &self.user_resolutions {
+                    let selected = [self.width, self.height] == [resolution.width, resolution.height];
+                    let text = format!("{}  {} × {}", resolution.name, resolution.width, resolution.height);
+                    if ui.selectable_label(selected, text).clicked() {
+                        picked = Some((resolution.width, resolution.height));
+                    }
+                }
+            });
+        if let Some((width, height)) = picked {
+            self.resize_canvas(width, height);
+        }
+
+        ui.add(egui::Slider::new(&mut self.width, 1..=3840 * 2).text("Width"));
+        ui.add(egui::Slider::new(&mut self.height, 1..=2160 * 2).text("Height"));
+
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.resolution_name);
+            let name = self.resolution_name.trim().to_owned();
+            if ui.add_enabled(!name.is_empty(), egui::Button::new("Save preset")).clicked() {
+                self.user_resolutions.retain(|resolution| resolution.name != name);
+                self.user_resolutions.push(Resolution::new(name.clone(), self.width, self.height));
+            }
+            let saved = self.user_resolutions.iter().any(|resolution| resolution.name == name);
+            if ui.add_enabled(saved, egui::Button::new("Delete preset")).clicked() {
+                self.user_resolutions.retain(|resolution| resolution.name != name);
+            }
+        });
+    }
+
+    /// Reads the display EDID at `edid_path`, pre-filling the canvas size with the display's native resolution and
+    /// the Gamut generator with its primaries and gamma.
+    pub fn load_edid(&mut self) {
+        let edid = match Edid::read(&self.edid_path) {
+            Ok(edid) => edid,
+            Err(err) => {
+                self.show_result(Err(err.into()));
+                return;
+            }
+        };
+
+        if let Some([width, height]) = edid.native_resolution() {
+            (self.width, self.height) = (width, height);
+        }
+
+        let chromaticity = edid.chromaticity;
+        let values = self.generator_values.entry("Gamut".to_owned()).or_default();
+        for (name, value) in [
+            ("red_x", chromaticity.red[0]),
+            ("red_y", chromaticity.red[1]),
+            ("green_x", chromaticity.green[0]),
+            ("green_y", chromaticity.green[1]),
+            ("blue_x", chromaticity.blue[0]),
+            ("blue_y", chromaticity.blue[1]),
+            ("white_x", chromaticity.white[0]),
+            ("white_y", chromaticity.white[1]),
+        ]
+        .into_iter()
+        .chain(edid.gamma.map(|gamma| ("display_gamma", gamma)))
+        {
+            values.insert(name.to_owned(), ParameterValue::Float(value));
+        }
+
+        self.edid = Some(edid);
+        self.error = None;
+    }
+
+    fn edid_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("EDID:");
+            ui.text_edit_singleline(&mut self.edid_path);
+            if ui.button("Load").clicked() {
+                self.load_edid();
+            }
+        });
+
+        let Some(edid) = &self.edid else {
+            return;
+        };
+
+        let name = edid.name.as_deref().unwrap_or("Unnamed display");
+        ui.label(format!(
+            "{name} ({} {:04X}, {}), EDID {}.{}",
+            edid.manufacturer, edid.product_code, edid.year, edid.version[0], edid.version[1]
         ));
+        if let Some(timing) = edid.timings.first() {
+            let scan = if timing.interlaced { "i" } else { "p" };
+            ui.label(format!("Native {} × {}{scan} at {:.2} Hz", timing.width, timing.height, timing.refresh_rate));
+        }
+
+        let c = edid.chromaticity;
+        ui.label(format!(
+            "Red {:.4}, {:.4}  Green {:.4}, {:.4}  Blue {:.4}, {:.4}  White {:.4}, {:.4}",
+            c.red[0], c.red[1], c.green[0], c.green[1], c.blue[0], c.blue[1], c.white[0], c.white[1]
+        ));
+        match edid.gamma {
+            Some(gamma) => ui.label(format!("Gamma {gamma:.2}")),
+            None => ui.label("Gamma not given"),
+        };
+        if !edid.colorimetry.is_empty() {
+            ui.label(format!("Colorimetry {:?}", edid.colorimetry));
+        }
+        if let Some(hdr) = &edid.hdr {
+            let luminance = |value: Option<f64>| value.map_or("?".to_owned(), |value| format!("{value:.4}"));
+            ui.label(format!(
+                "HDR {:?}, max {} cd/m², frame average {}, min {}",
+                hdr.eotfs,
+                luminance(hdr.max_luminance),
+                luminance(hdr.max_frame_average_luminance),
+                luminance(hdr.min_luminance)
+            ));
+        }
+    }
 
+    /// Draws the image at `path` on top of the canvas at `layer_position`, scaled by `layer_scale`.
+    pub fn add_image_layer(&mut self, path: &str) {
+        self.apply_shape(Operation::Image {
+            path: path.to_owned(),
+            position: self.layer_position,
+            scale: self.layer_scale,
+        });
+    }
+
+    pub fn update_image_with_bmp(&mut self) {
         self.update_image();
     }
 
+    /// Reloads the scripts in `script_folder` into the generators, showing any that fail to load.
+    pub fn reload_scripts(&mut self) {
+        let mut errors = vec![];
+        self.generators = Registry::builtin();
+        for script in script::load_folder(&self.script_folder) {
+            match script {
+                Ok(script) => self.generators.register(Box::new(script)),
+                Err(err) => errors.push(err.to_string()),
+            }
+        }
+
+        self.generator_error = (!errors.is_empty()).then(|| errors.join("\n"));
+        if self.generators.get(&self.selected_generator).is_none() {
+            self.selected_generator = self.generators.iter().next().map(|g| g.name().to_owned()).unwrap_or_default();
+        }
+    }
+
+    /// Renders the selected generator with the parameter values from the UI.
+    pub fn generate(&mut self) {
+        let Some(generator) = self.generators.get(&self.selected_generator) else {
+            return;
+        };
+
+        let values = self.generator_values.get(generator.name()).cloned().unwrap_or_default();
+        let operation = generator.operation(self.width, self.height, &values);
+        self.generator_error = self.try_apply(operation).err().map(|err| err.to_string());
+    }
+
+    pub fn add_rect(&mut self) {
+        self.apply_shape(self.rect_operation());
+    }
+
+    fn rect_operation(&self) -> Operation {
+        Operation::Rect {
+            start: self.rect_start,
+            end: self.rect_end,
+            paint: self.rect_paint.clone(),
+            rotation: self.rect_rotation,
+            corner_radii: self.corner_radii(),
+            outline: self.rect_outline,
+        }
+    }
+
+    /// Corner radii for the rect, either the shared `rounding` or the individual per-corner values.
+    fn corner_radii(&self) -> [f64; 4] {
+        match self.rect_per_corner_rounding {
+            true => self.rect_corner_radii.map(|r| r as f64),
+            false => [self.rounding as f64; 4],
+        }
+    }
+
     pub fn add_ellipse(&mut self) {
-        let color = [
-            self.ellipse_color.r(),
-            self.ellipse_color.g(),
-            self.ellipse_color.b(),
-        ];
-        self.bmp = Some(bmp_generator::bmp_generator::BmpGenerator::add_ellipse(
-            &mut self.bmp.as_mut().unwrap(),
-            self.ellipse_center.map(|c| { c as i32 }),
-            self.ellipse_size.map(|s| { s as i32 }),
-            self.ellipse_rotation,
-            color,
-        ));
+        self.apply_shape(self.ellipse_operation());
+    }
 
-        self.update_image();
+    fn ellipse_operation(&self) -> Operation {
+        Operation::Ellipse {
+            center: self.ellipse_center,
+            size: self.ellipse_size,
+            rotation: self.ellipse_rotation,
+            paint: self.ellipse_paint.clone(),
+            outline: self.ellipse_outline,
+        }
+    }
+
+    pub fn add_triangle(&mut self) {
+        self.apply_shape(self.triangle_operation());
+    }
+
+    fn triangle_operation(&self) -> Operation {
+        Operation::Triangle {
+            points: self.triangle_points,
+            paint: self.triangle_paint.clone(),
+            outline: self.triangle_outline,
+        }
+    }
+
+    pub fn add_polygon(&mut self) {
+        self.apply_shape(self.polygon_operation());
+    }
+
+    fn polygon_operation(&self) -> Operation {
+        Operation::Polygon {
+            points: bmp_generator::bmp_generator::parse_points(&self.polygon_points),
+            fill_rule: self.polygon_fill_rule,
+            paint: self.polygon_paint.clone(),
+            outline: self.polygon_outline,
+        }
+    }
+
+    pub fn add_line(&mut self) {
+        self.apply_shape(self.line_operation());
+    }
+
+    fn line_operation(&self) -> Operation {
+        Operation::Line {
+            start: self.line_start,
+            end: self.line_end,
+            thickness: self.line_thickness,
+            cap: self.line_cap,
+            paint: self.line_paint.clone(),
+        }
+    }
+
+    pub fn add_bezier(&mut self) {
+        self.apply_shape(self.bezier_operation());
+    }
+
+    fn bezier_operation(&self) -> Operation {
+        let control_points = match self.bezier_cubic {
+            true => &self.bezier_points[..],
+            false => &self.bezier_points[..3],
+        };
+        Operation::Bezier {
+            control_points: control_points.to_vec(),
+            rotation: self.bezier_rotation,
+            thickness: self.bezier_thickness,
+            filled: self.bezier_filled,
+            paint: self.bezier_paint.clone(),
+            outline: self.bezier_outline,
+        }
+    }
+
+    pub fn add_arc(&mut self) {
+        self.apply_shape(self.arc_operation());
+    }
+
+    fn arc_operation(&self) -> Operation {
+        Operation::Arc {
+            center: self.arc_center,
+            radii: self.arc_size,
+            angles: self.arc_angles,
+            rotation: self.arc_rotation,
+            thickness: self.arc_thickness,
+            filled: self.arc_filled,
+            paint: self.arc_paint.clone(),
+            outline: self.arc_outline,
+        }
+    }
+
+    /// Draws the selected shape once for every place in the array, through the shape transform.
+    pub fn add_array(&mut self) {
+        let operation = match self.array_shape {
+            ArrayShape::Rect => self.rect_operation(),
+            ArrayShape::Ellipse => self.ellipse_operation(),
+            ArrayShape::Triangle => self.triangle_operation(),
+            ArrayShape::Polygon => self.polygon_operation(),
+            ArrayShape::Line => self.line_operation(),
+            ArrayShape::Bezier => self.bezier_operation(),
+            ArrayShape::Arc => self.arc_operation(),
+        };
+        let arrangement = match self.array_kind {
+            ArrayKind::Linear => Arrangement::Linear {
+                count: self.array_count,
+                step: self.array_step,
+            },
+            ArrayKind::Grid => Arrangement::Grid {
+                columns: self.array_columns,
+                rows: self.array_rows,
+                spacing: self.array_spacing,
+            },
+            ArrayKind::Radial => Arrangement::Radial {
+                count: self.array_count,
+                center: self.array_center,
+                angle_step: self.array_angle_step,
+                radius_step: self.array_radius_step,
+            },
+        };
+        let colors = match self.array_cycle_colors {
+            true => self.array_colors.clone(),
+            false => Vec::new(),
+        };
+
+        self.apply_shape(Operation::Array {
+            arrangement,
+            colors,
+            operation: Box::new(operation),
+        });
+    }
+
+    fn array_ui(&mut self, ui: &mut egui::Ui) {
+        egui::ComboBox::from_label("Array shape")
+            .selected_text(format!("{:?}", self.array_shape))
+            .show_ui(ui, |ui| {
+                for shape in ArrayShape::ALL {
+                    ui.selectable_value(&mut self.array_shape, shape, format!("{shape:?}"));
+                }
+            });
+
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.array_kind, ArrayKind::Linear, "Linear");
+            ui.selectable_value(&mut self.array_kind, ArrayKind::Grid, "Grid");
+            ui.selectable_value(&mut self.array_kind, ArrayKind::Radial, "Radial");
+        });
+
+        match self.array_kind {
+            ArrayKind::Linear => {
+                ui.add(egui::Slider::new(&mut self.array_count, 1..=256).text("Copies"));
+                coordinate_slider(ui, &mut self.array_step[0], self.width, "Step X");
+                coordinate_slider(ui, &mut self.array_step[1], self.height, "Step Y");
+            }
+            ArrayKind::Grid => {
+                ui.add(egui::Slider::new(&mut self.array_columns, 1..=256).text("Columns"));
+                ui.add(egui::Slider::new(&mut self.array_rows, 1..=256).text("Rows"));
+                ui.add(egui::Slider::new(&mut self.array_spacing[0], 0.0..=self.width as f64).text("Spacing X"));
+                ui.add(egui::Slider::new(&mut self.array_spacing[1], 0.0..=self.height as f64).text("Spacing Y"));
+            }
+            ArrayKind::Radial => {
+                ui.add(egui::Slider::new(&mut self.array_count, 1..=360).text("Copies"));
+                coordinate_slider(ui, &mut self.array_center[0], self.width, "Array center X");
+                coordinate_slider(ui, &mut self.array_center[1], self.height, "Array center Y");
+                ui.add(egui::Slider::new(&mut self.array_angle_step, -180.0..=180.0).text("Angle step"));
+                let max_step = self.width.max(self.height) as f64 / 4.0;
+                ui.add(egui::Slider::new(&mut self.array_radius_step, -max_step..=max_step).text("Radius step"));
+            }
+        }
+
+        ui.add(egui::Checkbox::new(&mut self.array_cycle_colors, "Cycle colors"));
+        if self.array_cycle_colors {
+            ui.horizontal_wrapped(|ui| {
+                let removable = self.array_colors.len() > 1;
+                let mut removed = None;
+                for (i, color) in self.array_colors.iter_mut().enumerate() {
+                    ui.color_edit_button_srgb(color);
+                    if removable && ui.small_button("x").clicked() {
+                        removed = Some(i);
+                    }
+                }
+                if let Some(i) = removed {
+                    self.array_colors.remove(i);
+                }
+                if ui.button("+").clicked() {
+                    self.array_colors.push([255, 255, 255]);
+                }
+            });
+        }
+
+        ui.add_space(5.0);
+
+        if ui.button("Generate array").clicked() {
+            self.add_array();
+        }
     }
 
     pub fn update_image(&mut self) {
-        //let bytes = &self.bmp.as_ref().unwrap().contents;
-        let mut bytes: Vec<u8> = vec![];
-        let _ = self.bmp.as_ref().unwrap().to_writer(&mut bytes);
+        let bmp = self.bmp.take().unwrap();
+        self.show_in_preview(&bmp);
+        self.bmp = Some(bmp);
+
+        self.update_scopes();
+    }
+
+    /// Shows `bmp` in the preview without changing the canvas. If it can't be shown, the last preview stays
+    /// with the reason above it.
+    fn show_in_preview(&mut self, bmp: &Image) {
+        match to_retained_image("image.png", bmp) {
+            Ok(image) => {
+                self.image = image;
+                self.preview = Some(bmp.clone());
+                self.preview_error = None;
+            }
+            Err(err) => {
+                log::error!("Failed to show the preview: {err}");
+                self.preview_error = Some(format!("Can't show the preview: {err}"));
+            }
+        }
+    }
+
+    /// Recomputes the scopes from the canvas, if they are open.
+    pub fn update_scopes(&mut self) {
+        let Some(bmp) = self.bmp.as_ref().filter(|_| self.show_scopes) else {
+            self.scope_images.clear();
+            return;
+        };
+
+        let histogram = scopes::histogram(bmp, self.scope_coefficients);
+
+        let scope_images = [
+            ("Histogram", to_retained_image("histogram.png", &histogram.render(256))),
+            ("Waveform", to_retained_image("waveform.png", &scopes::waveform(bmp, self.scope_coefficients, 512))),
+            ("RGB parade", to_retained_image("parade.png", &scopes::parade(bmp, 768))),
+            ("Vectorscope", to_retained_image("vectorscope.png", &scopes::vectorscope(bmp, self.scope_coefficients, 256))),
+        ];
+        self.scope_images = scope_images
+            .into_iter()
+            .filter_map(|(name, image)| match image {
+                Ok(image) => Some((name, image)),
+                Err(err) => {
+                    log::error!("Failed to show the {name} scope: {err}");
+                    None
+                }
+            })
+            .collect();
+    }
+
+    fn scopes_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let mut changed = ui.radio_value(&mut self.scope_coefficients, LumaCoefficients::Bt709, "BT.709").changed();
+            changed |= ui.radio_value(&mut self.scope_coefficients, LumaCoefficients::Bt601, "BT.601").changed();
+            ui.add(egui::Checkbox::new(&mut self.scopes_docked, "Docked"));
+
+            if changed {
+                self.update_scopes();
+            }
+        });
+
+        egui::ScrollArea::horizontal().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                for (name, image) in &self.scope_images {
+                    ui.vertical(|ui| {
+                        ui.label(*name);
+                        image.show_max_size(ui, egui::vec2(f32::INFINITY, 256.0));
+                    });
+                }
+            });
+        });
+    }
+
+    /// Describes the preview pixel at `pixel` as its coordinate, RGB and hex value.
+    fn describe_pixel(&self, pixel: [u32; 2]) -> Option<String> {
+        let preview = self.preview.as_ref()?;
+        if pixel[0] >= preview.get_width() || pixel[1] >= preview.get_height() {
+            return None;
+        }
+
+        let value = preview.get_pixel(pixel[0], pixel[1]);
+        Some(format!("{}, {}: {} #{:X}", pixel[0], pixel[1], value, value))
+    }
+
+    /// Draws pixel boundaries over the visible part of the preview once pixels are big enough to tell apart,
+    /// and a marker around each pinned probe.
+    fn paint_preview_overlay(&self, ui: &egui::Ui, rect: egui::Rect) {
+        let painter = ui.painter_at(rect);
+        let visible = rect.intersect(ui.clip_rect());
+
+        if self.scale >= 8.0 && visible.is_positive() {
+            let stroke = egui::Stroke::new(1.0, egui::Color32::from_gray(128).gamma_multiply(0.5));
+
+            let first_x = ((visible.min.x - rect.min.x) / self.scale).floor() as i32;
+            let last_x = ((visible.max.x - rect.min.x) / self.scale).ceil() as i32;
+            for x in first_x..=last_x {
+                let screen_x = rect.min.x + x as f32 * self.scale;
+                painter.vline(screen_x, visible.y_range(), stroke);
+            }
+
+            let first_y = ((visible.min.y - rect.min.y) / self.scale).floor() as i32;
+            let last_y = ((visible.max.y - rect.min.y) / self.scale).ceil() as i32;
+            for y in first_y..=last_y {
+                let screen_y = rect.min.y + y as f32 * self.scale;
+                painter.hline(visible.x_range(), screen_y, stroke);
+            }
+        }
+
+        for probe in &self.probes {
+            let min = rect.min + egui::vec2(probe[0] as f32, probe[1] as f32) * self.scale;
+            let pixel = egui::Rect::from_min_size(min, egui::Vec2::splat(self.scale));
+            painter.rect_stroke(pixel.expand(2.0), 0.0, egui::Stroke::new(1.0, egui::Color32::WHITE));
+            painter.rect_stroke(pixel.expand(3.0), 0.0, egui::Stroke::new(1.0, egui::Color32::BLACK));
+        }
+    }
+
+    fn compare_region(&self, captured: &Image) -> Region {
+        let crop = self.compare_crop_enabled.then_some(self.compare_crop);
+        Region::new(self.bmp.as_ref().unwrap(), captured, self.compare_offset, crop)
+    }
+
+    /// Compares the canvas against the captured frame at `compare_path`.
+    pub fn compare_with_file(&mut self) {
+        let captured = match bmp_generator::bmp_generator::BmpGenerator::open_image(&self.compare_path) {
+            Ok(captured) => captured,
+            Err(err) => {
+                self.show_result(Err(err));
+                return;
+            }
+        };
+
+        let crop = self.compare_crop_enabled.then_some(self.compare_crop);
+        if self.compare_align > 0 {
+            self.compare_offset = compare::find_alignment(self.bmp.as_ref().unwrap(), &captured, crop, self.compare_align);
+        }
+
+        if let Err(message) = compare::check_region(self.bmp.as_ref().unwrap(), &captured, self.compare_offset, crop) {
+            self.comparison = None;
+            self.show_result(Err(PatternError::invalid_parameter("region", message)));
+            return;
+        }
+
+        let region = self.compare_region(&captured);
+        self.comparison = Some(compare::compare(self.bmp.as_ref().unwrap(), &captured, &region));
+        self.captured = Some(captured);
+    }
+
+    /// Shows the difference between the canvas and the last compared frame in the preview, either as the
+    /// amplified per-channel difference or as a heatmap.
+    pub fn show_difference(&mut self, heatmap: bool) {
+        let Some(captured) = self.captured.take() else {
+            return;
+        };
+
+        let region = self.compare_region(&captured);
+        let bmp = self.bmp.as_ref().unwrap();
+        let difference = match heatmap {
+            true => compare::heatmap(bmp, &captured, &region, 255),
+            false => compare::diff_image(bmp, &captured, &region, self.diff_gain),
+        };
 
-        self.image = RetainedImage::from_image_bytes("image.png", bytes.as_slice())
-            .unwrap()
-            .with_options(egui::TextureOptions::NEAREST)
+        self.show_in_preview(&difference);
+        self.captured = Some(captured);
     }
 
-    pub fn save_image(&self, path: &str) {
-        let bmp = self.bmp.clone().unwrap();
-        bmp.save(path).unwrap();
+    /// Saves the canvas to `path`, embedding the pattern description when the format supports it.
+    pub fn save_image(&mut self, path: &str) {
+        let metadata = Metadata::new(self.pattern_description());
+        let result = metadata::save_with_metadata(self.bmp.as_ref().unwrap(), &metadata, path);
+        self.show_result(result);
     }
 }
 
+/// A slider for a position along an axis `extent` pixels long. It reaches a whole `extent` past either edge so
+/// shapes can hang off the canvas, and takes fractional values for sub-pixel positions.
+fn coordinate_slider(ui: &mut egui::Ui, value: &mut f64, extent: u32, text: impl Into<egui::WidgetText>) {
+    let extent = extent as f64;
+    ui.add(egui::Slider::new(value, -extent..=extent * 2.0).text(text));
+}
+
+fn color_controls(ui: &mut egui::Ui, color: &mut egui::Color32) {
+    ui.add(
+        egui::Slider::new(&mut color[0], 0..=255)
+            .text("Red")
+            .text_color(egui::Color32::from_rgb(255, 0, 0)),
+    );
+    ui.add(
+        egui::Slider::new(&mut color[1], 0..=255)
+            .text("Green")
+            .text_color(egui::Color32::from_rgb(0, 255, 0)),
+    );
+    ui.add(
+        egui::Slider::new(&mut color[2], 0..=255)
+            .text("Blue")
+            .text_color(egui::Color32::from_rgb(0, 0, 255)),
+    );
+    egui::color_picker::color_edit_button_srgba(ui, color, egui::color_picker::Alpha::Opaque);
+}
+
+fn paint_controls(ui: &mut egui::Ui, paint: &mut Paint, width: u32, height: u32) {
+    ui.push_id(paint as *const Paint, |ui| {
+        let kind = |paint: &Paint| match paint {
+            Paint::Solid(_) => 0,
+            Paint::Gradient(_) => 1,
+            Paint::Pattern(_) => 2,
+        };
+
+        let mut selected = kind(paint);
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut selected, 0, "Solid");
+            ui.radio_value(&mut selected, 1, "Gradient");
+            ui.radio_value(&mut selected, 2, "Pattern");
+        });
+
+        // switching keeps the current color as the solid color, first stop or pattern foreground
+        if selected != kind(paint) {
+            let color = match &paint {
+                Paint::Solid(color) => *color,
+                Paint::Gradient(gradient) => gradient.stops.first().map_or([0, 0, 0], |s| s.color),
+                Paint::Pattern(pattern) => pattern.foreground,
+            };
+
+            *paint = match selected {
+                0 => Paint::Solid(color),
+                1 => Paint::Gradient(Gradient {
+                    kind: GradientKind::Linear,
+                    start: [0.0, 0.0],
+                    end: [width as f64, 0.0],
+                    stops: vec![
+                        GradientStop { position: 0.0, color },
+                        GradientStop { position: 1.0, color: [255, 255, 255] },
+                    ],
+                    interpolation: Interpolation::CodeValues,
+                }),
+                _ => Paint::Pattern(Pattern {
+                    kind: PatternKind::Checkerboard,
+                    origin: [0.0, 0.0],
+                    spacing: 16,
+                    num_colors: 8,
+                    foreground: color,
+                    background: [0, 0, 0],
+                }),
+            };
+        }
+
+        match paint {
+            Paint::Solid(color) => {
+                let mut color32 = egui::Color32::from_rgb(color[0], color[1], color[2]);
+                color_controls(ui, &mut color32);
+                *color = [color32.r(), color32.g(), color32.b()];
+            }
+            Paint::Gradient(gradient) => gradient_controls(ui, gradient, width, height),
+            Paint::Pattern(pattern) => pattern_controls(ui, pattern, width, height),
+        }
+    });
+}
+
+fn gradient_controls(ui: &mut egui::Ui, gradient: &mut Gradient, width: u32, height: u32) {
+    egui::ComboBox::from_label("Gradient")
+        .selected_text(format!("{:?}", gradient.kind))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut gradient.kind, GradientKind::Linear, "Linear");
+            ui.selectable_value(&mut gradient.kind, GradientKind::Radial, "Radial");
+            ui.selectable_value(&mut gradient.kind, GradientKind::Conic, "Conic");
+        });
+
+    egui::ComboBox::from_label("Interpolation")
+        .selected_text(format!("{:?}", gradient.interpolation))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut gradient.interpolation, Interpolation::CodeValues, "CodeValues");
+            ui.selectable_value(&mut gradient.interpolation, Interpolation::LinearLight, "LinearLight");
+        });
+
+    ui.add(egui::Slider::new(&mut gradient.start[0], 0.0..=width as f64).text("Gradient start X"));
+    ui.add(egui::Slider::new(&mut gradient.start[1], 0.0..=height as f64).text("Gradient start Y"));
+    ui.add(egui::Slider::new(&mut gradient.end[0], 0.0..=width as f64).text("Gradient end X"));
+    ui.add(egui::Slider::new(&mut gradient.end[1], 0.0..=height as f64).text("Gradient end Y"));
+
+    let mut remove = None;
+    let can_remove = gradient.stops.len() > 2;
+
+    for (i, stop) in gradient.stops.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.add(egui::Slider::new(&mut stop.position, 0.0..=1.0).text("Stop"));
+
+            let mut color32 = egui::Color32::from_rgb(stop.color[0], stop.color[1], stop.color[2]);
+            egui::color_picker::color_edit_button_srgba(ui, &mut color32, egui::color_picker::Alpha::Opaque);
+            stop.color = [color32.r(), color32.g(), color32.b()];
+
+            if can_remove && ui.button("Remove").clicked() {
+                remove = Some(i);
+            }
+        });
+    }
+
+    if let Some(i) = remove {
+        gradient.stops.remove(i);
+    }
+
+    if ui.button("Add stop").clicked() {
+        let color = gradient.stops.last().map_or([255, 255, 255], |s| s.color);
+        gradient.stops.push(GradientStop { position: 1.0, color });
+    }
+}
+
+fn pattern_controls(ui: &mut egui::Ui, pattern: &mut Pattern, width: u32, height: u32) {
+    egui::ComboBox::from_label("Pattern")
+        .selected_text(format!("{:?}", pattern.kind))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut pattern.kind, PatternKind::VerticalStripes, "VerticalStripes");
+            ui.selectable_value(&mut pattern.kind, PatternKind::HorizontalStripes, "HorizontalStripes");
+            ui.selectable_value(&mut pattern.kind, PatternKind::Checkerboard, "Checkerboard");
+            ui.selectable_value(&mut pattern.kind, PatternKind::Crosshatch, "Crosshatch");
+            ui.selectable_value(&mut pattern.kind, PatternKind::Dots, "Dots");
+        });
+
+    ui.add(egui::Slider::new(&mut pattern.origin[0], 0.0..=width as f64).step_by(1.0).text("Pattern origin X"));
+    ui.add(egui::Slider::new(&mut pattern.origin[1], 0.0..=height as f64).step_by(1.0).text("Pattern origin Y"));
+    ui.add(egui::Slider::new(&mut pattern.spacing, 1..=512).text("Pattern spacing"));
+
+    match pattern.kind {
+        PatternKind::VerticalStripes | PatternKind::HorizontalStripes => {
+            ui.add(egui::Slider::new(&mut pattern.num_colors, 1..=8).text("Num Colors"));
+        }
+        _ => {
+            for (color, name) in [(&mut pattern.foreground, "Foreground"), (&mut pattern.background, "Background")] {
+                ui.horizontal(|ui| {
+                    let mut color32 = egui::Color32::from_rgb(color[0], color[1], color[2]);
+                    egui::color_picker::color_edit_button_srgba(ui, &mut color32, egui::color_picker::Alpha::Opaque);
+                    *color = [color32.r(), color32.g(), color32.b()];
+                    ui.label(name);
+                });
+            }
+        }
+    }
+}
+
+/// A widget for a generator parameter, chosen by its type.
+fn parameter_controls(ui: &mut egui::Ui, parameter: &Parameter, value: &mut ParameterValue) {
+    match (&parameter.kind, value) {
+        (ParameterKind::Int { min, max }, ParameterValue::Int(value)) => {
+            ui.add(egui::Slider::new(value, *min..=*max).text(&parameter.label));
+        }
+        (ParameterKind::Float { min, max }, ParameterValue::Float(value)) => {
+            ui.add(egui::Slider::new(value, *min..=*max).text(&parameter.label));
+        }
+        (ParameterKind::Bool, ParameterValue::Bool(value)) => {
+            ui.add(egui::Checkbox::new(value, &parameter.label));
+        }
+        (ParameterKind::Color, ParameterValue::Color(color)) => {
+            ui.horizontal(|ui| {
+                ui.color_edit_button_srgb(color);
+                ui.label(&parameter.label);
+            });
+        }
+        (ParameterKind::Text, ParameterValue::Text(text)) => {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(text);
+                ui.label(&parameter.label);
+            });
+        }
+        _ => {}
+    }
+}
+
+fn outline_controls(ui: &mut egui::Ui, outline: &mut Outline) {
+    ui.push_id(outline as *const Outline, |ui| {
+        egui::ComboBox::from_label("Paint")
+            .selected_text(format!("{:?}", outline.mode))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut outline.mode, PaintMode::Fill, "Fill");
+                ui.selectable_value(&mut outline.mode, PaintMode::Stroke, "Stroke");
+                ui.selectable_value(&mut outline.mode, PaintMode::FillAndStroke, "FillAndStroke");
+            });
+
+        if outline.mode == PaintMode::Fill {
+            return;
+        }
+
+        ui.add(egui::Slider::new(&mut outline.width, 1.0..=100.0).text("Stroke width"));
+
+        egui::ComboBox::from_label("Stroke alignment")
+            .selected_text(format!("{:?}", outline.alignment))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut outline.alignment, StrokeAlignment::Inside, "Inside");
+                ui.selectable_value(&mut outline.alignment, StrokeAlignment::Center, "Center");
+                ui.selectable_value(&mut outline.alignment, StrokeAlignment::Outside, "Outside");
+            });
+
+        let mut color = egui::Color32::from_rgb(outline.color[0], outline.color[1], outline.color[2]);
+        color_controls(ui, &mut color);
+        outline.color = [color.r(), color.g(), color.b()];
+    });
+}
+
+fn to_retained_image(name: &str, bmp: &Image) -> Result<RetainedImage, String> {
+    let mut bytes: Vec<u8> = vec![];
+    bmp.to_writer(&mut bytes).map_err(|err| err.to_string())?;
+
+    RetainedImage::from_image_bytes(name, bytes.as_slice())
+        .map(|image| image.with_options(egui::TextureOptions::NEAREST))
+}
+
 impl eframe::App for TestPatternGenerator {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.show_scopes {
+            match self.scopes_docked {
+                true => {
+                    egui::TopBottomPanel::bottom("Scopes panel")
+                        .resizable(true)
+                        .show(ctx, |ui| self.scopes_ui(ui));
+                }
+                false => {
+                    let mut open = true;
+                    egui::Window::new("Scopes")
+                        .open(&mut open)
+                        .show(ctx, |ui| self.scopes_ui(ui));
+                    self.show_scopes = open;
+                }
+            }
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.add(egui::Slider::new(&mut self.width, 0..=3840 * 2).text("Width"));
-            ui.add(egui::Slider::new(&mut self.height, 0..=2160 * 2).text("Height"));
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                if let Some(err) = self.error.clone() {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(ui.visuals().error_fg_color, err);
+                        if ui.small_button("Dismiss").clicked() {
+                            self.error = None;
+                        }
+                    });
+                    ui.add_space(5.0);
+                }
 
-            ui.add_space(5.0);
+                self.resolution_ui(ui);
+                self.edid_ui(ui);
 
-            if ui.button("Reset").clicked() {
-                self.bmp = Some(bmp_generator::bmp_generator::BmpGenerator::clear(
-                    self.width,
-                    self.height,
-                ));
-                self.update_image();
-            }
+                ui.add_space(5.0);
 
-            ui.add_space(32.0);
-
-            ui.add(egui::Slider::new(&mut self.rect_start[0], 0..=self.width).text("Start X"));
-            ui.add(egui::Slider::new(&mut self.rect_start[1], 0..=self.height).text("Start Y"));
-
-            ui.add(egui::Slider::new(&mut self.rect_end[0], 0..=self.width * 2).text("End X"));
-            ui.add(egui::Slider::new(&mut self.rect_end[1], 0..=self.height * 2).text("End Y"));
-
-            ui.add_space(10.0);
-
-            ui.add(egui::Slider::new(&mut self.rect_rotation, 0.0..=360.0).text("Rotation"));
-
-            //ui.label("Color:");
-            ui.add_space(10.0);
-            ui.add(
-                egui::Slider::new(&mut self.rect_color[0], 0..=255)
-                    .text("Red")
-                    .text_color(egui::Color32::from_rgb(255, 0, 0)),
-            );
-            ui.add(
-                egui::Slider::new(&mut self.rect_color[1], 0..=255)
-                    .text("Green")
-                    .text_color(egui::Color32::from_rgb(0, 255, 0)),
-            );
-            ui.add(
-                egui::Slider::new(&mut self.rect_color[2], 0..=255)
-                    .text("Blue")
-                    .text_color(egui::Color32::from_rgb(0, 0, 255)),
-            );
-            egui::color_picker::color_edit_button_srgba(
-                ui,
-                &mut self.rect_color,
-                egui::color_picker::Alpha::Opaque,
-            );
-
-            ui.add_space(5.0);
+                paint_controls(ui, &mut self.background, self.width, self.height);
 
-            ui.horizontal(|ui| {
-                if ui.button("Generate rect").clicked() {
-                    self.add_rect();
+                ui.add_space(5.0);
+
+                ui.horizontal(|ui| {
+                    if ui.button("Reset").clicked() {
+                        self.apply(Operation::Background {
+                            width: self.width,
+                            height: self.height,
+                            paint: self.background.clone(),
+                        });
+                    }
+
+                    if ui.button("Resize").clicked() {
+                        self.resize_canvas(self.width, self.height);
+                    }
+                });
+
+                ui.add_space(32.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Image:");
+                    ui.text_edit_singleline(&mut self.open_path);
+                });
+
+                coordinate_slider(ui, &mut self.layer_position[0], self.width, "Layer X");
+                coordinate_slider(ui, &mut self.layer_position[1], self.height, "Layer Y");
+                ui.add(egui::Slider::new(&mut self.layer_scale, 0.1..=16.0).logarithmic(true).text("Layer scale"));
+
+                ui.add_space(5.0);
+
+                ui.horizontal(|ui| {
+                    if ui.button("Open image").clicked() {
+                        let path = self.open_path.clone();
+                        self.update_image_with_bmp_file(&path);
+                    }
+
+                    if ui.button("Add as layer").clicked() {
+                        let path = self.open_path.clone();
+                        self.add_image_layer(&path);
+                    }
+                });
+
+                ui.add_space(32.0);
+
+                ui.label("Shape transform:");
+                coordinate_slider(ui, &mut self.transform_offset[0], self.width, "Move X");
+                coordinate_slider(ui, &mut self.transform_offset[1], self.height, "Move Y");
+                ui.add(egui::Slider::new(&mut self.transform_rotation, -180.0..=180.0).text("Turn"));
+                ui.add(egui::Slider::new(&mut self.transform_scale[0], 0.1..=10.0).logarithmic(true).text("Scale X"));
+                ui.add(egui::Slider::new(&mut self.transform_scale[1], 0.1..=10.0).logarithmic(true).text("Scale Y"));
+                ui.add(egui::Slider::new(&mut self.transform_skew[0], -80.0..=80.0).text("Skew X"));
+                ui.add(egui::Slider::new(&mut self.transform_skew[1], -80.0..=80.0).text("Skew Y"));
+                coordinate_slider(ui, &mut self.transform_pivot[0], self.width, "Pivot X");
+                coordinate_slider(ui, &mut self.transform_pivot[1], self.height, "Pivot Y");
+
+                ui.add_space(5.0);
+
+                ui.horizontal(|ui| {
+                    if ui.button("Transform drawn shapes").clicked() {
+                        self.transform_drawn_shapes();
+                    }
+
+                    if ui.button("Reset transform").clicked() {
+                        self.transform_offset = [0.0, 0.0];
+                        self.transform_rotation = 0.0;
+                        self.transform_scale = [1.0, 1.0];
+                        self.transform_skew = [0.0, 0.0];
+                    }
+                });
+
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Symmetry:");
+                    ui.selectable_value(&mut self.symmetry, None, "Off");
+                    ui.selectable_value(&mut self.symmetry, Some(Symmetry::Horizontal), "Horizontal");
+                    ui.selectable_value(&mut self.symmetry, Some(Symmetry::Vertical), "Vertical");
+                    ui.selectable_value(&mut self.symmetry, Some(Symmetry::Both), "Both");
+                    let rotational = Symmetry::Rotational { folds: self.symmetry_folds };
+                    ui.selectable_value(&mut self.symmetry, Some(rotational), "Rotational");
+                });
+                if let Some(Symmetry::Rotational { folds }) = &mut self.symmetry {
+                    ui.add(egui::Slider::new(folds, 2..=64).text("Folds"));
+                    self.symmetry_folds = *folds;
+                }
+
+                ui.add_space(32.0);
+
+                self.array_ui(ui);
+
+                ui.add_space(32.0);
+
+                coordinate_slider(ui, &mut self.rect_start[0], self.width, "Start X");
+                coordinate_slider(ui, &mut self.rect_start[1], self.height, "Start Y");
+
+                coordinate_slider(ui, &mut self.rect_end[0], self.width, "End X");
+                coordinate_slider(ui, &mut self.rect_end[1], self.height, "End Y");
+
+                ui.add_space(10.0);
+
+                ui.add(egui::Slider::new(&mut self.rect_rotation, 0.0..=360.0).text("Rotation"));
+
+                let max_rounding = self.width.max(self.height) as f32 / 2.0;
+                ui.horizontal(|ui| {
+                    ui.add_enabled(
+                        !self.rect_per_corner_rounding,
+                        egui::Slider::new(&mut self.rounding, 0.0..=max_rounding).text("Rounding"),
+                    );
+                    ui.add(egui::Checkbox::new(&mut self.rect_per_corner_rounding, "Per corner"));
+                });
+                if self.rect_per_corner_rounding {
+                    for (radius, name) in self.rect_corner_radii.iter_mut().zip(["Top left", "Top right", "Bottom right", "Bottom left"]) {
+                        ui.add(egui::Slider::new(radius, 0.0..=max_rounding).text(name));
+                    }
                 }
 
-                if ui.button("Rotation sweep").clicked() {
-                    let original_rotation = self.rect_rotation;
-                    for i in (0..360).step_by(self.rect_rotation as usize)
-                    {
-                        self.rect_rotation = i as f64;
+                //ui.label("Color:");
+                ui.add_space(10.0);
+                paint_controls(ui, &mut self.rect_paint, self.width, self.height);
+
+                ui.add_space(10.0);
+                outline_controls(ui, &mut self.rect_outline);
+
+                ui.add_space(5.0);
+
+                ui.horizontal(|ui| {
+                    if ui.button("Generate rect").clicked() {
                         self.add_rect();
                     }
-                    self.rect_rotation = original_rotation;
+                });
+
+                ui.add_space(32.0);
+
+                coordinate_slider(ui, &mut self.ellipse_center[0], self.width, "Center X");
+                coordinate_slider(ui, &mut self.ellipse_center[1], self.height, "Center Y");
+
+                ui.add(egui::Slider::new(&mut self.ellipse_size[0], 0.0..=self.width as f64).text("Radius X"));
+                ui.add(egui::Slider::new(&mut self.ellipse_size[1], 0.0..=self.height as f64).text("Radius Y"));
+
+                ui.add_space(10.0);
+
+                ui.add(egui::Slider::new(&mut self.ellipse_rotation, 0.0..=360.0).text("Rotation"));
+
+                ui.add_space(10.0);
+                paint_controls(ui, &mut self.ellipse_paint, self.width, self.height);
+
+                ui.add_space(10.0);
+                outline_controls(ui, &mut self.ellipse_outline);
+
+                ui.add_space(5.0);
+
+                if ui.button("Generate ellipse").clicked() {
+                    self.add_ellipse();
                 }
-            });
 
-            ui.add_space(32.0);
-
-            ui.add(egui::Slider::new(&mut self.ellipse_center[0], 0..=self.width).text("Center X"));
-            ui.add(egui::Slider::new(&mut self.ellipse_center[1], 0..=self.height).text("Center Y"));
-
-            ui.add(egui::Slider::new(&mut self.ellipse_size[0], 0..=self.width).text("Radius X"));
-            ui.add(egui::Slider::new(&mut self.ellipse_size[1], 0..=self.height).text("Radius Y"));
-
-            ui.add_space(10.0);
-
-            ui.add_space(10.0);
-            ui.add(
-                egui::Slider::new(&mut self.ellipse_color[0], 0..=255)
-                    .text("Red")
-                    .text_color(egui::Color32::from_rgb(255, 0, 0)),
-            );
-            ui.add(
-                egui::Slider::new(&mut self.ellipse_color[1], 0..=255)
-                    .text("Green")
-                    .text_color(egui::Color32::from_rgb(0, 255, 0)),
-            );
-            ui.add(
-                egui::Slider::new(&mut self.ellipse_color[2], 0..=255)
-                    .text("Blue")
-                    .text_color(egui::Color32::from_rgb(0, 0, 255)),
-            );
-            egui::color_picker::color_edit_button_srgba(
-                ui,
-                &mut self.ellipse_color,
-                egui::color_picker::Alpha::Opaque,
-            );
-
-            ui.add_space(5.0);
-
-            if ui.button("Generate ellipse").clicked() {
-                self.add_ellipse();
-            }
+                ui.add_space(32.0);
 
-            ui.add_space(32.0);
+                for (i, point) in self.triangle_points.iter_mut().enumerate() {
+                    coordinate_slider(ui, &mut point[0], self.width, format!("Vertex {} X", i + 1));
+                    coordinate_slider(ui, &mut point[1], self.height, format!("Vertex {} Y", i + 1));
+                }
 
-            ui.add(egui::Slider::new(&mut self.num_stripe_colors, 0..=8).text("Num Colors"));
-            ui.add(
-                egui::Slider::new(&mut self.stripe_spacing, 0..=2160)
-                    .step_by(1.0)
-                    .text("Spacing"),
-            );
+                ui.add_space(10.0);
+                paint_controls(ui, &mut self.triangle_paint, self.width, self.height);
 
-            ui.horizontal(|ui| {
-                if ui.button("Generate stripes").clicked() {
-                    self.update_image_with_bmp_stripes();
+                ui.add_space(10.0);
+                outline_controls(ui, &mut self.triangle_outline);
+
+                ui.add_space(5.0);
+
+                if ui.button("Generate triangle").clicked() {
+                    self.add_triangle();
                 }
-                ui.add(egui::Checkbox::new(&mut self.horizontal_stripes, "Horizontal"));
-            });
 
-            if ui.button("Save").clicked() {
-                self.save_image("assets/image.bmp");
-            }
+                ui.add_space(32.0);
+
+                ui.label("Points (x,y; x,y; ...):");
+                ui.text_edit_singleline(&mut self.polygon_points);
+
+                egui::ComboBox::from_label("Fill rule")
+                    .selected_text(format!("{:?}", self.polygon_fill_rule))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.polygon_fill_rule, FillRule::NonZero, "NonZero");
+                        ui.selectable_value(&mut self.polygon_fill_rule, FillRule::EvenOdd, "EvenOdd");
+                    });
+
+                ui.add_space(10.0);
+                paint_controls(ui, &mut self.polygon_paint, self.width, self.height);
+
+                ui.add_space(10.0);
+                outline_controls(ui, &mut self.polygon_outline);
+
+                ui.add_space(5.0);
+
+                if ui.button("Generate polygon").clicked() {
+                    self.add_polygon();
+                }
+
+                ui.add_space(32.0);
+
+                coordinate_slider(ui, &mut self.line_start[0], self.width, "Start X");
+                coordinate_slider(ui, &mut self.line_start[1], self.height, "Start Y");
+
+                coordinate_slider(ui, &mut self.line_end[0], self.width, "End X");
+                coordinate_slider(ui, &mut self.line_end[1], self.height, "End Y");
+
+                ui.add_space(10.0);
+
+                ui.add(egui::Slider::new(&mut self.line_thickness, 1.0..=100.0).text("Thickness"));
+
+                egui::ComboBox::from_label("End caps")
+                    .selected_text(format!("{:?}", self.line_cap))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.line_cap, LineCap::Butt, "Butt");
+                        ui.selectable_value(&mut self.line_cap, LineCap::Square, "Square");
+                        ui.selectable_value(&mut self.line_cap, LineCap::Round, "Round");
+                    });
+
+                ui.add_space(10.0);
+                paint_controls(ui, &mut self.line_paint, self.width, self.height);
+
+                ui.add_space(5.0);
+
+                if ui.button("Generate line").clicked() {
+                    self.add_line();
+                }
+
+                ui.add_space(32.0);
+
+                let num_bezier_points = if self.bezier_cubic { 4 } else { 3 };
+                for (i, point) in self.bezier_points[..num_bezier_points].iter_mut().enumerate() {
+                    coordinate_slider(ui, &mut point[0], self.width, format!("Control {} X", i + 1));
+                    coordinate_slider(ui, &mut point[1], self.height, format!("Control {} Y", i + 1));
+                }
+                ui.add(egui::Checkbox::new(&mut self.bezier_cubic, "Cubic"));
+
+                ui.add_space(10.0);
+
+                ui.add(egui::Slider::new(&mut self.bezier_rotation, 0.0..=360.0).text("Rotation"));
+                ui.add(egui::Slider::new(&mut self.bezier_thickness, 1.0..=100.0).text("Thickness"));
+                ui.add(egui::Checkbox::new(&mut self.bezier_filled, "Filled"));
+
+                ui.add_space(10.0);
+                paint_controls(ui, &mut self.bezier_paint, self.width, self.height);
+
+                if self.bezier_filled {
+                    ui.add_space(10.0);
+                    outline_controls(ui, &mut self.bezier_outline);
+                }
+
+                ui.add_space(5.0);
+
+                if ui.button("Generate bezier").clicked() {
+                    self.add_bezier();
+                }
+
+                ui.add_space(32.0);
+
+                coordinate_slider(ui, &mut self.arc_center[0], self.width, "Center X");
+                coordinate_slider(ui, &mut self.arc_center[1], self.height, "Center Y");
+
+                ui.add(egui::Slider::new(&mut self.arc_size[0], 0.0..=self.width as f64).text("Radius X"));
+                ui.add(egui::Slider::new(&mut self.arc_size[1], 0.0..=self.height as f64).text("Radius Y"));
+
+                ui.add(egui::Slider::new(&mut self.arc_angles[0], 0.0..=360.0).text("Start angle"));
+                ui.add(egui::Slider::new(&mut self.arc_angles[1], 0.0..=360.0).text("End angle"));
+
+                ui.add_space(10.0);
+
+                ui.add(egui::Slider::new(&mut self.arc_rotation, 0.0..=360.0).text("Rotation"));
+                ui.add(egui::Slider::new(&mut self.arc_thickness, 1.0..=500.0).text("Thickness"));
+                ui.add(egui::Checkbox::new(&mut self.arc_filled, "Filled"));
+
+                ui.add_space(10.0);
+                paint_controls(ui, &mut self.arc_paint, self.width, self.height);
+
+                if self.arc_filled {
+                    ui.add_space(10.0);
+                    outline_controls(ui, &mut self.arc_outline);
+                }
+
+                ui.add_space(5.0);
+
+                if ui.button("Generate arc").clicked() {
+                    self.add_arc();
+                }
+
+                ui.add_space(32.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Scripts:");
+                    ui.text_edit_singleline(&mut self.script_folder);
+                    if ui.button("Reload").clicked() {
+                        self.reload_scripts();
+                    }
+                });
+
+                egui::ComboBox::from_label("Generator")
+                    .selected_text(&self.selected_generator)
+                    .show_ui(ui, |ui| {
+                        for generator in self.generators.iter() {
+                            let name = generator.name().to_owned();
+                            ui.selectable_value(&mut self.selected_generator, name, generator.name());
+                        }
+                    });
+
+                if let Some(generator) = self.generators.get(&self.selected_generator) {
+                    let values = self.generator_values.entry(generator.name().to_owned()).or_default();
+                    *values = generator.values(values).into_map();
+                    for parameter in generator.parameters() {
+                        if let Some(value) = values.get_mut(&parameter.name) {
+                            parameter_controls(ui, &parameter, value);
+                        }
+                    }
+                }
+
+                if let Some(err) = &self.generator_error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+
+                ui.add_space(5.0);
+
+                if ui.button("Generate").clicked() {
+                    self.generate();
+                }
+
+                ui.add_space(32.0);
+
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        let path = self.save_path.clone();
+                        self.save_image(&path);
+                    }
+                    ui.text_edit_singleline(&mut self.save_path);
+                });
+
+                ui.add_space(32.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Captured frame:");
+                    ui.text_edit_singleline(&mut self.compare_path);
+                });
+
+                ui.add(egui::Slider::new(&mut self.compare_offset[0], -64..=64).text("Offset X"));
+                ui.add(egui::Slider::new(&mut self.compare_offset[1], -64..=64).text("Offset Y"));
+                ui.add(egui::Slider::new(&mut self.compare_align, 0..=16).text("Auto align (max shift)"));
+
+                ui.add(egui::Checkbox::new(&mut self.compare_crop_enabled, "Crop"));
+                if self.compare_crop_enabled {
+                    ui.add(egui::Slider::new(&mut self.compare_crop[0], 0..=self.width).text("Crop X"));
+                    ui.add(egui::Slider::new(&mut self.compare_crop[1], 0..=self.height).text("Crop Y"));
+                    ui.add(egui::Slider::new(&mut self.compare_crop[2], 0..=self.width).text("Crop width"));
+                    ui.add(egui::Slider::new(&mut self.compare_crop[3], 0..=self.height).text("Crop height"));
+                }
+
+                ui.add_space(5.0);
+
+                if ui.button("Compare").clicked() {
+                    self.compare_with_file();
+                }
+
+                if let Some(comparison) = &self.comparison {
+                    ui.label(format!(
+                        "Compared {}x{} at offset {},{}: {}",
+                        comparison.width,
+                        comparison.height,
+                        self.compare_offset[0],
+                        self.compare_offset[1],
+                        if comparison.is_identical() { "bit-exact" } else { "different" },
+                    ));
+                    ui.label(format!("Differing pixels: {}", comparison.differing_pixels));
+                    for (i, channel) in ["Red", "Green", "Blue"].iter().enumerate() {
+                        ui.label(format!(
+                            "{channel}: max error {}, mean error {:.4}",
+                            comparison.max_error[i], comparison.mean_error[i]
+                        ));
+                    }
+                    ui.label(format!("PSNR: {:.2} dB", comparison.psnr));
+                    ui.label(format!("SSIM: {:.5}", comparison.ssim));
+
+                    ui.add(egui::Slider::new(&mut self.diff_gain, 1..=64).text("Diff gain"));
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Show diff").clicked() {
+                            self.show_difference(false);
+                        }
+                        if ui.button("Show heatmap").clicked() {
+                            self.show_difference(true);
+                        }
+                        if ui.button("Show canvas").clicked() {
+                            self.update_image();
+                        }
+                    });
+                }
+            });
         });
         egui::SidePanel::right("Image panel")
             .max_width(3840.0)
             .show(ctx, |ui| {
-                ui.heading("Preview:");
+                ui.horizontal(|ui| {
+                    ui.heading("Preview:");
+                    if ui.toggle_value(&mut self.show_scopes, "Scopes").changed() {
+                        self.update_scopes();
+                    }
+                });
                 ui.add(
                     egui::Slider::new(&mut self.scale, 0.1..=20.0)
                         .step_by(0.01)
                         .text("Scale"),
                 );
+
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.preview_tool, PreviewTool::Inspect, "Inspect");
+                    ui.radio_value(&mut self.preview_tool, PreviewTool::Edit, "Edit shapes");
+                });
+                if self.preview_tool == PreviewTool::Edit {
+                    ui.horizontal(|ui| {
+                        for shape in EditableShape::ALL {
+                            ui.selectable_value(&mut self.selected_shape, shape, format!("{shape:?}"));
+                        }
+                    });
+                }
+
+                let hovered = self.hovered_pixel.and_then(|pixel| self.describe_pixel(pixel));
+                ui.label(hovered.unwrap_or_else(|| "Hover the preview to inspect a pixel, click to pin it".to_owned()));
+
+                let mut remove = None;
+                for (i, probe) in self.probes.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.small_button("x").clicked() {
+                            remove = Some(i);
+                        }
+                        ui.label(self.describe_pixel(*probe).unwrap_or_else(|| format!("{}, {}: outside image", probe[0], probe[1])));
+                    });
+                }
+                if let Some(i) = remove {
+                    self.probes.remove(i);
+                }
+                if !self.probes.is_empty() && ui.button("Clear probes").clicked() {
+                    self.probes.clear();
+                }
+
+                if let Some(err) = &self.preview_error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+
                 egui::ScrollArea::vertical()
                     .auto_shrink([false, false])
                     .show(ui, |ui| {
                         egui::ScrollArea::horizontal().show(ui, |ui| {
-                            self.image.show_scaled(ui, self.scale);
+                            let response = ui.add(
+                                egui::Image::new(self.image.texture_id(ctx), self.image.size_vec2() * self.scale)
+                                    .sense(egui::Sense::click_and_drag()),
+                            );
+
+                            // screen position to image pixel, the same at any scale
+                            let to_pixel = |pos: egui::Pos2| {
+                                let offset = (pos - response.rect.min) / self.scale;
+                                [offset.x.max(0.0) as u32, offset.y.max(0.0) as u32]
+                            };
+
+                            self.hovered_pixel = response.hover_pos().map(to_pixel);
+
+                            self.paint_preview_overlay(ui, response.rect);
+
+                            match self.preview_tool {
+                                PreviewTool::Inspect => {
+                                    if let Some(pos) = response.interact_pointer_pos().filter(|_| response.clicked()) {
+                                        self.probes.push(to_pixel(pos));
+                                    }
+                                }
+                                PreviewTool::Edit => self.edit_preview(ui, &response),
+                            }
                         });
                     });
             });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bmp_generator::paint::{Gradient, GradientKind, GradientStop, Interpolation};
+
+    #[test]
+    fn remapping_scales_positions_sizes_and_radii() {
+        let mut app = TestPatternGenerator {
+            rect_start: [10.0, 20.0],
+            rect_end: [30.0, 40.0],
+            ellipse_size: [8.0, 4.0],
+            rounding: 6.0,
+            rect_corner_radii: [2.0, 4.0, 6.0, 8.0],
+            array_radius_step: 10.0,
+            polygon_points: "0,0; 10,5; 4,8".to_owned(),
+            ..Default::default()
+        };
+
+        app.remap_controls([2.0, 0.5]);
+
+        assert_eq!(app.rect_start, [20.0, 10.0]);
+        assert_eq!(app.rect_end, [60.0, 20.0]);
+        assert_eq!(app.ellipse_size, [16.0, 2.0]);
+        assert_eq!(app.rounding, 3.0);
+        assert_eq!(app.rect_corner_radii, [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(app.array_radius_step, 5.0);
+        assert_eq!(app.polygon_points, "0,0; 20,2.5; 8,4");
+    }
+
+    #[test]
+    fn remapping_moves_gradients_with_their_shapes() {
+        let gradient = Gradient {
+            kind: GradientKind::Linear,
+            start: [10.0, 10.0],
+            end: [20.0, 40.0],
+            stops: vec![GradientStop {
+                position: 0.0,
+                color: [0, 0, 0],
+            }],
+            interpolation: Interpolation::CodeValues,
+        };
+        let mut app = TestPatternGenerator {
+            rect_paint: Paint::Gradient(gradient),
+            ..Default::default()
+        };
+
+        app.remap_controls([2.0, 0.5]);
+
+        let Paint::Gradient(gradient) = &app.rect_paint else {
+            panic!("the paint should still be a gradient");
+        };
+        assert_eq!([gradient.start, gradient.end], [[20.0, 5.0], [40.0, 20.0]]);
+    }
+
+    #[test]
+    fn remapping_matches_resized_patterns() {
+        let mut app = TestPatternGenerator {
+            rect_start: [10.0, 20.0],
+            rect_end: [30.0, 40.0],
+            rounding: 6.0,
+            ..Default::default()
+        };
+        let rect = app.rect_operation();
+        let pattern = PatternDescription {
+            width: 100,
+            height: 100,
+            operations: vec![rect],
+        };
+
+        app.remap_controls([2.0, 0.5]);
+
+        let resized = pattern.resized(200, 50).unwrap();
+        assert_eq!(resized.operations, [app.rect_operation()]);
+    }
+}
@@ -1 +0,0 @@
-pub mod bmp_generator;
\ No newline at end of file
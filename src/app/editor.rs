@@ -0,0 +1,516 @@
+//! Drawing and editing the pending shapes directly on the preview.
+//!
+//! The shapes being edited are the ones described by the left panel, so dragging a handle moves the same
+//! values as the sliders do. Dragging snaps positions to whole pixels, and shapes can be dragged off any edge.
+
+use crate::bmp_generator::bmp_generator::{
+    ellipse_contains, flatten_arc, flatten_cubic, flatten_quadratic, line_contains, parse_points, polygon_contains,
+    rect_contains, rotate_points, rounded_rect_outline, FillRule, LineCap,
+};
+use crate::bmp_generator::transform::Transform;
+use super::TestPatternGenerator;
+use eframe::egui;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum PreviewTool {
+    Inspect,
+    Edit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum EditableShape {
+    Rect,
+    Ellipse,
+    Triangle,
+    Polygon,
+    Line,
+    Bezier,
+    Arc,
+}
+
+impl EditableShape {
+    pub const ALL: [EditableShape; 7] = [
+        EditableShape::Rect,
+        EditableShape::Ellipse,
+        EditableShape::Triangle,
+        EditableShape::Polygon,
+        EditableShape::Line,
+        EditableShape::Bezier,
+        EditableShape::Arc,
+    ];
+
+    /// Whether the shape has a rotation handle after its other handles.
+    fn rotates(self) -> bool {
+        matches!(
+            self,
+            EditableShape::Rect | EditableShape::Ellipse | EditableShape::Bezier | EditableShape::Arc
+        )
+    }
+}
+
+/// What the current drag on the preview is doing.
+#[derive(Clone, Copy, Debug)]
+pub enum Drag {
+    Move { last: [f64; 2] },
+    /// Dragging handle `index`, with `anchor` the point that stays put while resizing.
+    Handle { index: usize, anchor: [f64; 2] },
+    Rotate,
+    Draw { start: [f64; 2] },
+}
+
+/// Radius of a handle on screen, in points.
+const HANDLE_RADIUS: f32 = 5.0;
+/// How far the rotation handle sits outside its shape on screen, in points.
+const ROTATE_HANDLE_DISTANCE: f32 = 24.0;
+
+fn snap(point: [f64; 2]) -> [f64; 2] {
+    point.map(f64::round)
+}
+
+fn rotate(vector: [f64; 2], degrees: f64) -> [f64; 2] {
+    rotate_points(&[vector], [0.0, 0.0], degrees)[0]
+}
+
+/// `point` in the coordinates of a shape rotated by `degrees` about `center`, for testing it against the shape.
+fn unrotate(point: [f64; 2], center: [f64; 2], degrees: f64) -> (f64, f64) {
+    let local = Transform::rotate(degrees).around(center).inverse().map_or(point, |inverse| inverse.apply(point));
+    (local[0], local[1])
+}
+
+/// Whether `point` is within `thickness / 2` of the open path through `points`.
+fn path_contains(points: &[[f64; 2]], thickness: f64, point: [f64; 2]) -> bool {
+    points
+        .windows(2)
+        .any(|segment| line_contains(segment[0], segment[1], thickness, LineCap::Round, (point[0], point[1])))
+}
+
+/// `points` moved and stretched so their bounds fill the box from `min` to `max`, snapped to whole pixels.
+fn fit_points(points: &[[f64; 2]], min: [f64; 2], max: [f64; 2]) -> Vec<[f64; 2]> {
+    let low = [0, 1].map(|axis| points.iter().map(|p| p[axis]).fold(f64::INFINITY, f64::min));
+    let high = [0, 1].map(|axis| points.iter().map(|p| p[axis]).fold(f64::NEG_INFINITY, f64::max));
+
+    points
+        .iter()
+        .map(|p| {
+            snap([0, 1].map(|axis| match high[axis] - low[axis] {
+                span if span > 0.0 => min[axis] + (p[axis] - low[axis]) / span * (max[axis] - min[axis]),
+                _ => (min[axis] + max[axis]) / 2.0,
+            }))
+        })
+        .collect()
+}
+
+/// Angle in whole degrees, from 0 to 360, of the direction from `center` to `point`, measured so that a point
+/// straight above `center` is 0.
+fn angle_from(center: [f64; 2], point: [f64; 2]) -> f64 {
+    let angle = (point[1] - center[1]).atan2(point[0] - center[0]).to_degrees() + 90.0;
+    angle.rem_euclid(360.0).round() % 360.0
+}
+
+impl TestPatternGenerator {
+    fn rect_center(&self) -> [f64; 2] {
+        [
+            (self.rect_start[0] + self.rect_end[0]) / 2.0,
+            (self.rect_start[1] + self.rect_end[1]) / 2.0,
+        ]
+    }
+
+    /// The polygon's vertices, parsed from the text in the left panel.
+    fn polygon_vertices(&self) -> Vec<[f64; 2]> {
+        parse_points(&self.polygon_points)
+    }
+
+    fn set_polygon_vertices(&mut self, vertices: &[[f64; 2]]) {
+        let vertices: Vec<String> = vertices.iter().map(|[x, y]| format!("{x},{y}")).collect();
+        self.polygon_points = vertices.join("; ");
+    }
+
+    /// The control points the Bezier curve is drawn from, three for a quadratic curve and four for a cubic one.
+    fn bezier_control_points(&self) -> &[[f64; 2]] {
+        match self.bezier_cubic {
+            true => &self.bezier_points[..],
+            false => &self.bezier_points[..3],
+        }
+    }
+
+    /// The middle of the Bezier curve's control points, which it rotates about.
+    fn bezier_pivot(&self) -> [f64; 2] {
+        let points = self.bezier_control_points();
+        let count = points.len() as f64;
+        [
+            points.iter().map(|p| p[0]).sum::<f64>() / count,
+            points.iter().map(|p| p[1]).sum::<f64>() / count,
+        ]
+    }
+
+    /// The Bezier curve as drawn, in image coordinates.
+    fn bezier_path(&self) -> Vec<[f64; 2]> {
+        let points = match *self.bezier_control_points() {
+            [p0, p1, p2] => flatten_quadratic(p0, p1, p2),
+            [p0, p1, p2, p3] => flatten_cubic(p0, p1, p2, p3),
+            _ => vec![],
+        };
+        rotate_points(&points, self.bezier_pivot(), self.bezier_rotation)
+    }
+
+    /// The arc as drawn, in image coordinates, closed back to its center when it's filled.
+    fn arc_path(&self) -> Vec<[f64; 2]> {
+        let mut points = flatten_arc(self.arc_center, self.arc_size, self.arc_angles[0], self.arc_angles[1], self.arc_rotation);
+        if self.arc_filled {
+            points.push(self.arc_center);
+        }
+        points
+    }
+
+    /// Whether `shape` is drawn as an open path rather than a closed outline.
+    fn editor_is_open(&self, shape: EditableShape) -> bool {
+        match shape {
+            EditableShape::Line => true,
+            EditableShape::Bezier => !self.bezier_filled,
+            EditableShape::Arc => !self.arc_filled,
+            _ => false,
+        }
+    }
+
+    /// Outline of `shape` in image coordinates, for drawing it over the preview.
+    fn editor_outline(&self, shape: EditableShape) -> Vec<[f64; 2]> {
+        match shape {
+            EditableShape::Rect => {
+                rounded_rect_outline(self.rect_start, self.rect_end, self.rect_rotation, self.corner_radii())
+            }
+            EditableShape::Ellipse => {
+                flatten_arc(self.ellipse_center, self.ellipse_size, 0.0, 360.0, self.ellipse_rotation)
+            }
+            EditableShape::Triangle => self.triangle_points.to_vec(),
+            EditableShape::Polygon => self.polygon_vertices(),
+            EditableShape::Line => vec![self.line_start, self.line_end],
+            EditableShape::Bezier => self.bezier_path(),
+            EditableShape::Arc => self.arc_path(),
+        }
+    }
+
+    /// Handle positions of `shape` in image coordinates. For shapes that rotate, the last one is the rotation
+    /// handle, which keeps the same distance from the shape on screen at any `scale`.
+    fn editor_handles(&self, shape: EditableShape) -> Vec<[f64; 2]> {
+        let rotate_distance = (ROTATE_HANDLE_DISTANCE / self.scale) as f64;
+
+        match shape {
+            EditableShape::Rect => {
+                let center = self.rect_center();
+                let half = [
+                    (self.rect_end[0] - self.rect_start[0]) / 2.0,
+                    (self.rect_end[1] - self.rect_start[1]) / 2.0,
+                ];
+
+                let mut handles: Vec<[f64; 2]> = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]]
+                    .iter()
+                    .map(|d| [half[0] * d[0], half[1] * d[1]])
+                    .collect();
+                handles.push([0.0, -half[1] - rotate_distance]);
+
+                handles
+                    .iter()
+                    .map(|h| rotate(*h, self.rect_rotation))
+                    .map(|h| [center[0] + h[0], center[1] + h[1]])
+                    .collect()
+            }
+            EditableShape::Ellipse => {
+                let center = self.ellipse_center;
+                let size = self.ellipse_size;
+
+                [[size[0], 0.0], [0.0, size[1]], [0.0, -size[1] - rotate_distance]]
+                    .iter()
+                    .map(|h| rotate(*h, self.ellipse_rotation))
+                    .map(|h| [center[0] + h[0], center[1] + h[1]])
+                    .collect()
+            }
+            EditableShape::Triangle => self.triangle_points.to_vec(),
+            EditableShape::Polygon => self.polygon_vertices(),
+            EditableShape::Line => vec![self.line_start, self.line_end],
+            EditableShape::Bezier => {
+                // the control points, and the rotation handle above all of them
+                let pivot = self.bezier_pivot();
+                let points = self.bezier_control_points();
+                let reach = points
+                    .iter()
+                    .map(|p| (p[0] - pivot[0]).hypot(p[1] - pivot[1]))
+                    .fold(0.0, f64::max);
+
+                let mut handles = rotate_points(points, pivot, self.bezier_rotation);
+                let rotate_handle = rotate([0.0, -reach - rotate_distance], self.bezier_rotation);
+                handles.push([pivot[0] + rotate_handle[0], pivot[1] + rotate_handle[1]]);
+                handles
+            }
+            EditableShape::Arc => {
+                let center = self.arc_center;
+                let size = self.arc_size;
+
+                [[size[0], 0.0], [0.0, size[1]], [0.0, -size[1] - rotate_distance]]
+                    .iter()
+                    .map(|h| rotate(*h, self.arc_rotation))
+                    .map(|h| [center[0] + h[0], center[1] + h[1]])
+                    .collect()
+            }
+        }
+    }
+
+    fn editor_contains(&self, shape: EditableShape, point: [f64; 2]) -> bool {
+        match shape {
+            EditableShape::Rect => {
+                let local = unrotate(point, self.rect_center(), self.rect_rotation);
+                rect_contains(self.rect_start, self.rect_end, self.corner_radii(), local)
+            }
+            EditableShape::Ellipse => {
+                let local = unrotate(point, self.ellipse_center, self.ellipse_rotation);
+                ellipse_contains(self.ellipse_center, self.ellipse_size, local)
+            }
+            EditableShape::Triangle => {
+                polygon_contains(&self.triangle_points, FillRule::NonZero, (point[0], point[1]))
+            }
+            EditableShape::Polygon => {
+                polygon_contains(&self.polygon_vertices(), self.polygon_fill_rule, (point[0], point[1]))
+            }
+            // thin lines are hard to hit, so accept anything within a handle's reach
+            EditableShape::Line => {
+                let thickness = self.line_thickness.max(2.0 * (HANDLE_RADIUS / self.scale) as f64);
+                line_contains(self.line_start, self.line_end, thickness, LineCap::Round, (point[0], point[1]))
+            }
+            EditableShape::Bezier if self.bezier_filled => {
+                polygon_contains(&self.bezier_path(), FillRule::NonZero, (point[0], point[1]))
+            }
+            EditableShape::Bezier => {
+                let thickness = self.bezier_thickness.max(2.0 * (HANDLE_RADIUS / self.scale) as f64);
+                path_contains(&self.bezier_path(), thickness, point)
+            }
+            EditableShape::Arc if self.arc_filled => {
+                polygon_contains(&self.arc_path(), FillRule::NonZero, (point[0], point[1]))
+            }
+            EditableShape::Arc => {
+                let thickness = self.arc_thickness.max(2.0 * (HANDLE_RADIUS / self.scale) as f64);
+                path_contains(&self.arc_path(), thickness, point)
+            }
+        }
+    }
+
+    /// Moves `shape` by `delta` pixels.
+    fn editor_move(&mut self, shape: EditableShape, delta: [f64; 2]) {
+        let mut vertices = self.polygon_vertices();
+        let points: Vec<&mut [f64; 2]> = match shape {
+            EditableShape::Rect => vec![&mut self.rect_start, &mut self.rect_end],
+            EditableShape::Ellipse => vec![&mut self.ellipse_center],
+            EditableShape::Triangle => self.triangle_points.iter_mut().collect(),
+            EditableShape::Polygon => vertices.iter_mut().collect(),
+            EditableShape::Line => vec![&mut self.line_start, &mut self.line_end],
+            EditableShape::Bezier => self.bezier_points.iter_mut().collect(),
+            EditableShape::Arc => vec![&mut self.arc_center],
+        };
+
+        for point in points {
+            *point = [point[0] + delta[0], point[1] + delta[1]];
+        }
+
+        if shape == EditableShape::Polygon {
+            self.set_polygon_vertices(&vertices);
+        }
+    }
+
+    /// Moves `shape` so its first point lies on whole pixels, so moving it by whole pixels keeps it snapped.
+    fn editor_snap(&mut self, shape: EditableShape) {
+        let first = match shape {
+            EditableShape::Rect => self.rect_start,
+            EditableShape::Ellipse => self.ellipse_center,
+            EditableShape::Triangle => self.triangle_points[0],
+            EditableShape::Polygon => match self.polygon_vertices().first() {
+                Some(vertex) => *vertex,
+                None => return,
+            },
+            EditableShape::Line => self.line_start,
+            EditableShape::Bezier => self.bezier_points[0],
+            EditableShape::Arc => self.arc_center,
+        };
+
+        let snapped = snap(first);
+        self.editor_move(shape, [snapped[0] - first[0], snapped[1] - first[1]]);
+    }
+
+    fn editor_drag_handle(&mut self, shape: EditableShape, index: usize, anchor: [f64; 2], point: [f64; 2]) {
+        match shape {
+            EditableShape::Rect => {
+                // the opposite corner stays put, and the size is measured along the rect's own axes
+                let local = rotate([point[0] - anchor[0], point[1] - anchor[1]], -self.rect_rotation);
+                let size = [local[0].abs().round(), local[1].abs().round()];
+                let center = [(anchor[0] + point[0]) / 2.0, (anchor[1] + point[1]) / 2.0];
+
+                self.rect_start = snap([center[0] - size[0] / 2.0, center[1] - size[1] / 2.0]);
+                self.rect_end = [self.rect_start[0] + size[0], self.rect_start[1] + size[1]];
+            }
+            EditableShape::Ellipse => {
+                let center = self.ellipse_center;
+                let local = rotate([point[0] - center[0], point[1] - center[1]], -self.ellipse_rotation);
+                self.ellipse_size[index] = local[index].abs().round();
+            }
+            EditableShape::Triangle => self.triangle_points[index] = snap(point),
+            EditableShape::Polygon => {
+                let mut vertices = self.polygon_vertices();
+                if let Some(vertex) = vertices.get_mut(index) {
+                    *vertex = snap(point);
+                    self.set_polygon_vertices(&vertices);
+                }
+            }
+            EditableShape::Line => match index {
+                0 => self.line_start = snap(point),
+                _ => self.line_end = snap(point),
+            },
+            EditableShape::Bezier => {
+                // handles are drawn rotated, so turn the point back into the curve's own coordinates
+                let local = unrotate(point, self.bezier_pivot(), self.bezier_rotation);
+                self.bezier_points[index] = snap([local.0, local.1]);
+            }
+            EditableShape::Arc => {
+                let center = self.arc_center;
+                let local = rotate([point[0] - center[0], point[1] - center[1]], -self.arc_rotation);
+                self.arc_size[index] = local[index].abs().round();
+            }
+        }
+    }
+
+    fn editor_rotate(&mut self, shape: EditableShape, point: [f64; 2]) {
+        match shape {
+            EditableShape::Rect => self.rect_rotation = angle_from(self.rect_center(), point),
+            EditableShape::Ellipse => self.ellipse_rotation = angle_from(self.ellipse_center, point),
+            EditableShape::Bezier => self.bezier_rotation = angle_from(self.bezier_pivot(), point),
+            EditableShape::Arc => self.arc_rotation = angle_from(self.arc_center, point),
+            EditableShape::Triangle | EditableShape::Polygon | EditableShape::Line => {}
+        }
+    }
+
+    /// Replaces `shape` with one drawn by dragging from `start` to `end`. Ellipses and arcs are drawn from their
+    /// center, and polygons and Bezier curves keep their points, stretched to fill the dragged box.
+    fn editor_draw(&mut self, shape: EditableShape, start: [f64; 2], end: [f64; 2]) {
+        let min = snap([start[0].min(end[0]), start[1].min(end[1])]);
+        let max = snap([start[0].max(end[0]), start[1].max(end[1])]);
+
+        match shape {
+            EditableShape::Rect => {
+                self.rect_start = min;
+                self.rect_end = max;
+            }
+            EditableShape::Ellipse => {
+                self.ellipse_center = snap(start);
+                self.ellipse_size = snap([(end[0] - start[0]).abs(), (end[1] - start[1]).abs()]);
+            }
+            EditableShape::Triangle => {
+                self.triangle_points = [[((min[0] + max[0]) / 2.0).round(), min[1]], [max[0], max[1]], [min[0], max[1]]];
+            }
+            EditableShape::Polygon => {
+                let vertices = fit_points(&self.polygon_vertices(), min, max);
+                self.set_polygon_vertices(&vertices);
+            }
+            EditableShape::Line => {
+                self.line_start = snap(start);
+                self.line_end = snap(end);
+            }
+            EditableShape::Bezier => {
+                let fitted = fit_points(&self.bezier_points, min, max);
+                self.bezier_points.copy_from_slice(&fitted);
+            }
+            EditableShape::Arc => {
+                self.arc_center = snap(start);
+                self.arc_size = snap([(end[0] - start[0]).abs(), (end[1] - start[1]).abs()]);
+            }
+        }
+    }
+
+    /// Handles dragging on the preview image shown in `response` and draws the shapes and handles over it.
+    pub(super) fn edit_preview(&mut self, ui: &egui::Ui, response: &egui::Response) {
+        let rect = response.rect;
+        let scale = self.scale;
+        let to_image = |pos: egui::Pos2| {
+            let offset = (pos - rect.min) / scale;
+            [offset.x.round() as f64, offset.y.round() as f64]
+        };
+        let to_screen = |point: [f64; 2]| rect.min + egui::vec2(point[0] as f32, point[1] as f32) * scale;
+
+        if let Some(pos) = response.interact_pointer_pos() {
+            let point = to_image(pos);
+
+            if response.drag_started() {
+                self.drag = Some(self.start_drag(pos, point, &to_screen));
+                if let Some(Drag::Move { .. }) = self.drag {
+                    self.editor_snap(self.selected_shape);
+                }
+            }
+
+            match self.drag {
+                Some(Drag::Move { last }) => {
+                    self.editor_move(self.selected_shape, [point[0] - last[0], point[1] - last[1]]);
+                    self.drag = Some(Drag::Move { last: point });
+                }
+                Some(Drag::Handle { index, anchor }) => self.editor_drag_handle(self.selected_shape, index, anchor, point),
+                Some(Drag::Rotate) => self.editor_rotate(self.selected_shape, point),
+                Some(Drag::Draw { start }) => self.editor_draw(self.selected_shape, start, point),
+                None => {}
+            }
+        }
+
+        if response.drag_released() {
+            self.drag = None;
+        }
+
+        let painter = ui.painter_at(rect);
+        for shape in EditableShape::ALL {
+            let selected = shape == self.selected_shape;
+            let color = match selected {
+                true => egui::Color32::YELLOW,
+                false => egui::Color32::from_gray(160),
+            };
+
+            let mut outline: Vec<egui::Pos2> = self.editor_outline(shape).into_iter().map(to_screen).collect();
+            if !self.editor_is_open(shape) {
+                outline.extend(outline.first().copied());
+            }
+            painter.add(egui::Shape::line(outline, egui::Stroke::new(1.0, color)));
+
+            if selected {
+                for handle in self.editor_handles(shape) {
+                    painter.circle(to_screen(handle), HANDLE_RADIUS, egui::Color32::WHITE, egui::Stroke::new(1.0, egui::Color32::BLACK));
+                }
+            }
+        }
+    }
+
+    /// Works out what a drag starting at screen position `pos` (image position `point`) should do: grab a
+    /// handle of the selected shape, move whichever shape is under the pointer, or draw a new one.
+    fn start_drag(&mut self, pos: egui::Pos2, point: [f64; 2], to_screen: &dyn Fn([f64; 2]) -> egui::Pos2) -> Drag {
+        let shape = self.selected_shape;
+        let handles = self.editor_handles(shape);
+
+        if let Some(index) = handles
+            .iter()
+            .position(|h| to_screen(*h).distance(pos) <= HANDLE_RADIUS * 1.5)
+        {
+            if shape.rotates() && index == handles.len() - 1 {
+                return Drag::Rotate;
+            }
+
+            let anchor = match shape {
+                EditableShape::Rect => handles[(index + 2) % 4],
+                _ => handles[index],
+            };
+            return Drag::Handle { index, anchor };
+        }
+
+        // the selected shape wins when shapes overlap
+        let under_pointer = std::iter::once(shape)
+            .chain(EditableShape::ALL)
+            .find(|s| self.editor_contains(*s, point));
+
+        match under_pointer {
+            Some(hit) => {
+                self.selected_shape = hit;
+                Drag::Move { last: point }
+            }
+            None => Drag::Draw { start: point },
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Repeating a shape in a row, a grid or a circle.
+
+use super::transform::Transform;
+
+/// Where the copies of an arrayed shape go. The first copy is always the shape where it was drawn.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Arrangement {
+    /// `count` copies, each moved by `step` from the last.
+    Linear { count: u32, step: [f64; 2] },
+    /// `columns` by `rows` copies, `spacing` apart, filled row by row.
+    Grid { columns: u32, rows: u32, spacing: [f64; 2] },
+    /// `count` copies, each turned clockwise about `center` by `angle_step` degrees from the last and pushed
+    /// `radius_step` further out. Copies are pushed out the way straight up points before they're turned, so a
+    /// shape drawn above the center spirals away from it.
+    Radial {
+        count: u32,
+        center: [f64; 2],
+        angle_step: f64,
+        radius_step: f64,
+    },
+}
+
+impl Arrangement {
+    /// How many copies there are.
+    pub fn count(&self) -> u32 {
+        match *self {
+            Arrangement::Linear { count, .. } | Arrangement::Radial { count, .. } => count,
+            Arrangement::Grid { columns, rows, .. } => columns.saturating_mul(rows),
+        }
+    }
+
+    /// The transform that puts the shape where each copy goes, in order.
+    pub fn transforms(&self) -> Vec<Transform> {
+        match *self {
+            Arrangement::Linear { count, step } => (0..count)
+                .map(|i| Transform::translate(step[0] * i as f64, step[1] * i as f64))
+                .collect(),
+            Arrangement::Grid { columns, rows, spacing } => (0..rows)
+                .flat_map(|row| {
+                    (0..columns).map(move |column| {
+                        Transform::translate(spacing[0] * column as f64, spacing[1] * row as f64)
+                    })
+                })
+                .collect(),
+            Arrangement::Radial {
+                count,
+                center,
+                angle_step,
+                radius_step,
+            } => (0..count)
+                .map(|i| {
+                    Transform::translate(0.0, -radius_step * i as f64)
+                        .then(Transform::rotate(angle_step * i as f64).around(center))
+                })
+                .collect(),
+        }
+    }
+}
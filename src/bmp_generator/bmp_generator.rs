@@ -0,0 +1,890 @@
+use std::f64::consts::PI;
+use std::sync::OnceLock;
+use bmp::{Image, Pixel};
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use epaint::text::FontDefinitions;
+use rayon::prelude::*;
+use super::error::PatternError;
+use super::expression::{Expression, Variables};
+use super::paint::{stripe_color, Paint};
+use super::transform::Transform;
+
+pub struct BmpGenerator {}
+
+/// The largest text size drawn, in pixels. Each glyph is rasterized whole, so huge sizes would take a long time
+/// and a lot of memory even for text that's mostly off the canvas.
+pub const MAX_TEXT_SIZE: f64 = 1024.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum FillRule {
+    EvenOdd,
+    NonZero,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum LineCap {
+    Butt,
+    Square,
+    Round,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum PaintMode {
+    Fill,
+    Stroke,
+    FillAndStroke,
+}
+
+/// Where the stroke sits relative to the shape's edge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum StrokeAlignment {
+    Inside,
+    Center,
+    Outside,
+}
+
+/// How a closed shape is painted: its interior, its edge, or both. The stroke is drawn on top of the fill.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Outline {
+    pub mode: PaintMode,
+    pub width: f64,
+    pub alignment: StrokeAlignment,
+    pub color: [u8; 3],
+}
+
+impl Default for Outline {
+    fn default() -> Self {
+        Self {
+            mode: PaintMode::Fill,
+            width: 1.0,
+            alignment: StrokeAlignment::Inside,
+            color: [255, 255, 255],
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub fn point_in_ellipse(h: f64, k: f64, a: f64, b: f64, x: f64, y: f64) -> f64 {
+    (((x - h) * (x - h)) / (a * a)) + (((y - k) * (y - k)) / (b * b))
+}
+
+/// The top left and bottom right of the rect with corners `a` and `b`, whichever way round they were given.
+pub fn ordered_corners(a: [f64; 2], b: [f64; 2]) -> ([f64; 2], [f64; 2]) {
+    ([a[0].min(b[0]), a[1].min(b[1])], [a[0].max(b[0]), a[1].max(b[1])])
+}
+
+/// The middle of the rect with corners `rect_start` and `rect_end`, which shapes rotate about.
+pub fn rect_center(rect_start: [f64; 2], rect_end: [f64; 2]) -> [f64; 2] {
+    [(rect_start[0] + rect_end[0]) / 2.0, (rect_start[1] + rect_end[1]) / 2.0]
+}
+
+/// Tests a point against the rect from `rect_start` to `rect_end`, with corners rounded by `corner_radii`
+/// (top-left, top-right, bottom-right, bottom-left). Rotated rects are tested through a [`Transform`].
+pub fn rect_contains(rect_start: [f64; 2], rect_end: [f64; 2], corner_radii: [f64; 4], point: (f64, f64)) -> bool {
+    let (rect_start, rect_end) = ordered_corners(rect_start, rect_end);
+    let width = rect_end[0] - rect_start[0];
+    let height = rect_end[1] - rect_start[1];
+    let center = rect_center(rect_start, rect_end);
+
+    let x = point.0 - center[0];
+    let y = point.1 - center[1];
+
+    if !(x.abs() <= width / 2.0 && y.abs() <= height / 2.0) {
+        return false;
+    }
+
+    let radius = match (x < 0.0, y < 0.0) {
+        (true, true) => corner_radii[0],
+        (false, true) => corner_radii[1],
+        (false, false) => corner_radii[2],
+        (true, false) => corner_radii[3],
+    };
+    let radius = radius.clamp(0.0, width.min(height) / 2.0);
+
+    // only points inside the corner square can fall outside the rounding
+    let corner_x = x.abs() - (width / 2.0 - radius);
+    let corner_y = y.abs() - (height / 2.0 - radius);
+
+    corner_x <= 0.0 || corner_y <= 0.0 || corner_x * corner_x + corner_y * corner_y <= radius * radius
+}
+
+/// Tests a point against the ellipse with radii `radii` around `center`. Rotated ellipses are tested through a
+/// [`Transform`].
+pub fn ellipse_contains(center: [f64; 2], radii: [f64; 2], point: (f64, f64)) -> bool {
+    let x = (point.0 - center[0]) / radii[0];
+    let y = (point.1 - center[1]) / radii[1];
+    x * x + y * y <= 1.0
+}
+
+pub fn polygon_contains(points: &[[f64; 2]], fill_rule: FillRule, point: (f64, f64)) -> bool {
+    let mut crossings = 0;
+    let mut winding = 0;
+
+    for i in 0..points.len() {
+        let a = points[i];
+        let b = points[(i + 1) % points.len()];
+
+        // half-open test so a vertex shared by two edges is only counted once
+        if (a[1] <= point.1) != (b[1] <= point.1) {
+            let t = (point.1 - a[1]) / (b[1] - a[1]);
+            let x = a[0] + t * (b[0] - a[0]);
+
+            if x > point.0 {
+                crossings += 1;
+                winding += if b[1] > a[1] { 1 } else { -1 };
+            }
+        }
+    }
+
+    match fill_rule {
+        FillRule::EvenOdd => crossings % 2 == 1,
+        FillRule::NonZero => winding != 0,
+    }
+}
+
+pub fn line_contains(start: [f64; 2], end: [f64; 2], thickness: f64, cap: LineCap, point: (f64, f64)) -> bool {
+    let half = thickness / 2.0;
+
+    let dx = end[0] - start[0];
+    let dy = end[1] - start[1];
+    let length = (dx * dx + dy * dy).sqrt();
+
+    if length == 0.0 {
+        // a zero-length line only has its caps
+        return match cap {
+            LineCap::Butt => false,
+            LineCap::Square => (point.0 - start[0]).abs() <= half && (point.1 - start[1]).abs() <= half,
+            LineCap::Round => (point.0 - start[0]).hypot(point.1 - start[1]) <= half,
+        };
+    }
+
+    // distance along the line and away from it, in pixels
+    let along = ((point.0 - start[0]) * dx + (point.1 - start[1]) * dy) / length;
+    let across = (-(point.0 - start[0]) * dy + (point.1 - start[1]) * dx) / length;
+
+    match cap {
+        LineCap::Butt => along >= 0.0 && along <= length && across.abs() <= half,
+        LineCap::Square => along >= -half && along <= length + half && across.abs() <= half,
+        LineCap::Round => {
+            let clamped = along.clamp(0.0, length);
+            (along - clamped).hypot(across) <= half
+        }
+    }
+}
+
+/// Parses a list of points written as `x,y; x,y; ...`, skipping any entry that isn't a valid pair.
+pub fn parse_points(text: &str) -> Vec<[f64; 2]> {
+    text.split(';')
+        .filter_map(|pair| {
+            let mut coords = pair.split(',').map(|c| c.trim().parse::<f64>());
+            match (coords.next(), coords.next(), coords.next()) {
+                (Some(Ok(x)), Some(Ok(y)), None) => Some([x, y]),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn path_length(points: &[[f64; 2]]) -> f64 {
+    points
+        .windows(2)
+        .map(|w| (w[1][0] - w[0][0]).hypot(w[1][1] - w[0][1]))
+        .sum()
+}
+
+/// Picks how many straight segments to split a curve into so that each one is about two pixels long.
+fn segment_count(approx_length: f64) -> usize {
+    ((approx_length / 2.0).ceil() as usize).clamp(8, 4096)
+}
+
+pub fn flatten_quadratic(p0: [f64; 2], p1: [f64; 2], p2: [f64; 2]) -> Vec<[f64; 2]> {
+    let segments = segment_count(path_length(&[p0, p1, p2]));
+
+    (0..=segments)
+        .map(|i| {
+            let t = i as f64 / segments as f64;
+            let u = 1.0 - t;
+            [
+                u * u * p0[0] + 2.0 * u * t * p1[0] + t * t * p2[0],
+                u * u * p0[1] + 2.0 * u * t * p1[1] + t * t * p2[1],
+            ]
+        })
+        .collect()
+}
+
+pub fn flatten_cubic(p0: [f64; 2], p1: [f64; 2], p2: [f64; 2], p3: [f64; 2]) -> Vec<[f64; 2]> {
+    let segments = segment_count(path_length(&[p0, p1, p2, p3]));
+
+    (0..=segments)
+        .map(|i| {
+            let t = i as f64 / segments as f64;
+            let u = 1.0 - t;
+            [
+                u * u * u * p0[0] + 3.0 * u * u * t * p1[0] + 3.0 * u * t * t * p2[0] + t * t * t * p3[0],
+                u * u * u * p0[1] + 3.0 * u * u * t * p1[1] + 3.0 * u * t * t * p2[1] + t * t * t * p3[1],
+            ]
+        })
+        .collect()
+}
+
+/// Flattens an elliptical arc running clockwise (in image coordinates) from `start_angle` to `end_angle`, in degrees.
+pub fn flatten_arc(center: [f64; 2], radii: [f64; 2], start_angle: f64, end_angle: f64, rotation: f64) -> Vec<[f64; 2]> {
+    let sweep = (end_angle - start_angle) * PI / 180.0;
+    let segments = segment_count(sweep.abs() * radii[0].max(radii[1]));
+
+    let points: Vec<[f64; 2]> = (0..=segments)
+        .map(|i| {
+            let angle = start_angle * PI / 180.0 + sweep * i as f64 / segments as f64;
+            [center[0] + radii[0] * angle.cos(), center[1] + radii[1] * angle.sin()]
+        })
+        .collect();
+
+    rotate_points(&points, center, rotation)
+}
+
+/// Rotates `points` by `rotation` degrees about `pivot`.
+pub fn rotate_points(points: &[[f64; 2]], pivot: [f64; 2], rotation: f64) -> Vec<[f64; 2]> {
+    Transform::rotate(rotation).around(pivot).apply_all(points)
+}
+
+/// Returns the pixel range `[start_x, start_y, end_x, end_y)` covered by `points`, clipped to the image.
+fn bounding_box(bmp: &Image, points: &[[f64; 2]], margin: f64) -> [u32; 4] {
+    let width = bmp.get_width() as f64;
+    let height = bmp.get_height() as f64;
+
+    let min_x = points.iter().map(|p| p[0]).fold(f64::INFINITY, f64::min) - margin;
+    let min_y = points.iter().map(|p| p[1]).fold(f64::INFINITY, f64::min) - margin;
+    let max_x = points.iter().map(|p| p[0]).fold(f64::NEG_INFINITY, f64::max) + margin;
+    let max_y = points.iter().map(|p| p[1]).fold(f64::NEG_INFINITY, f64::max) + margin;
+
+    [
+        min_x.floor().clamp(0.0, width) as u32,
+        min_y.floor().clamp(0.0, height) as u32,
+        (max_x.ceil() + 1.0).clamp(0.0, width) as u32,
+        (max_y.ceil() + 1.0).clamp(0.0, height) as u32,
+    ]
+}
+
+/// The `[min_x, min_y, max_x, max_y]` extent of `points`.
+fn points_bounds(points: &[[f64; 2]]) -> [f64; 4] {
+    [
+        points.iter().map(|p| p[0]).fold(f64::INFINITY, f64::min),
+        points.iter().map(|p| p[1]).fold(f64::INFINITY, f64::min),
+        points.iter().map(|p| p[0]).fold(f64::NEG_INFINITY, f64::max),
+        points.iter().map(|p| p[1]).fold(f64::NEG_INFINITY, f64::max),
+    ]
+}
+
+/// Distance from `point` to the segment from `a` to `b`.
+fn distance_to_segment(a: [f64; 2], b: [f64; 2], point: (f64, f64)) -> f64 {
+    let dx = b[0] - a[0];
+    let dy = b[1] - a[1];
+    let length_squared = dx * dx + dy * dy;
+
+    let t = match length_squared {
+        l if l == 0.0 => 0.0,
+        l => (((point.0 - a[0]) * dx + (point.1 - a[1]) * dy) / l).clamp(0.0, 1.0),
+    };
+
+    (point.0 - (a[0] + t * dx)).hypot(point.1 - (a[1] + t * dy))
+}
+
+/// The pixels of `area` (`[start_x, start_y, end_x, end_y)`) whose centers are within `reach` of the closed
+/// polygon `points`, row by row. Each edge only visits the pixels around it, so the cost grows with the length of
+/// the outline rather than with the area of the shape.
+fn outline_band(area: [u32; 4], points: &[[f64; 2]], reach: f64) -> Vec<bool> {
+    let [start_x, start_y, end_x, end_y] = area;
+    let width = (end_x - start_x) as usize;
+    let mut band = vec![false; width * (end_y - start_y) as usize];
+
+    for i in 0..points.len() {
+        let a = points[i];
+        let b = points[(i + 1) % points.len()];
+
+        let clip = |value: f64, start: u32, end: u32| value.clamp(start as f64, end as f64) as u32;
+        let x0 = clip((a[0].min(b[0]) - reach - 0.5).floor(), start_x, end_x);
+        let x1 = clip((a[0].max(b[0]) + reach + 0.5).ceil(), start_x, end_x);
+        let y0 = clip((a[1].min(b[1]) - reach - 0.5).floor(), start_y, end_y);
+        let y1 = clip((a[1].max(b[1]) + reach + 0.5).ceil(), start_y, end_y);
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let index = (y - start_y) as usize * width + (x - start_x) as usize;
+                if !band[index] && distance_to_segment(a, b, (x as f64 + 0.5, y as f64 + 0.5)) <= reach {
+                    band[index] = true;
+                }
+            }
+        }
+    }
+
+    band
+}
+
+/// Returns the outline of a rect with rounded corners, matching the shape tested by `rect_contains`.
+pub fn rounded_rect_outline(rect_start: [f64; 2], rect_end: [f64; 2], rect_angle: f64, corner_radii: [f64; 4]) -> Vec<[f64; 2]> {
+    let (rect_start, rect_end) = ordered_corners(rect_start, rect_end);
+    let width = rect_end[0] - rect_start[0];
+    let height = rect_end[1] - rect_start[1];
+    let center = [rect_start[0] + width / 2.0, rect_start[1] + height / 2.0];
+
+    // corners in drawing order, with the direction of each corner and the angles its arc covers
+    let corners = [
+        (corner_radii[0], [-1.0, -1.0], 180.0),
+        (corner_radii[1], [1.0, -1.0], 270.0),
+        (corner_radii[2], [1.0, 1.0], 0.0),
+        (corner_radii[3], [-1.0, 1.0], 90.0),
+    ];
+
+    let mut points = vec![];
+
+    for (radius, direction, start_angle) in corners {
+        let radius = radius.clamp(0.0, width.min(height) / 2.0);
+        let corner_center = [
+            center[0] + direction[0] * (width / 2.0 - radius),
+            center[1] + direction[1] * (height / 2.0 - radius),
+        ];
+
+        match radius > 0.0 {
+            true => points.extend(flatten_arc(corner_center, [radius, radius], start_angle, start_angle + 90.0, 0.0)),
+            false => points.push(corner_center),
+        }
+    }
+
+    rotate_points(&points, center, rect_angle)
+}
+
+/// Paints a closed shape according to `outline`. `contains` is the fill test, `outline_points` the closed
+/// edge the stroke is measured from, and `bounds` the `[min_x, min_y, max_x, max_y]` extent of the fill, all in
+/// the shape's own coordinates, which `transform` places on the canvas.
+///
+/// Every shape goes through the same inverse transform: each pixel is sampled at its center and mapped back into
+/// the shape's coordinates for the fill test and the fill paint. Strokes are measured on the canvas, so they keep their width in
+/// pixels however the shape is scaled.
+fn paint_shape(
+    bmp: &mut Image,
+    bounds: [f64; 4],
+    contains: impl Fn(f64, f64) -> bool,
+    outline_points: &[[f64; 2]],
+    fill_paint: &Paint,
+    outline: &Outline,
+    transform: &Transform,
+) {
+    let Some(inverse) = transform.inverse() else {
+        return;
+    };
+    let bounds = transform.bounds(bounds);
+
+    let reach = match (outline.mode, outline.alignment) {
+        (PaintMode::Fill, _) | (_, StrokeAlignment::Inside) => 0.0,
+        (_, StrokeAlignment::Center) => outline.width / 2.0,
+        (_, StrokeAlignment::Outside) => outline.width,
+    };
+
+    let area = bounding_box(bmp, &[[bounds[0], bounds[1]], [bounds[2], bounds[3]]], reach);
+    let [start_x, start_y, end_x, end_y] = area;
+
+    let band = match outline.mode {
+        PaintMode::Fill => Vec::new(),
+        _ => {
+            let stroke_width = match outline.alignment {
+                StrokeAlignment::Center => outline.width / 2.0,
+                StrokeAlignment::Inside | StrokeAlignment::Outside => outline.width,
+            };
+            outline_band(area, &transform.apply_all(outline_points), stroke_width)
+        }
+    };
+    let band_width = (end_x - start_x) as usize;
+
+    for x in start_x..end_x {
+        for y in start_y..end_y {
+            let local = inverse.apply([x as f64 + 0.5, y as f64 + 0.5]);
+            let inside = contains(local[0], local[1]);
+
+            let stroked = outline.mode != PaintMode::Fill
+                && band[(y - start_y) as usize * band_width + (x - start_x) as usize]
+                && match outline.alignment {
+                    StrokeAlignment::Inside => inside,
+                    StrokeAlignment::Center => true,
+                    StrokeAlignment::Outside => !inside,
+                };
+
+            if stroked {
+                bmp.set_pixel(x, y, Pixel::new(outline.color[0], outline.color[1], outline.color[2]));
+            } else if inside && outline.mode != PaintMode::Stroke {
+                bmp.set_pixel(x, y, fill_paint.pixel_at_point(local));
+            }
+        }
+    }
+}
+
+/// Draws a line between `start` and `end` on the canvas. `transform` is what placed them there, and the paint is
+/// sampled back through it, the same as for filled shapes.
+fn draw_line(
+    bmp: &mut Image,
+    start: [f64; 2],
+    end: [f64; 2],
+    thickness: f64,
+    cap: LineCap,
+    line_paint: &Paint,
+    transform: &Transform,
+) {
+    let Some(inverse) = transform.inverse() else {
+        return;
+    };
+    // square caps reach out diagonally, so pad the bounding box by the full half-diagonal
+    let [start_x, start_y, end_x, end_y] = bounding_box(bmp, &[start, end], thickness * 0.75);
+
+    for x in start_x..end_x {
+        for y in start_y..end_y {
+            let center = [x as f64 + 0.5, y as f64 + 0.5];
+            if line_contains(start, end, thickness, cap, (center[0], center[1])) {
+                bmp.set_pixel(x, y, line_paint.pixel_at_point(inverse.apply(center)));
+            }
+        }
+    }
+}
+
+/// Blends `over` onto the canvas pixel at `x`, `y` by how much of the pixel a glyph covers.
+fn blend_coverage(bmp: &mut Image, x: u32, y: u32, coverage: f32, over: Pixel) {
+    let under = bmp.get_pixel(x, y);
+    let blend = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * coverage.min(1.0)).round() as u8;
+    bmp.set_pixel(x, y, Pixel::new(blend(under.r, over.r), blend(under.g, over.g), blend(under.b, over.b)));
+}
+
+/// Stripes need to be at least a pixel wide and to cycle through at least one color.
+fn check_stripes(spacing: u32, num_colors: u32) -> Result<(), PatternError> {
+    if spacing == 0 {
+        return Err(PatternError::invalid_parameter("spacing", "stripes must be at least 1 pixel wide"));
+    }
+    if num_colors == 0 {
+        return Err(PatternError::invalid_parameter("num_colors", "stripes need at least 1 color"));
+    }
+    Ok(())
+}
+
+impl BmpGenerator {
+    pub fn clear(width: u32, height: u32) -> Image {
+        Image::new(width, height)
+    }
+
+    /// Creates a new image covered entirely by `background`.
+    pub fn background(width: u32, height: u32, background: &Paint) -> Image {
+        let mut bmp = Image::new(width, height);
+
+        for (x, y) in bmp.coordinates() {
+            bmp.set_pixel(x, y, background.pixel_at(x, y));
+        }
+
+        bmp
+    }
+
+    /// Loads a BMP, PNG or JPEG file as an editable image.
+    pub fn open_image(path: &str) -> Result<Image, PatternError> {
+        let rgb = image::open(path)
+            .map_err(|source| PatternError::Open {
+                path: path.to_owned(),
+                source,
+            })?
+            .to_rgb8();
+
+        let mut bmp = Image::new(rgb.width(), rgb.height());
+        for (x, y, pixel) in rgb.enumerate_pixels() {
+            bmp.set_pixel(x, y, Pixel::new(pixel[0], pixel[1], pixel[2]));
+        }
+
+        Ok(bmp)
+    }
+
+    /// Saves `bmp` in the format given by the extension of `path`.
+    pub fn save_image(bmp: &Image, path: &str) -> Result<(), PatternError> {
+        let mut rgb = image::RgbImage::new(bmp.get_width(), bmp.get_height());
+        for (x, y) in bmp.coordinates() {
+            let pixel = bmp.get_pixel(x, y);
+            rgb.put_pixel(x, y, image::Rgb([pixel.r, pixel.g, pixel.b]));
+        }
+
+        rgb.save(path).map_err(|source| PatternError::Save {
+            path: path.to_owned(),
+            source,
+        })
+    }
+
+    /// Draws `layer` with its top left corner at `position`, scaled by `scale` using nearest neighbour
+    /// sampling so every source pixel stays a solid block, and then placed by `transform`.
+    pub fn add_image(bmp: &mut Image, layer: &Image, position: [f64; 2], scale: f64, transform: &Transform) {
+        let Some(inverse) = transform.inverse().filter(|_| scale > 0.0) else {
+            return;
+        };
+
+        let layer_end = [
+            position[0] + layer.get_width() as f64 * scale,
+            position[1] + layer.get_height() as f64 * scale,
+        ];
+        let bounds = transform.bounds([position[0], position[1], layer_end[0], layer_end[1]]);
+        let [start_x, start_y, end_x, end_y] =
+            bounding_box(bmp, &[[bounds[0], bounds[1]], [bounds[2] - 1.0, bounds[3] - 1.0]], 0.0);
+
+        for x in start_x..end_x {
+            for y in start_y..end_y {
+                let local = inverse.apply([x as f64 + 0.5, y as f64 + 0.5]);
+                let source_x = ((local[0] - position[0]) / scale).floor();
+                let source_y = ((local[1] - position[1]) / scale).floor();
+
+                if source_x >= 0.0
+                    && source_y >= 0.0
+                    && source_x < layer.get_width() as f64
+                    && source_y < layer.get_height() as f64
+                {
+                    bmp.set_pixel(x, y, layer.get_pixel(source_x as u32, source_y as u32));
+                }
+            }
+        }
+    }
+
+    /// Draws a rect rotated by `rotation` degrees about its center, and then placed by `transform`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_rect(
+        bmp: &mut Image,
+        rect_start: [f64; 2],
+        rect_end: [f64; 2],
+        rect_paint: &Paint,
+        rotation: f64,
+        corner_radii: [f64; 4],
+        outline: Outline,
+        transform: &Transform,
+    ) {
+        let (rect_start, rect_end) = ordered_corners(rect_start, rect_end);
+        let transform = Transform::rotate(rotation)
+            .around(rect_center(rect_start, rect_end))
+            .then(*transform);
+
+        paint_shape(
+            bmp,
+            [rect_start[0], rect_start[1], rect_end[0], rect_end[1]],
+            |x, y| rect_contains(rect_start, rect_end, corner_radii, (x, y)),
+            &rounded_rect_outline(rect_start, rect_end, 0.0, corner_radii),
+            rect_paint,
+            &outline,
+            &transform,
+        );
+    }
+
+    /// Draws an ellipse with radii `size` around `center`, rotated by `rotation` degrees and then placed by
+    /// `transform`.
+    pub fn add_ellipse(
+        bmp: &mut Image,
+        center: [f64; 2],
+        size: [f64; 2],
+        rotation: f64,
+        ellipse_paint: &Paint,
+        outline: Outline,
+        transform: &Transform,
+    ) {
+        let size = size.map(f64::abs);
+        let transform = Transform::rotate(rotation).around(center).then(*transform);
+
+        paint_shape(
+            bmp,
+            [center[0] - size[0], center[1] - size[1], center[0] + size[0], center[1] + size[1]],
+            |x, y| ellipse_contains(center, size, (x, y)),
+            &flatten_arc(center, size, 0.0, 360.0, 0.0),
+            ellipse_paint,
+            &outline,
+            &transform,
+        );
+    }
+
+    pub fn generate_stripes(width: u32, height: u32, spacing: u32, num_colors: u32) -> Result<Image, PatternError> {
+        check_stripes(spacing, num_colors)?;
+        let mut bmp = Image::new(width, height);
+
+        for (color_index, x) in (0..width).step_by(spacing as usize).enumerate() {
+            let color = stripe_color(color_index as u32 % num_colors);
+
+            // the last stripe can be cut off by the edge, and a stripe can be far wider than the canvas
+            for i in 0..spacing.min(width - x) {
+                for j in 0..height {
+                    bmp.set_pixel(i + x, j, Pixel::new(color[0], color[1], color[2]));
+                }
+            }
+        }
+
+        Ok(bmp)
+    }
+
+    pub fn generate_stripes_horizontal(width: u32, height: u32, spacing: u32, num_colors: u32) -> Result<Image, PatternError> {
+        check_stripes(spacing, num_colors)?;
+        let mut bmp = Image::new(width, height);
+
+        for (color_index, x) in (0..height).step_by(spacing as usize).enumerate() {
+            let color = stripe_color(color_index as u32 % num_colors);
+
+            for i in 0..spacing.min(height - x) {
+                for j in 0..width {
+                    bmp.set_pixel(j, x + i, Pixel::new(color[0], color[1], color[2]));
+                }
+            }
+        }
+
+        Ok(bmp)
+    }
+
+    /// Creates an image where each channel is given by an expression of the pixel position, `time` and `frame`,
+    /// evaluated on all cores a row at a time. Results are clamped to `0..=255`.
+    pub fn generate_expression(width: u32, height: u32, channels: &[Expression; 3], time: f64, frame: u64) -> Image {
+        let rows: Vec<Vec<[u8; 3]>> = (0..height)
+            .into_par_iter()
+            .map(|y| {
+                let mut stack = Vec::new();
+                (0..width)
+                    .map(|x| {
+                        let variables = Variables {
+                            x: x as f64,
+                            y: y as f64,
+                            width: width as f64,
+                            height: height as f64,
+                            time,
+                            frame: frame as f64,
+                        };
+                        [0, 1, 2].map(|channel| channels[channel].evaluate_with(&variables, &mut stack).clamp(0.0, 255.0) as u8)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut bmp = Image::new(width, height);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, color) in row.iter().enumerate() {
+                bmp.set_pixel(x as u32, y as u32, Pixel::new(color[0], color[1], color[2]));
+            }
+        }
+
+        bmp
+    }
+
+    pub fn add_polygon(
+        bmp: &mut Image,
+        points: &[[f64; 2]],
+        fill_rule: FillRule,
+        polygon_paint: &Paint,
+        outline: Outline,
+        transform: &Transform,
+    ) {
+        if points.len() < 3 {
+            return;
+        }
+
+        paint_shape(
+            bmp,
+            points_bounds(points),
+            |x, y| polygon_contains(points, fill_rule, (x, y)),
+            points,
+            polygon_paint,
+            &outline,
+            transform,
+        );
+    }
+
+    pub fn add_triangle(
+        bmp: &mut Image,
+        points: [[f64; 2]; 3],
+        triangle_paint: &Paint,
+        outline: Outline,
+        transform: &Transform,
+    ) {
+        BmpGenerator::add_polygon(bmp, &points, FillRule::NonZero, triangle_paint, outline, transform)
+    }
+
+    /// Draws `text` in the default egui font with the top left of its first line at `position`, `size` pixels
+    /// tall, then placed by `transform`. Glyph edges are antialiased by blending with what is already on the canvas.
+    pub fn add_text(
+        bmp: &mut Image,
+        text: &str,
+        position: [f64; 2],
+        size: f64,
+        text_paint: &Paint,
+        transform: &Transform,
+    ) -> Result<(), PatternError> {
+        if !(0.0..=MAX_TEXT_SIZE).contains(&size) {
+            let message = format!("text must be between 0 and {MAX_TEXT_SIZE} pixels tall");
+            return Err(PatternError::invalid_parameter("size", message));
+        }
+        let Some(inverse) = transform.inverse() else {
+            return Ok(());
+        };
+
+        // moved text is laid out where it ends up, so its glyphs line up with the canvas pixels. Anything else is
+        // laid out in place and each glyph is sampled back through the transform, the same as shapes.
+        let offset = transform.translation();
+        let position = match offset {
+            Some(offset) => [position[0] + offset[0], position[1] + offset[1]],
+            None => position,
+        };
+
+        // the font data is embedded in egui, so load it once and keep it for every later call
+        static FONTS: OnceLock<FontDefinitions> = OnceLock::new();
+        let Some(font) = FONTS
+            .get_or_init(FontDefinitions::default)
+            .font_data
+            .get("Ubuntu-Light")
+            .and_then(|data| FontRef::try_from_slice_and_index(&data.font, data.index).ok())
+        else {
+            return Ok(());
+        };
+        let font = font.as_scaled(PxScale::from(size as f32));
+
+        for (line_index, line) in text.lines().enumerate() {
+            let baseline = position[1] as f32 + font.ascent() + line_index as f32 * (font.height() + font.line_gap());
+            let mut caret = position[0] as f32;
+            let mut previous = None;
+
+            for c in line.chars() {
+                let id = font.glyph_id(c);
+                if let Some(previous) = previous {
+                    caret += font.kern(previous, id);
+                }
+                previous = Some(id);
+
+                let glyph = id.with_scale_and_position(font.scale(), point(caret, baseline));
+                caret += font.h_advance(id);
+
+                let Some(outline) = font.outline_glyph(glyph) else {
+                    continue;
+                };
+                let bounds = outline.px_bounds();
+
+                if let Some(offset) = offset {
+                    outline.draw(|glyph_x, glyph_y, coverage| {
+                        let x = bounds.min.x as i64 + glyph_x as i64;
+                        let y = bounds.min.y as i64 + glyph_y as i64;
+                        if x < 0 || y < 0 || x >= bmp.get_width() as i64 || y >= bmp.get_height() as i64 {
+                            return;
+                        }
+
+                        let (x, y) = (x as u32, y as u32);
+                        let over = text_paint.pixel_at_point([x as f64 + 0.5 - offset[0], y as f64 + 0.5 - offset[1]]);
+                        blend_coverage(bmp, x, y, coverage, over);
+                    });
+                    continue;
+                }
+
+                let glyph_width = bounds.width() as usize;
+                let mut coverages = vec![0.0; glyph_width * bounds.height() as usize];
+                outline.draw(|glyph_x, glyph_y, coverage| {
+                    if let Some(cell) = coverages.get_mut(glyph_y as usize * glyph_width + glyph_x as usize) {
+                        *cell = coverage;
+                    }
+                });
+
+                let glyph_bounds = [bounds.min.x, bounds.min.y, bounds.max.x, bounds.max.y].map(|v| v as f64);
+                let placed = transform.bounds(glyph_bounds);
+                let corners = [[placed[0], placed[1]], [placed[2], placed[3]]];
+                let [start_x, start_y, end_x, end_y] = bounding_box(bmp, &corners, 0.0);
+                for x in start_x..end_x {
+                    for y in start_y..end_y {
+                        let local = inverse.apply([x as f64 + 0.5, y as f64 + 0.5]);
+                        let glyph_x = (local[0] - glyph_bounds[0]).floor();
+                        let glyph_y = (local[1] - glyph_bounds[1]).floor();
+                        if glyph_x < 0.0 || glyph_y < 0.0 || glyph_x >= glyph_width as f64 {
+                            continue;
+                        }
+                        match coverages.get(glyph_y as usize * glyph_width + glyph_x as usize) {
+                            Some(&coverage) if coverage > 0.0 => {
+                                blend_coverage(bmp, x, y, coverage, text_paint.pixel_at_point(local))
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn add_line(
+        bmp: &mut Image,
+        start: [f64; 2],
+        end: [f64; 2],
+        thickness: f64,
+        cap: LineCap,
+        line_paint: &Paint,
+        transform: &Transform,
+    ) {
+        draw_line(bmp, transform.apply(start), transform.apply(end), thickness, cap, line_paint, transform);
+    }
+
+    /// Draws each segment of an open path as a line with round caps, so consecutive segments join smoothly.
+    /// `transform` moves the path, but the thickness stays in canvas pixels.
+    pub fn stroke_path(
+        bmp: &mut Image,
+        points: &[[f64; 2]],
+        thickness: f64,
+        path_paint: &Paint,
+        transform: &Transform,
+    ) {
+        for segment in transform.apply_all(points).windows(2) {
+            draw_line(bmp, segment[0], segment[1], thickness, LineCap::Round, path_paint, transform);
+        }
+    }
+
+    /// Draws a quadratic (3 control points) or cubic (4 control points) Bezier curve, rotated about the
+    /// center of its control points and then placed by `transform`. `outline` only applies when the curve is filled.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_bezier(
+        bmp: &mut Image,
+        control_points: &[[f64; 2]],
+        rotation: f64,
+        thickness: f64,
+        filled: bool,
+        bezier_paint: &Paint,
+        outline: Outline,
+        transform: &Transform,
+    ) {
+        let points = match *control_points {
+            [p0, p1, p2] => flatten_quadratic(p0, p1, p2),
+            [p0, p1, p2, p3] => flatten_cubic(p0, p1, p2, p3),
+            _ => return,
+        };
+
+        let count = control_points.len() as f64;
+        let pivot = [
+            control_points.iter().map(|p| p[0]).sum::<f64>() / count,
+            control_points.iter().map(|p| p[1]).sum::<f64>() / count,
+        ];
+        let transform = Transform::rotate(rotation).around(pivot).then(*transform);
+
+        match filled {
+            true => BmpGenerator::add_polygon(bmp, &points, FillRule::NonZero, bezier_paint, outline, &transform),
+            false => BmpGenerator::stroke_path(bmp, &points, thickness, bezier_paint, &transform),
+        }
+    }
+
+    /// Draws an elliptical arc rotated about its center and then placed by `transform`. Filled arcs are drawn as a
+    /// pie slice back to `center`, and only those use `outline`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_arc(
+        bmp: &mut Image,
+        center: [f64; 2],
+        radii: [f64; 2],
+        start_angle: f64,
+        end_angle: f64,
+        rotation: f64,
+        thickness: f64,
+        filled: bool,
+        arc_paint: &Paint,
+        outline: Outline,
+        transform: &Transform,
+    ) {
+        let mut points = flatten_arc(center, radii, start_angle, end_angle, 0.0);
+        let transform = Transform::rotate(rotation).around(center).then(*transform);
+
+        match filled {
+            true => {
+                points.push(center);
+                BmpGenerator::add_polygon(bmp, &points, FillRule::NonZero, arc_paint, outline, &transform)
+            }
+            false => BmpGenerator::stroke_path(bmp, &points, thickness, arc_paint, &transform),
+        }
+    }
+}
@@ -0,0 +1,589 @@
+//! Builders for the operations a [`Canvas`](super::canvas::Canvas) can draw.
+//!
+//! Each builder starts from the shape's position and size and fills everything else with a default, so only
+//! what differs needs to be spelled out:
+//!
+//! ```
+//! use test_pattern_generator::{Line, LineCap, Rect};
+//!
+//! let box_ = Rect::new([10.0, 10.0], [90.0, 50.0]).paint([255, 0, 0]).rotation(15.0);
+//! let rule = Line::new([0.0, 100.0], [200.0, 100.0]).thickness(3.0).cap(LineCap::Round);
+//! ```
+//!
+//! Shapes default to a white fill with no stroke.
+
+use std::collections::BTreeMap;
+
+use super::array::Arrangement;
+use super::bmp_generator::{FillRule, LineCap, Outline};
+use super::description::Operation;
+use super::generators::ParameterValue;
+use super::paint::Paint;
+use super::transform::Transform;
+
+const WHITE: [u8; 3] = [255, 255, 255];
+
+/// A rectangle between two corners, rotated about its center by `rotation` degrees.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rect {
+    start: [f64; 2],
+    end: [f64; 2],
+    paint: Paint,
+    rotation: f64,
+    corner_radii: [f64; 4],
+    outline: Outline,
+}
+
+impl Rect {
+    pub fn new(start: [f64; 2], end: [f64; 2]) -> Self {
+        Self {
+            start,
+            end,
+            paint: Paint::Solid(WHITE),
+            rotation: 0.0,
+            corner_radii: [0.0; 4],
+            outline: Outline::default(),
+        }
+    }
+
+    pub fn paint(mut self, paint: impl Into<Paint>) -> Self {
+        self.paint = paint.into();
+        self
+    }
+
+    pub fn rotation(mut self, degrees: f64) -> Self {
+        self.rotation = degrees;
+        self
+    }
+
+    /// Rounds the corners: top-left, top-right, bottom-right, bottom-left, before rotation.
+    pub fn corner_radii(mut self, radii: [f64; 4]) -> Self {
+        self.corner_radii = radii;
+        self
+    }
+
+    pub fn outline(mut self, outline: Outline) -> Self {
+        self.outline = outline;
+        self
+    }
+}
+
+impl From<Rect> for Operation {
+    fn from(rect: Rect) -> Self {
+        Operation::Rect {
+            start: rect.start,
+            end: rect.end,
+            paint: rect.paint,
+            rotation: rect.rotation,
+            corner_radii: rect.corner_radii,
+            outline: rect.outline,
+        }
+    }
+}
+
+/// An ellipse given by its center and its radius along each axis.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ellipse {
+    center: [f64; 2],
+    size: [f64; 2],
+    rotation: f64,
+    paint: Paint,
+    outline: Outline,
+}
+
+impl Ellipse {
+    pub fn new(center: [f64; 2], size: [f64; 2]) -> Self {
+        Self {
+            center,
+            size,
+            rotation: 0.0,
+            paint: Paint::Solid(WHITE),
+            outline: Outline::default(),
+        }
+    }
+
+    pub fn paint(mut self, paint: impl Into<Paint>) -> Self {
+        self.paint = paint.into();
+        self
+    }
+
+    pub fn rotation(mut self, degrees: f64) -> Self {
+        self.rotation = degrees;
+        self
+    }
+
+    pub fn outline(mut self, outline: Outline) -> Self {
+        self.outline = outline;
+        self
+    }
+}
+
+impl From<Ellipse> for Operation {
+    fn from(ellipse: Ellipse) -> Self {
+        Operation::Ellipse {
+            center: ellipse.center,
+            size: ellipse.size,
+            rotation: ellipse.rotation,
+            paint: ellipse.paint,
+            outline: ellipse.outline,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Triangle {
+    points: [[f64; 2]; 3],
+    paint: Paint,
+    outline: Outline,
+}
+
+impl Triangle {
+    pub fn new(points: [[f64; 2]; 3]) -> Self {
+        Self {
+            points,
+            paint: Paint::Solid(WHITE),
+            outline: Outline::default(),
+        }
+    }
+
+    pub fn paint(mut self, paint: impl Into<Paint>) -> Self {
+        self.paint = paint.into();
+        self
+    }
+
+    pub fn outline(mut self, outline: Outline) -> Self {
+        self.outline = outline;
+        self
+    }
+}
+
+impl From<Triangle> for Operation {
+    fn from(triangle: Triangle) -> Self {
+        Operation::Triangle {
+            points: triangle.points,
+            paint: triangle.paint,
+            outline: triangle.outline,
+        }
+    }
+}
+
+/// A closed polygon through `points`, filled with the non-zero rule unless told otherwise.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon {
+    points: Vec<[f64; 2]>,
+    fill_rule: FillRule,
+    paint: Paint,
+    outline: Outline,
+}
+
+impl Polygon {
+    pub fn new(points: impl Into<Vec<[f64; 2]>>) -> Self {
+        Self {
+            points: points.into(),
+            fill_rule: FillRule::NonZero,
+            paint: Paint::Solid(WHITE),
+            outline: Outline::default(),
+        }
+    }
+
+    pub fn fill_rule(mut self, fill_rule: FillRule) -> Self {
+        self.fill_rule = fill_rule;
+        self
+    }
+
+    pub fn paint(mut self, paint: impl Into<Paint>) -> Self {
+        self.paint = paint.into();
+        self
+    }
+
+    pub fn outline(mut self, outline: Outline) -> Self {
+        self.outline = outline;
+        self
+    }
+}
+
+impl From<Polygon> for Operation {
+    fn from(polygon: Polygon) -> Self {
+        Operation::Polygon {
+            points: polygon.points,
+            fill_rule: polygon.fill_rule,
+            paint: polygon.paint,
+            outline: polygon.outline,
+        }
+    }
+}
+
+/// A straight line, one pixel thick with butt caps unless told otherwise.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+    start: [f64; 2],
+    end: [f64; 2],
+    thickness: f64,
+    cap: LineCap,
+    paint: Paint,
+}
+
+impl Line {
+    pub fn new(start: [f64; 2], end: [f64; 2]) -> Self {
+        Self {
+            start,
+            end,
+            thickness: 1.0,
+            cap: LineCap::Butt,
+            paint: Paint::Solid(WHITE),
+        }
+    }
+
+    pub fn thickness(mut self, thickness: f64) -> Self {
+        self.thickness = thickness;
+        self
+    }
+
+    pub fn cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn paint(mut self, paint: impl Into<Paint>) -> Self {
+        self.paint = paint.into();
+        self
+    }
+}
+
+impl From<Line> for Operation {
+    fn from(line: Line) -> Self {
+        Operation::Line {
+            start: line.start,
+            end: line.end,
+            thickness: line.thickness,
+            cap: line.cap,
+            paint: line.paint,
+        }
+    }
+}
+
+/// A quadratic (3 control points) or cubic (4 control points) Bezier curve, stroked unless filled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bezier {
+    control_points: Vec<[f64; 2]>,
+    rotation: f64,
+    thickness: f64,
+    filled: bool,
+    paint: Paint,
+    outline: Outline,
+}
+
+impl Bezier {
+    pub fn new(control_points: impl Into<Vec<[f64; 2]>>) -> Self {
+        Self {
+            control_points: control_points.into(),
+            rotation: 0.0,
+            thickness: 1.0,
+            filled: false,
+            paint: Paint::Solid(WHITE),
+            outline: Outline::default(),
+        }
+    }
+
+    pub fn rotation(mut self, degrees: f64) -> Self {
+        self.rotation = degrees;
+        self
+    }
+
+    pub fn thickness(mut self, thickness: f64) -> Self {
+        self.thickness = thickness;
+        self
+    }
+
+    pub fn filled(mut self, filled: bool) -> Self {
+        self.filled = filled;
+        self
+    }
+
+    pub fn paint(mut self, paint: impl Into<Paint>) -> Self {
+        self.paint = paint.into();
+        self
+    }
+
+    /// Only used when the curve is filled.
+    pub fn outline(mut self, outline: Outline) -> Self {
+        self.outline = outline;
+        self
+    }
+}
+
+impl From<Bezier> for Operation {
+    fn from(bezier: Bezier) -> Self {
+        Operation::Bezier {
+            control_points: bezier.control_points,
+            rotation: bezier.rotation,
+            thickness: bezier.thickness,
+            filled: bezier.filled,
+            paint: bezier.paint,
+            outline: bezier.outline,
+        }
+    }
+}
+
+/// An elliptical arc from `angles[0]` to `angles[1]` degrees, stroked unless filled as a pie slice.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Arc {
+    center: [f64; 2],
+    radii: [f64; 2],
+    angles: [f64; 2],
+    rotation: f64,
+    thickness: f64,
+    filled: bool,
+    paint: Paint,
+    outline: Outline,
+}
+
+impl Arc {
+    pub fn new(center: [f64; 2], radii: [f64; 2], angles: [f64; 2]) -> Self {
+        Self {
+            center,
+            radii,
+            angles,
+            rotation: 0.0,
+            thickness: 1.0,
+            filled: false,
+            paint: Paint::Solid(WHITE),
+            outline: Outline::default(),
+        }
+    }
+
+    pub fn rotation(mut self, degrees: f64) -> Self {
+        self.rotation = degrees;
+        self
+    }
+
+    pub fn thickness(mut self, thickness: f64) -> Self {
+        self.thickness = thickness;
+        self
+    }
+
+    pub fn filled(mut self, filled: bool) -> Self {
+        self.filled = filled;
+        self
+    }
+
+    pub fn paint(mut self, paint: impl Into<Paint>) -> Self {
+        self.paint = paint.into();
+        self
+    }
+
+    /// Only used when the arc is filled.
+    pub fn outline(mut self, outline: Outline) -> Self {
+        self.outline = outline;
+        self
+    }
+}
+
+impl From<Arc> for Operation {
+    fn from(arc: Arc) -> Self {
+        Operation::Arc {
+            center: arc.center,
+            radii: arc.radii,
+            angles: arc.angles,
+            rotation: arc.rotation,
+            thickness: arc.thickness,
+            filled: arc.filled,
+            paint: arc.paint,
+            outline: arc.outline,
+        }
+    }
+}
+
+/// Text in the default font, 16 pixels tall unless told otherwise.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Text {
+    text: String,
+    position: [f64; 2],
+    size: f64,
+    paint: Paint,
+}
+
+impl Text {
+    pub fn new(text: impl Into<String>, position: [f64; 2]) -> Self {
+        Self {
+            text: text.into(),
+            position,
+            size: 16.0,
+            paint: Paint::Solid(WHITE),
+        }
+    }
+
+    pub fn size(mut self, size: f64) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn paint(mut self, paint: impl Into<Paint>) -> Self {
+        self.paint = paint.into();
+        self
+    }
+}
+
+impl From<Text> for Operation {
+    fn from(text: Text) -> Self {
+        Operation::Text {
+            text: text.text,
+            position: text.position,
+            size: text.size,
+            paint: text.paint,
+        }
+    }
+}
+
+/// A generator from the [`Registry`](super::generators::Registry) rendered on a new canvas. Parameters that
+/// aren't set keep the generator's defaults.
+///
+/// ```
+/// use test_pattern_generator::Generate;
+///
+/// let stripes = Generate::new("Stripes", 1920, 1080).parameter("spacing", 8).parameter("horizontal", true);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Generate {
+    generator: String,
+    width: u32,
+    height: u32,
+    parameters: BTreeMap<String, ParameterValue>,
+}
+
+impl Generate {
+    pub fn new(generator: impl Into<String>, width: u32, height: u32) -> Self {
+        Self {
+            generator: generator.into(),
+            width,
+            height,
+            parameters: BTreeMap::new(),
+        }
+    }
+
+    pub fn parameter(mut self, name: impl Into<String>, value: impl Into<ParameterValue>) -> Self {
+        self.parameters.insert(name.into(), value.into());
+        self
+    }
+}
+
+impl From<Generate> for Operation {
+    fn from(generate: Generate) -> Self {
+        Operation::Generate {
+            generator: generate.generator,
+            width: generate.width,
+            height: generate.height,
+            parameters: generate.parameters,
+        }
+    }
+}
+
+/// Operations drawn together through one [`Transform`], on top of any rotation of their own.
+///
+/// ```
+/// use test_pattern_generator::{Group, Rect, Transform};
+///
+/// // two bars turned together about the middle of a 200 by 200 canvas
+/// let cross = Group::new(Transform::rotate(45.0).around([100.0, 100.0]))
+///     .with(Rect::new([90.0, 20.0], [110.0, 180.0]))
+///     .with(Rect::new([20.0, 90.0], [180.0, 110.0]));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Group {
+    transform: Transform,
+    operations: Vec<Operation>,
+}
+
+impl Group {
+    pub fn new(transform: Transform) -> Self {
+        Self {
+            transform,
+            operations: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, operation: impl Into<Operation>) -> Self {
+        self.operations.push(operation.into());
+        self
+    }
+}
+
+impl From<Group> for Operation {
+    fn from(group: Group) -> Self {
+        Operation::Group {
+            transform: group.transform,
+            operations: group.operations,
+        }
+    }
+}
+
+/// Copies of an operation in a row, a grid or a circle, optionally cycling through colors.
+///
+/// ```
+/// use test_pattern_generator::{Array, Ellipse};
+///
+/// // a 16 by 9 grid of dots alternating red and blue
+/// let dots = Array::grid(Ellipse::new([60.0, 60.0], [10.0, 10.0]), 16, 9, [120.0, 120.0])
+///     .colors([[255, 0, 0], [0, 0, 255]]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Array {
+    arrangement: Arrangement,
+    colors: Vec<[u8; 3]>,
+    operation: Operation,
+}
+
+impl Array {
+    pub fn new(operation: impl Into<Operation>, arrangement: Arrangement) -> Self {
+        Self {
+            arrangement,
+            colors: Vec::new(),
+            operation: operation.into(),
+        }
+    }
+
+    pub fn linear(operation: impl Into<Operation>, count: u32, step: [f64; 2]) -> Self {
+        Self::new(operation, Arrangement::Linear { count, step })
+    }
+
+    pub fn grid(operation: impl Into<Operation>, columns: u32, rows: u32, spacing: [f64; 2]) -> Self {
+        Self::new(operation, Arrangement::Grid { columns, rows, spacing })
+    }
+
+    pub fn radial(operation: impl Into<Operation>, count: u32, center: [f64; 2], angle_step: f64) -> Self {
+        Self::new(
+            operation,
+            Arrangement::Radial {
+                count,
+                center,
+                angle_step,
+                radius_step: 0.0,
+            },
+        )
+    }
+
+    /// Pushes each copy of a radial array this much further from the center than the last. Does nothing for the
+    /// other arrangements.
+    pub fn radius_step(mut self, step: f64) -> Self {
+        if let Arrangement::Radial { radius_step, .. } = &mut self.arrangement {
+            *radius_step = step;
+        }
+        self
+    }
+
+    /// Paints the copies with `colors` in turn.
+    pub fn colors(mut self, colors: impl IntoIterator<Item = [u8; 3]>) -> Self {
+        self.colors = colors.into_iter().collect();
+        self
+    }
+}
+
+impl From<Array> for Operation {
+    fn from(array: Array) -> Self {
+        Operation::Array {
+            arrangement: array.arrangement,
+            colors: array.colors,
+            operation: Box::new(array.operation),
+        }
+    }
+}
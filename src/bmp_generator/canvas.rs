@@ -0,0 +1,172 @@
+//! An image to draw patterns on, which remembers how it was drawn.
+
+use std::collections::BTreeMap;
+
+use bmp::Image;
+
+use super::bmp_generator::BmpGenerator;
+use super::description::{check_canvas_size, Operation, PatternDescription};
+use super::error::PatternError;
+use super::generators::{ParameterValue, PatternGenerator};
+use super::metadata::{self, Metadata};
+use super::symmetry::Symmetry;
+
+/// A canvas and the operations drawn on it since it was created.
+///
+/// Operations that replace the whole canvas, like [`Operation::Background`] or a generator, also resize it and
+/// start the recorded pattern over, the same as in the app.
+#[derive(Clone, Debug)]
+pub struct Canvas {
+    image: Image,
+    operations: Vec<Operation>,
+    symmetry: Option<Symmetry>,
+}
+
+impl Canvas {
+    /// A black `width` by `height` canvas.
+    ///
+    /// # Panics
+    ///
+    /// If the size is empty or has more than [`MAX_CANVAS_PIXELS`] pixels; use [`Canvas::try_new`] for sizes
+    /// from users.
+    ///
+    /// [`MAX_CANVAS_PIXELS`]: super::description::MAX_CANVAS_PIXELS
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::try_new(width, height).expect("canvas size out of range")
+    }
+
+    /// A black `width` by `height` canvas, or an error if the size is empty or too large for [`Canvas::new`].
+    pub fn try_new(width: u32, height: u32) -> Result<Self, PatternError> {
+        check_canvas_size(width, height)?;
+        Ok(Self {
+            image: BmpGenerator::clear(width, height),
+            operations: Vec::new(),
+            symmetry: None,
+        })
+    }
+
+    /// Draws `description` again from the start.
+    pub fn from_description(description: &PatternDescription) -> Result<Self, PatternError> {
+        let mut canvas = Canvas::try_new(description.width, description.height)?;
+        for operation in &description.operations {
+            canvas.draw(operation.clone())?;
+        }
+        Ok(canvas)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.image.get_width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.image.get_height()
+    }
+
+    /// Draws `operation`, which can be an [`Operation`] or any of the builders such as [`Rect`](super::builders::Rect),
+    /// and records it. Returns the canvas again so draws can be chained with `?`.
+    pub fn draw(&mut self, operation: impl Into<Operation>) -> Result<&mut Self, PatternError> {
+        let operation = match (operation.into(), self.symmetry) {
+            (operation, Some(symmetry)) if operation.is_shape() => Operation::Symmetric {
+                symmetry,
+                operation: Box::new(operation),
+            },
+            (operation, _) => operation,
+        };
+        operation.apply(&mut self.image)?;
+
+        if operation.creates_canvas() {
+            self.operations.clear();
+        }
+        self.operations.push(operation);
+
+        Ok(self)
+    }
+
+    /// Copies every shape drawn from now on with `symmetry`, or stops copying them with `None`.
+    ///
+    /// ```
+    /// use test_pattern_generator::{Canvas, Rect, Symmetry};
+    ///
+    /// let mut canvas = Canvas::new(1920, 1080);
+    /// canvas.set_symmetry(Some(Symmetry::Both));
+    /// // a marker in the top left corner, and so in all four
+    /// canvas.draw(Rect::new([0.0, 0.0], [40.0, 40.0]))?;
+    /// assert_eq!(canvas.pixel(1900, 1060), [255, 255, 255]);
+    /// # Ok::<(), test_pattern_generator::PatternError>(())
+    /// ```
+    pub fn set_symmetry(&mut self, symmetry: Option<Symmetry>) -> &mut Self {
+        self.symmetry = symmetry;
+        self
+    }
+
+    /// Renders `generator`, which doesn't have to be one of the built in ones, with `parameters` at the size of the
+    /// canvas. Parameters that are missing keep their defaults.
+    pub fn generate(
+        &mut self,
+        generator: &dyn PatternGenerator,
+        parameters: &BTreeMap<String, ParameterValue>,
+    ) -> Result<&mut Self, PatternError> {
+        self.draw(generator.operation(self.width(), self.height(), parameters))
+    }
+
+    /// The color of the pixel at `x`, `y`.
+    ///
+    /// # Panics
+    ///
+    /// If the pixel is outside the canvas.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        let pixel = self.image.get_pixel(x, y);
+        [pixel.r, pixel.g, pixel.b]
+    }
+
+    /// The pixels row by row from the top left, three bytes each.
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        let mut buffer = Vec::with_capacity(self.width() as usize * self.height() as usize * 3);
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                buffer.extend(self.pixel(x, y));
+            }
+        }
+        buffer
+    }
+
+    /// The pixels row by row from the top left, four bytes each with the alpha fully opaque.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let mut buffer = Vec::with_capacity(self.width() as usize * self.height() as usize * 4);
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let [r, g, b] = self.pixel(x, y);
+                buffer.extend([r, g, b, 255]);
+            }
+        }
+        buffer
+    }
+
+    /// The canvas as an [`image`] buffer, for encoding or comparing it with the `image` crate.
+    pub fn to_rgb_image(&self) -> image::RgbImage {
+        image::RgbImage::from_raw(self.width(), self.height(), self.to_rgb8()).expect("buffer matches the canvas size")
+    }
+
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    pub fn into_image(self) -> Image {
+        self.image
+    }
+
+    /// Everything needed to draw the canvas again.
+    pub fn description(&self) -> PatternDescription {
+        PatternDescription {
+            width: self.width(),
+            height: self.height(),
+            operations: self.operations.clone(),
+        }
+    }
+
+    /// Saves the canvas in the format given by the extension of `path`, with the pattern embedded as metadata
+    /// in PNG and TIFF files.
+    pub fn save(&self, path: &str) -> Result<(), PatternError> {
+        metadata::save_with_metadata(&self.image, &Metadata::new(self.description()), path)
+    }
+}
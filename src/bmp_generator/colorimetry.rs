@@ -0,0 +1,99 @@
+//! Color spaces described by their primaries, and converting colors between them.
+
+/// The CIE 1931 `x, y` coordinates of a color space's red, green and blue primaries and its white point.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Chromaticity {
+    pub red: [f64; 2],
+    pub green: [f64; 2],
+    pub blue: [f64; 2],
+    pub white: [f64; 2],
+}
+
+const D65: [f64; 2] = [0.3127, 0.3290];
+
+impl Chromaticity {
+    /// BT.709, which sRGB shares.
+    pub const BT709: Chromaticity = Chromaticity {
+        red: [0.640, 0.330],
+        green: [0.300, 0.600],
+        blue: [0.150, 0.060],
+        white: D65,
+    };
+
+    /// DCI-P3 with the DCI white point, as in cinema projection.
+    pub const DCI_P3: Chromaticity = Chromaticity {
+        red: [0.680, 0.320],
+        green: [0.265, 0.690],
+        blue: [0.150, 0.060],
+        white: [0.314, 0.351],
+    };
+
+    /// P3 primaries with a D65 white point, as in most wide gamut displays.
+    pub const DISPLAY_P3: Chromaticity = Chromaticity {
+        white: D65,
+        ..Chromaticity::DCI_P3
+    };
+
+    pub const BT2020: Chromaticity = Chromaticity {
+        red: [0.708, 0.292],
+        green: [0.170, 0.797],
+        blue: [0.131, 0.046],
+        white: D65,
+    };
+
+    /// The matrix taking linear RGB in this space to CIE XYZ, with the white point at a luminance of 1, or `None`
+    /// when the primaries don't span a space, such as when they all lie on one line.
+    pub fn rgb_to_xyz(&self) -> Option<[[f64; 3]; 3]> {
+        let xyz = |[x, y]: [f64; 2]| [x / y, 1.0, (1.0 - x - y) / y];
+        let [r, g, b] = [xyz(self.red), xyz(self.green), xyz(self.blue)];
+        let primaries = [[r[0], g[0], b[0]], [r[1], g[1], b[1]], [r[2], g[2], b[2]]];
+
+        // scale each primary so that full red, green and blue together make the white point
+        let scale = multiply_vector(&invert(&primaries)?, xyz(self.white));
+        let matrix = primaries.map(|row| [row[0] * scale[0], row[1] * scale[1], row[2] * scale[2]]);
+        matrix.iter().flatten().all(|value| value.is_finite()).then_some(matrix)
+    }
+
+    /// The matrix taking linear RGB in this space to linear RGB in `target`, or `None` when either space is
+    /// degenerate. Colors outside the target's gamut come out below 0 or above 1.
+    pub fn conversion_to(&self, target: &Chromaticity) -> Option<[[f64; 3]; 3]> {
+        let to_xyz = self.rgb_to_xyz()?;
+        let from_xyz = invert(&target.rgb_to_xyz()?)?;
+        Some(multiply(&from_xyz, &to_xyz))
+    }
+
+    /// The red, green and blue weights that make up luminance in this space, as used for luma.
+    pub fn luminance_weights(&self) -> Option<[f64; 3]> {
+        self.rgb_to_xyz().map(|matrix| matrix[1])
+    }
+}
+
+/// `matrix` times the column vector `vector`.
+pub fn multiply_vector(matrix: &[[f64; 3]; 3], vector: [f64; 3]) -> [f64; 3] {
+    matrix.map(|row| row[0] * vector[0] + row[1] * vector[1] + row[2] * vector[2])
+}
+
+fn multiply(a: &[[f64; 3]; 3], b: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let mut product = [[0.0; 3]; 3];
+    for (row, out) in product.iter_mut().enumerate() {
+        for (column, value) in out.iter_mut().enumerate() {
+            *value = (0..3).map(|i| a[row][i] * b[i][column]).sum();
+        }
+    }
+    product
+}
+
+fn invert(m: &[[f64; 3]; 3]) -> Option<[[f64; 3]; 3]> {
+    let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let adjugate = [
+        [cofactor(1, 2, 1, 2), -cofactor(0, 2, 1, 2), cofactor(0, 1, 1, 2)],
+        [-cofactor(1, 2, 0, 2), cofactor(0, 2, 0, 2), -cofactor(0, 1, 0, 2)],
+        [cofactor(1, 2, 0, 1), -cofactor(0, 2, 0, 1), cofactor(0, 1, 0, 1)],
+    ];
+    let determinant = m[0][0] * adjugate[0][0] + m[0][1] * adjugate[1][0] + m[0][2] * adjugate[2][0];
+    if determinant.abs() < 1e-12 || !determinant.is_finite() {
+        return None;
+    }
+
+    Some(adjugate.map(|row| row.map(|value| value / determinant)))
+}
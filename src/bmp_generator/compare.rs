@@ -0,0 +1,290 @@
+use bmp::{Image, Pixel};
+
+/// Differences between a reference image and a captured frame, measured over the region they share.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comparison {
+    /// Size of the compared region.
+    pub width: u32,
+    pub height: u32,
+    pub max_error: [u8; 3],
+    pub mean_error: [f64; 3],
+    pub differing_pixels: u64,
+    /// Peak signal-to-noise ratio in dB over all channels, infinite for identical images and 0 when there was
+    /// nothing to compare.
+    pub psnr: f64,
+    /// Mean structural similarity of the luma, 1.0 for identical images and 0 when there was nothing to compare.
+    pub ssim: f64,
+}
+
+impl Comparison {
+    /// Whether the images match exactly over a region that isn't empty.
+    pub fn is_identical(&self) -> bool {
+        self.width > 0 && self.height > 0 && self.max_error == [0, 0, 0]
+    }
+}
+
+/// Checks that the part of the reference being compared, `crop` or the whole image, lies inside `reference` and,
+/// shifted by `offset`, inside `captured`. Without this the comparison would shrink to where the two overlap, and
+/// a frame missing most of the picture could still pass.
+pub fn check_region(reference: &Image, captured: &Image, offset: [i32; 2], crop: Option<[u32; 4]>) -> Result<(), String> {
+    let [x, y, width, height] = crop.unwrap_or([0, 0, reference.get_width(), reference.get_height()]);
+    let (x, y, width, height) = (x as i64, y as i64, width as i64, height as i64);
+
+    if x + width > reference.get_width() as i64 || y + height > reference.get_height() as i64 {
+        return Err(format!(
+            "the region {x},{y} {width}x{height} reaches outside the {}x{} reference",
+            reference.get_width(),
+            reference.get_height()
+        ));
+    }
+
+    let (captured_x, captured_y) = (x + offset[0] as i64, y + offset[1] as i64);
+    if captured_x < 0
+        || captured_y < 0
+        || captured_x + width > captured.get_width() as i64
+        || captured_y + height > captured.get_height() as i64
+    {
+        return Err(format!(
+            "the captured frame is {}x{}, which doesn't hold the region {x},{y} {width}x{height} shifted by {},{}",
+            captured.get_width(),
+            captured.get_height(),
+            offset[0],
+            offset[1]
+        ));
+    }
+
+    Ok(())
+}
+
+/// The part of the reference to compare against: `[x, y, width, height]` in reference pixels, with the
+/// captured frame shifted by `offset` so reference pixel `(x, y)` lines up with captured `(x + dx, y + dy)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    pub crop: [u32; 4],
+    pub offset: [i32; 2],
+}
+
+impl Region {
+    /// The largest region both images cover with the given `offset`, optionally limited to `crop`.
+    pub fn new(reference: &Image, captured: &Image, offset: [i32; 2], crop: Option<[u32; 4]>) -> Region {
+        let crop = crop.unwrap_or([0, 0, reference.get_width(), reference.get_height()]);
+
+        let start_x = (crop[0] as i64).max(-offset[0] as i64).max(0);
+        let start_y = (crop[1] as i64).max(-offset[1] as i64).max(0);
+        let end_x = (crop[0] as i64 + crop[2] as i64)
+            .min(reference.get_width() as i64)
+            .min(captured.get_width() as i64 - offset[0] as i64);
+        let end_y = (crop[1] as i64 + crop[3] as i64)
+            .min(reference.get_height() as i64)
+            .min(captured.get_height() as i64 - offset[1] as i64);
+
+        Region {
+            crop: [
+                start_x as u32,
+                start_y as u32,
+                (end_x - start_x).max(0) as u32,
+                (end_y - start_y).max(0) as u32,
+            ],
+            offset,
+        }
+    }
+
+    fn pixels(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        let [x, y, width, height] = self.crop;
+        (y..y + height).flat_map(move |y| (x..x + width).map(move |x| (x, y)))
+    }
+
+    fn captured_pixel(&self, captured: &Image, x: u32, y: u32) -> Pixel {
+        captured.get_pixel((x as i32 + self.offset[0]) as u32, (y as i32 + self.offset[1]) as u32)
+    }
+}
+
+fn channels(pixel: Pixel) -> [u8; 3] {
+    [pixel.r, pixel.g, pixel.b]
+}
+
+fn luma(pixel: Pixel) -> f64 {
+    0.2126 * pixel.r as f64 + 0.7152 * pixel.g as f64 + 0.0722 * pixel.b as f64
+}
+
+pub fn compare(reference: &Image, captured: &Image, region: &Region) -> Comparison {
+    let mut max_error = [0u8; 3];
+    let mut total_error = [0u64; 3];
+    let mut squared_error = 0u64;
+    let mut differing_pixels = 0;
+
+    for (x, y) in region.pixels() {
+        let a = channels(reference.get_pixel(x, y));
+        let b = channels(region.captured_pixel(captured, x, y));
+
+        for i in 0..3 {
+            let error = a[i].abs_diff(b[i]);
+            max_error[i] = max_error[i].max(error);
+            total_error[i] += error as u64;
+            squared_error += error as u64 * error as u64;
+        }
+
+        if a != b {
+            differing_pixels += 1;
+        }
+    }
+
+    let count = region.crop[2] as f64 * region.crop[3] as f64;
+    if count == 0.0 {
+        return Comparison {
+            width: region.crop[2],
+            height: region.crop[3],
+            max_error,
+            mean_error: [0.0; 3],
+            differing_pixels,
+            psnr: 0.0,
+            ssim: 0.0,
+        };
+    }
+    let mse = squared_error as f64 / (count * 3.0);
+
+    Comparison {
+        width: region.crop[2],
+        height: region.crop[3],
+        max_error,
+        mean_error: total_error.map(|e| e as f64 / count),
+        differing_pixels,
+        psnr: match mse {
+            m if m == 0.0 => f64::INFINITY,
+            m => 10.0 * (255.0 * 255.0 / m).log10(),
+        },
+        ssim: ssim(reference, captured, region),
+    }
+}
+
+/// SSIM of the luma over 8x8 windows placed every 4 pixels, averaged over all windows.
+pub fn ssim(reference: &Image, captured: &Image, region: &Region) -> f64 {
+    const WINDOW: u32 = 8;
+    const STRIDE: u32 = 4;
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let [start_x, start_y, width, height] = region.crop;
+    if width < WINDOW || height < WINDOW {
+        return if compare_pixels_equal(reference, captured, region) { 1.0 } else { 0.0 };
+    }
+
+    let mut total = 0.0;
+    let mut windows = 0;
+
+    for window_y in (start_y..=start_y + height - WINDOW).step_by(STRIDE as usize) {
+        for window_x in (start_x..=start_x + width - WINDOW).step_by(STRIDE as usize) {
+            let mut sum_a = 0.0;
+            let mut sum_b = 0.0;
+            let mut sum_aa = 0.0;
+            let mut sum_bb = 0.0;
+            let mut sum_ab = 0.0;
+
+            for y in window_y..window_y + WINDOW {
+                for x in window_x..window_x + WINDOW {
+                    let a = luma(reference.get_pixel(x, y));
+                    let b = luma(region.captured_pixel(captured, x, y));
+                    sum_a += a;
+                    sum_b += b;
+                    sum_aa += a * a;
+                    sum_bb += b * b;
+                    sum_ab += a * b;
+                }
+            }
+
+            let n = (WINDOW * WINDOW) as f64;
+            let mean_a = sum_a / n;
+            let mean_b = sum_b / n;
+            let variance_a = sum_aa / n - mean_a * mean_a;
+            let variance_b = sum_bb / n - mean_b * mean_b;
+            let covariance = sum_ab / n - mean_a * mean_b;
+
+            total += ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+                / ((mean_a * mean_a + mean_b * mean_b + C1) * (variance_a + variance_b + C2));
+            windows += 1;
+        }
+    }
+
+    total / windows as f64
+}
+
+fn compare_pixels_equal(reference: &Image, captured: &Image, region: &Region) -> bool {
+    region
+        .pixels()
+        .all(|(x, y)| reference.get_pixel(x, y) == region.captured_pixel(captured, x, y))
+}
+
+/// Finds the offset within `max_shift` pixels in each direction that gives the lowest mean squared error. Only
+/// offsets that keep the whole region inside the captured frame are tried, see [`check_region`].
+pub fn find_alignment(reference: &Image, captured: &Image, crop: Option<[u32; 4]>, max_shift: u32) -> [i32; 2] {
+    let max_shift = max_shift as i32;
+    let mut best = ([0, 0], f64::INFINITY);
+
+    for dy in -max_shift..=max_shift {
+        for dx in -max_shift..=max_shift {
+            if check_region(reference, captured, [dx, dy], crop).is_err() {
+                continue;
+            }
+            let region = Region::new(reference, captured, [dx, dy], crop);
+            let count = region.crop[2] as f64 * region.crop[3] as f64;
+            if count == 0.0 {
+                continue;
+            }
+
+            let squared_error: u64 = region
+                .pixels()
+                .map(|(x, y)| {
+                    let a = channels(reference.get_pixel(x, y));
+                    let b = channels(region.captured_pixel(captured, x, y));
+                    (0..3).map(|i| (a[i].abs_diff(b[i]) as u64).pow(2)).sum::<u64>()
+                })
+                .sum();
+
+            let mse = squared_error as f64 / count;
+            if mse < best.1 {
+                best = ([dx, dy], mse);
+            }
+        }
+    }
+
+    best.0
+}
+
+/// Absolute per-channel difference over the compared region, multiplied by `gain` so small errors show up.
+pub fn diff_image(reference: &Image, captured: &Image, region: &Region, gain: u32) -> Image {
+    let mut diff = Image::new(region.crop[2], region.crop[3]);
+
+    for (x, y) in region.pixels() {
+        let a = channels(reference.get_pixel(x, y));
+        let b = channels(region.captured_pixel(captured, x, y));
+        let error = [0, 1, 2].map(|i| (a[i].abs_diff(b[i]) as u32 * gain).min(255) as u8);
+
+        diff.set_pixel(x - region.crop[0], y - region.crop[1], Pixel::new(error[0], error[1], error[2]));
+    }
+
+    diff
+}
+
+/// The largest channel error of each pixel, colored black (none) through blue, cyan and yellow to red
+/// (an error of `full_scale` or more).
+pub fn heatmap(reference: &Image, captured: &Image, region: &Region, full_scale: u8) -> Image {
+    let mut heatmap = Image::new(region.crop[2], region.crop[3]);
+
+    for (x, y) in region.pixels() {
+        let a = channels(reference.get_pixel(x, y));
+        let b = channels(region.captured_pixel(captured, x, y));
+        let error = (0..3).map(|i| a[i].abs_diff(b[i])).max().unwrap_or(0);
+
+        let t = (error as f64 / full_scale.max(1) as f64).min(1.0);
+        let color = match error {
+            0 => [0, 0, 0],
+            _ if t < 1.0 / 3.0 => [0, (t * 3.0 * 255.0) as u8, 255],
+            _ if t < 2.0 / 3.0 => [((t * 3.0 - 1.0) * 255.0) as u8, 255, ((2.0 - t * 3.0) * 255.0) as u8],
+            _ => [255, ((3.0 - t * 3.0) * 255.0) as u8, 0],
+        };
+
+        heatmap.set_pixel(x - region.crop[0], y - region.crop[1], Pixel::new(color[0], color[1], color[2]));
+    }
+
+    heatmap
+}
@@ -0,0 +1,765 @@
+use std::collections::BTreeMap;
+
+use bmp::Image;
+use super::array::Arrangement;
+use super::bmp_generator::{
+    flatten_arc, flatten_cubic, flatten_quadratic, ordered_corners, rect_center, rounded_rect_outline, BmpGenerator,
+    FillRule, LineCap, Outline, PaintMode,
+};
+use super::error::PatternError;
+use super::generators::{ParameterValue, Registry};
+use super::paint::{remap, Paint};
+use super::script::Script;
+use super::symmetry::Symmetry;
+use super::transform::Transform;
+use super::work::{clipped_area, points_bounds, shape_work, Edges};
+
+/// One step in building a pattern. The first step of a pattern creates the canvas; later ones draw onto it.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Operation {
+    Background {
+        width: u32,
+        height: u32,
+        paint: Paint,
+    },
+    /// Renders a generator from the [`Registry`] on a new canvas.
+    Generate {
+        generator: String,
+        width: u32,
+        height: u32,
+        parameters: BTreeMap<String, ParameterValue>,
+    },
+    /// Runs a script on the canvas. The source is kept so the pattern can be drawn again without the script file.
+    Script {
+        name: String,
+        source: String,
+        parameters: BTreeMap<String, ParameterValue>,
+    },
+    /// Replaces the canvas with an image file.
+    OpenImage {
+        path: String,
+    },
+    /// Draws an image file over the canvas.
+    Image {
+        path: String,
+        position: [f64; 2],
+        scale: f64,
+    },
+    Rect {
+        start: [f64; 2],
+        end: [f64; 2],
+        paint: Paint,
+        rotation: f64,
+        corner_radii: [f64; 4],
+        outline: Outline,
+    },
+    Ellipse {
+        center: [f64; 2],
+        size: [f64; 2],
+        rotation: f64,
+        paint: Paint,
+        outline: Outline,
+    },
+    Triangle {
+        points: [[f64; 2]; 3],
+        paint: Paint,
+        outline: Outline,
+    },
+    Polygon {
+        points: Vec<[f64; 2]>,
+        fill_rule: FillRule,
+        paint: Paint,
+        outline: Outline,
+    },
+    Line {
+        start: [f64; 2],
+        end: [f64; 2],
+        thickness: f64,
+        cap: LineCap,
+        paint: Paint,
+    },
+    Bezier {
+        control_points: Vec<[f64; 2]>,
+        rotation: f64,
+        thickness: f64,
+        filled: bool,
+        paint: Paint,
+        outline: Outline,
+    },
+    Arc {
+        center: [f64; 2],
+        radii: [f64; 2],
+        angles: [f64; 2],
+        rotation: f64,
+        thickness: f64,
+        filled: bool,
+        paint: Paint,
+        outline: Outline,
+    },
+    /// Draws text in the default font with the top left of its first line at `position`.
+    Text {
+        text: String,
+        position: [f64; 2],
+        size: f64,
+        paint: Paint,
+    },
+    /// Draws `operations` moved, rotated, scaled or skewed together by `transform`, on top of any transform of
+    /// their own. A group of one transforms a single shape.
+    Group {
+        transform: Transform,
+        operations: Vec<Operation>,
+    },
+    /// Draws `operation` once for every place in `arrangement`. When there are `colors`, the copies are painted
+    /// with them in turn instead of the shape's own paint.
+    Array {
+        arrangement: Arrangement,
+        #[serde(default)]
+        colors: Vec<[u8; 3]>,
+        operation: Box<Operation>,
+    },
+    /// Draws `operation` and its mirrored or rotated copies about the middle of the canvas.
+    Symmetric {
+        symmetry: Symmetry,
+        operation: Box<Operation>,
+    },
+}
+
+impl Operation {
+    /// Whether this operation replaces the whole canvas, making everything before it irrelevant.
+    pub fn creates_canvas(&self) -> bool {
+        matches!(
+            self,
+            Operation::Background { .. }
+                | Operation::Generate { .. }
+                | Operation::OpenImage { .. }
+        )
+    }
+
+    /// Whether this operation draws onto the canvas in a way that can be transformed, grouped, arrayed or
+    /// mirrored, rather than replacing the canvas or running a script on it.
+    pub fn is_shape(&self) -> bool {
+        !self.creates_canvas() && !matches!(self, Operation::Script { .. })
+    }
+
+    /// Whether this operation, or any operation inside it, runs a script or reads a file.
+    pub fn runs_scripts_or_reads_files(&self) -> bool {
+        match self {
+            Operation::Script { .. } | Operation::OpenImage { .. } | Operation::Image { .. } => true,
+            Operation::Group { operations, .. } => operations.iter().any(Operation::runs_scripts_or_reads_files),
+            Operation::Array { operation, .. } | Operation::Symmetric { operation, .. } => {
+                operation.runs_scripts_or_reads_files()
+            }
+            _ => false,
+        }
+    }
+
+    /// How many copies of a single shape this operation draws: the counts of nested arrays and symmetry multiplied
+    /// together, taking the largest across the operations of a group.
+    pub fn copies(&self) -> u64 {
+        match self {
+            Operation::Group { operations, .. } => operations.iter().map(Operation::copies).max().unwrap_or(1),
+            Operation::Array {
+                arrangement, operation, ..
+            } => (arrangement.count() as u64).saturating_mul(operation.copies()),
+            Operation::Symmetric { symmetry, operation } => {
+                (symmetry.count() as u64).saturating_mul(operation.copies())
+            }
+            _ => 1,
+        }
+    }
+
+    /// How many shapes and images drawing this operation paints, counting every copy made by arrays and symmetry,
+    /// and each character of text as a shape of its own.
+    pub fn shapes_drawn(&self) -> u64 {
+        match self {
+            Operation::Text { text, .. } => text.chars().count().max(1) as u64,
+            Operation::Group { operations, .. } => operations
+                .iter()
+                .fold(0, |total: u64, operation| total.saturating_add(operation.shapes_drawn())),
+            Operation::Array {
+                arrangement, operation, ..
+            } => (arrangement.count() as u64).saturating_mul(operation.shapes_drawn()),
+            Operation::Symmetric { symmetry, operation } => {
+                (symmetry.count() as u64).saturating_mul(operation.shapes_drawn())
+            }
+            _ => 1,
+        }
+    }
+
+    /// A rough count of the pixel tests drawing this operation takes on a `width` by `height` canvas: for every copy,
+    /// the canvas pixels around each shape times the edges tested at each of them, plus the pixels around every
+    /// edge of a stroke. Operations that would draw more than [`MAX_ARRAY_COPIES`] copies count as endless.
+    pub fn estimated_work(&self, width: u32, height: u32) -> u64 {
+        if self.copies() > MAX_ARRAY_COPIES as u64 {
+            return u64::MAX;
+        }
+        // float to integer casts saturate, and a NaN shape draws nothing
+        self.work(&[Transform::IDENTITY], [width, height]) as u64
+    }
+
+    /// The work of drawing the operation once for each of `placements`. Shapes only go through their points once
+    /// however many copies there are.
+    fn work(&self, placements: &[Transform], size: [u32; 2]) -> f64 {
+        let canvas = size[0] as f64 * size[1] as f64;
+        let copies = placements.len() as f64;
+        let stroked = |outline: &Outline| outline.mode != PaintMode::Fill;
+        let each = |work: &dyn Fn(&Transform) -> f64| placements.iter().map(work).sum::<f64>();
+
+        match self {
+            Operation::Background { width, height, .. } | Operation::Generate { width, height, .. } => {
+                *width as f64 * *height as f64
+            }
+            Operation::Script { .. } | Operation::OpenImage { .. } => canvas,
+            Operation::Image { .. } => copies * canvas,
+            Operation::Rect {
+                start,
+                end,
+                rotation,
+                corner_radii,
+                outline,
+                ..
+            } => {
+                let (start, end) = ordered_corners(*start, *end);
+                let turn = Transform::rotate(*rotation).around(rect_center(start, end));
+                let edges = stroked(outline)
+                    .then(|| Edges::new(&rounded_rect_outline(start, end, 0.0, *corner_radii), true));
+                let bounds = [start[0], start[1], end[0], end[1]];
+                each(&|placement| shape_work(&turn.then(*placement), bounds, 1.0, outline, edges.as_ref(), size))
+            }
+            Operation::Ellipse {
+                center,
+                size: radii,
+                rotation,
+                outline,
+                ..
+            } => {
+                let radii = radii.map(f64::abs);
+                let turn = Transform::rotate(*rotation).around(*center);
+                let edges = stroked(outline).then(|| Edges::new(&flatten_arc(*center, radii, 0.0, 360.0, 0.0), true));
+                let bounds = [center[0] - radii[0], center[1] - radii[1], center[0] + radii[0], center[1] + radii[1]];
+                each(&|placement| shape_work(&turn.then(*placement), bounds, 1.0, outline, edges.as_ref(), size))
+            }
+            Operation::Triangle { points, outline, .. } => polygon_work(points, outline, placements, size),
+            Operation::Polygon { points, outline, .. } => polygon_work(points, outline, placements, size),
+            Operation::Line { start, end, thickness, .. } => each(&|placement| {
+                let placed = placement.apply_all(&[*start, *end]);
+                clipped_area(points_bounds(&placed), thickness.abs() * 0.75, size)
+            }),
+            Operation::Bezier {
+                control_points,
+                rotation,
+                thickness,
+                filled,
+                outline,
+                ..
+            } => {
+                let points = match *control_points.as_slice() {
+                    [p0, p1, p2] => flatten_quadratic(p0, p1, p2),
+                    [p0, p1, p2, p3] => flatten_cubic(p0, p1, p2, p3),
+                    _ => return 0.0,
+                };
+                let pivot = points_center(control_points);
+                let turned: Vec<Transform> = placements
+                    .iter()
+                    .map(|placement| Transform::rotate(*rotation).around(pivot).then(*placement))
+                    .collect();
+                path_work(&points, *filled, *thickness, outline, &turned, size)
+            }
+            Operation::Arc {
+                center,
+                radii,
+                angles,
+                rotation,
+                thickness,
+                filled,
+                outline,
+                ..
+            } => {
+                let mut points = flatten_arc(*center, *radii, angles[0], angles[1], 0.0);
+                if *filled {
+                    points.push(*center);
+                }
+                let turned: Vec<Transform> = placements
+                    .iter()
+                    .map(|placement| Transform::rotate(*rotation).around(*center).then(*placement))
+                    .collect();
+                path_work(&points, *filled, *thickness, outline, &turned, size)
+            }
+            Operation::Text { text, size: text_size, .. } => {
+                // every glyph is rasterized whole, then the pixels of its box on the canvas are gone through
+                let glyphs = text.chars().count() as f64;
+                let glyph = [0.0, 0.0, *text_size, *text_size];
+                each(&|placement| {
+                    let placed = placement.bounds(glyph);
+                    let covered = ((placed[2] - placed[0] + 2.0).min(size[0] as f64))
+                        * ((placed[3] - placed[1] + 2.0).min(size[1] as f64));
+                    glyphs * (text_size * text_size + covered)
+                })
+            }
+            Operation::Group { transform, operations } => {
+                let placed: Vec<Transform> = placements.iter().map(|placement| transform.then(*placement)).collect();
+                operations.iter().map(|operation| operation.work(&placed, size)).sum()
+            }
+            Operation::Array {
+                arrangement, operation, ..
+            } => {
+                let copies = arrangement.transforms();
+                let placed: Vec<Transform> = placements
+                    .iter()
+                    .flat_map(|placement| copies.iter().map(|copy| copy.then(*placement)))
+                    .collect();
+                operation.work(&placed, size)
+            }
+            Operation::Symmetric { symmetry, operation } => {
+                let copies = symmetry.transforms(size[0], size[1]);
+                let placed: Vec<Transform> = placements
+                    .iter()
+                    .flat_map(|placement| copies.iter().map(|copy| copy.then(*placement)))
+                    .collect();
+                operation.work(&placed, size)
+            }
+        }
+    }
+
+    /// The operation painted a solid `color`. Images keep their own colors.
+    fn recolored(&self, color: [u8; 3]) -> Operation {
+        let mut operation = self.clone();
+        match &mut operation {
+            Operation::Rect { paint, .. }
+            | Operation::Ellipse { paint, .. }
+            | Operation::Triangle { paint, .. }
+            | Operation::Polygon { paint, .. }
+            | Operation::Line { paint, .. }
+            | Operation::Bezier { paint, .. }
+            | Operation::Arc { paint, .. }
+            | Operation::Text { paint, .. } => *paint = Paint::Solid(color),
+            Operation::Group { operations, .. } => {
+                for grouped in operations.iter_mut() {
+                    *grouped = grouped.recolored(color);
+                }
+            }
+            Operation::Array { colors, operation, .. } => {
+                colors.clear();
+                *operation = Box::new(operation.recolored(color));
+            }
+            Operation::Symmetric { operation, .. } => *operation = Box::new(operation.recolored(color)),
+            _ => {}
+        }
+        operation
+    }
+
+    /// The operation redrawn for a `width` by `height` canvas, with its coordinates scaled by `scale` to match.
+    /// Line thicknesses, outlines, text sizes and image scales stay as they are, as do scripts, which draw from
+    /// the canvas size themselves.
+    fn remapped(&self, scale: [f64; 2], width: u32, height: u32) -> Operation {
+        let mut operation = self.clone();
+        match &mut operation {
+            Operation::Background { width: w, height: h, paint } => {
+                (*w, *h) = (width, height);
+                *paint = paint.remapped(scale);
+            }
+            Operation::Generate { width: w, height: h, .. } => (*w, *h) = (width, height),
+            Operation::Script { .. } | Operation::OpenImage { .. } => {}
+            Operation::Image { position, .. } => *position = remap(*position, scale),
+            Operation::Rect {
+                start,
+                end,
+                paint,
+                corner_radii,
+                ..
+            } => {
+                *start = remap(*start, scale);
+                *end = remap(*end, scale);
+                *paint = paint.remapped(scale);
+                *corner_radii = corner_radii.map(|r| r * scale[0].min(scale[1]));
+            }
+            Operation::Ellipse { center, size, paint, .. } => {
+                *center = remap(*center, scale);
+                *size = remap(*size, scale);
+                *paint = paint.remapped(scale);
+            }
+            Operation::Triangle { points, paint, .. } => {
+                *points = points.map(|p| remap(p, scale));
+                *paint = paint.remapped(scale);
+            }
+            Operation::Polygon { points, paint, .. } => {
+                points.iter_mut().for_each(|p| *p = remap(*p, scale));
+                *paint = paint.remapped(scale);
+            }
+            Operation::Line { start, end, paint, .. } => {
+                *start = remap(*start, scale);
+                *end = remap(*end, scale);
+                *paint = paint.remapped(scale);
+            }
+            Operation::Bezier {
+                control_points, paint, ..
+            } => {
+                control_points.iter_mut().for_each(|p| *p = remap(*p, scale));
+                *paint = paint.remapped(scale);
+            }
+            Operation::Arc {
+                center, radii, paint, ..
+            } => {
+                *center = remap(*center, scale);
+                *radii = remap(*radii, scale);
+                *paint = paint.remapped(scale);
+            }
+            Operation::Text { position, paint, .. } => {
+                *position = remap(*position, scale);
+                *paint = paint.remapped(scale);
+            }
+            Operation::Group { transform, operations } => {
+                // the same transform, but in the scaled coordinates the grouped operations are now in
+                let to_scaled = Transform::scale(scale[0], scale[1]);
+                let from_scaled = Transform::scale(1.0 / scale[0], 1.0 / scale[1]);
+                *transform = from_scaled.then(*transform).then(to_scaled);
+                for grouped in operations.iter_mut() {
+                    *grouped = grouped.remapped(scale, width, height);
+                }
+            }
+            Operation::Array {
+                arrangement, operation, ..
+            } => {
+                match arrangement {
+                    Arrangement::Linear { step, .. } => *step = remap(*step, scale),
+                    Arrangement::Grid { spacing, .. } => *spacing = remap(*spacing, scale),
+                    Arrangement::Radial {
+                        center, radius_step, ..
+                    } => {
+                        *center = remap(*center, scale);
+                        *radius_step *= scale[1];
+                    }
+                }
+                *operation = Box::new(operation.remapped(scale, width, height));
+            }
+            Operation::Symmetric { operation, .. } => *operation = Box::new(operation.remapped(scale, width, height)),
+        }
+        operation
+    }
+
+    pub fn apply(&self, bmp: &mut Image) -> Result<(), PatternError> {
+        // checked once for the whole operation, since arrays inside arrays multiply their counts
+        if self.copies() > MAX_ARRAY_COPIES as u64 {
+            let message = format!("there can be at most {MAX_ARRAY_COPIES} copies, counting every nested array");
+            return Err(PatternError::invalid_parameter("arrangement", message));
+        }
+        self.apply_transformed(bmp, &Transform::IDENTITY)
+    }
+
+    /// Draws the operation with `transform` from the groups it's in.
+    fn apply_transformed(&self, bmp: &mut Image, transform: &Transform) -> Result<(), PatternError> {
+        match self {
+            Operation::Background { width, height, paint } => {
+                check_canvas_size(*width, *height)?;
+                *bmp = BmpGenerator::background(*width, *height, paint);
+            }
+            Operation::Generate {
+                generator,
+                width,
+                height,
+                parameters,
+            } => {
+                check_canvas_size(*width, *height)?;
+                let registry = Registry::builtin();
+                let Some(generator) = registry.get(generator) else {
+                    return Err(PatternError::UnknownGenerator(generator.clone()));
+                };
+
+                let mut canvas = BmpGenerator::clear(*width, *height);
+                generator.render(&mut canvas, &generator.values(parameters))?;
+                *bmp = canvas;
+            }
+            Operation::Script {
+                name,
+                source,
+                parameters,
+            } => {
+                Script::new(name, source)?.run(bmp, parameters)?;
+            }
+            Operation::OpenImage { path } => {
+                *bmp = BmpGenerator::open_image(path)?;
+            }
+            Operation::Image { path, position, scale } => {
+                BmpGenerator::add_image(bmp, &BmpGenerator::open_image(path)?, *position, *scale, transform);
+            }
+            Operation::Rect {
+                start,
+                end,
+                paint,
+                rotation,
+                corner_radii,
+                outline,
+            } => {
+                BmpGenerator::add_rect(bmp, *start, *end, paint, *rotation, *corner_radii, *outline, transform);
+            }
+            Operation::Ellipse {
+                center,
+                size,
+                rotation,
+                paint,
+                outline,
+            } => {
+                BmpGenerator::add_ellipse(bmp, *center, *size, *rotation, paint, *outline, transform);
+            }
+            Operation::Triangle { points, paint, outline } => {
+                BmpGenerator::add_triangle(bmp, *points, paint, *outline, transform);
+            }
+            Operation::Polygon {
+                points,
+                fill_rule,
+                paint,
+                outline,
+            } => {
+                BmpGenerator::add_polygon(bmp, points, *fill_rule, paint, *outline, transform);
+            }
+            Operation::Line {
+                start,
+                end,
+                thickness,
+                cap,
+                paint,
+            } => {
+                BmpGenerator::add_line(bmp, *start, *end, *thickness, *cap, paint, transform);
+            }
+            Operation::Bezier {
+                control_points,
+                rotation,
+                thickness,
+                filled,
+                paint,
+                outline,
+            } => {
+                BmpGenerator::add_bezier(
+                    bmp,
+                    control_points,
+                    *rotation,
+                    *thickness,
+                    *filled,
+                    paint,
+                    *outline,
+                    transform,
+                );
+            }
+            Operation::Arc {
+                center,
+                radii,
+                angles,
+                rotation,
+                thickness,
+                filled,
+                paint,
+                outline,
+            } => {
+                BmpGenerator::add_arc(
+                    bmp,
+                    *center,
+                    *radii,
+                    angles[0],
+                    angles[1],
+                    *rotation,
+                    *thickness,
+                    *filled,
+                    paint,
+                    *outline,
+                    transform,
+                );
+            }
+            Operation::Text {
+                text,
+                position,
+                size,
+                paint,
+            } => {
+                BmpGenerator::add_text(bmp, text, *position, *size, paint, transform)?;
+            }
+            Operation::Group {
+                transform: group_transform,
+                operations,
+            } => {
+                if !operations.iter().all(Operation::is_shape) {
+                    let message = "backgrounds, generators, opened images and scripts can't be grouped";
+                    return Err(PatternError::invalid_parameter("operations", message));
+                }
+
+                let transform = group_transform.then(*transform);
+                for operation in operations {
+                    operation.apply_transformed(bmp, &transform)?;
+                }
+            }
+            Operation::Array {
+                arrangement,
+                colors,
+                operation,
+            } => {
+                if !operation.is_shape() {
+                    let message = "backgrounds, generators, opened images and scripts can't be arrayed";
+                    return Err(PatternError::invalid_parameter("operation", message));
+                }
+                if arrangement.count() == 0 {
+                    return Err(PatternError::invalid_parameter("arrangement", "there must be at least one copy"));
+                }
+
+                for (i, placement) in arrangement.transforms().into_iter().enumerate() {
+                    let placement = placement.then(*transform);
+                    match colors.is_empty() {
+                        true => operation.apply_transformed(bmp, &placement)?,
+                        false => operation.recolored(colors[i % colors.len()]).apply_transformed(bmp, &placement)?,
+                    }
+                }
+            }
+            Operation::Symmetric { symmetry, operation } => {
+                if !operation.is_shape() {
+                    let message = "backgrounds, generators, opened images and scripts can't be mirrored";
+                    return Err(PatternError::invalid_parameter("operation", message));
+                }
+                if *symmetry == (Symmetry::Rotational { folds: 0 }) {
+                    return Err(PatternError::invalid_parameter("symmetry", "there must be at least one fold"));
+                }
+
+                for copy in symmetry.transforms(bmp.get_width(), bmp.get_height()) {
+                    operation.apply_transformed(bmp, &copy.then(*transform))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The work of a polygon, filled by testing each pixel against every edge, for each of `placements`.
+fn polygon_work(points: &[[f64; 2]], outline: &Outline, placements: &[Transform], size: [u32; 2]) -> f64 {
+    if points.len() < 3 {
+        return 0.0;
+    }
+    let bounds = points_bounds(points);
+    let edges = (outline.mode != PaintMode::Fill).then(|| Edges::new(points, true));
+    placements
+        .iter()
+        .map(|placement| shape_work(placement, bounds, points.len() as f64, outline, edges.as_ref(), size))
+        .sum()
+}
+
+/// The work of a flattened curve, either filled as a polygon or stroked `thickness` wide segment by segment.
+fn path_work(
+    points: &[[f64; 2]],
+    filled: bool,
+    thickness: f64,
+    outline: &Outline,
+    placements: &[Transform],
+    size: [u32; 2],
+) -> f64 {
+    if filled {
+        return polygon_work(points, outline, placements, size);
+    }
+    let bounds = points_bounds(points);
+    let edges = Edges::new(points, false);
+    let reach = thickness.abs() * 0.75;
+    placements
+        .iter()
+        .map(|placement| edges.work(placement, reach, clipped_area(placement.bounds(bounds), reach, size)))
+        .sum()
+}
+
+/// The average of `points`, which curves are turned about.
+fn points_center(points: &[[f64; 2]]) -> [f64; 2] {
+    let count = points.len() as f64;
+    [
+        points.iter().map(|p| p[0]).sum::<f64>() / count,
+        points.iter().map(|p| p[1]).sum::<f64>() / count,
+    ]
+}
+
+/// The most copies of a shape that [`Operation::Array`]s may draw, multiplied through any nested arrays and
+/// symmetry, so a mistyped count can't hang the app drawing billions of shapes.
+pub const MAX_ARRAY_COPIES: u32 = 100_000;
+
+/// The most pixels a canvas may have, enough for 8K.
+pub const MAX_CANVAS_PIXELS: u64 = 8192 * 8192;
+
+/// Canvases need at least one pixel, since an empty image can't be shown or saved, and at most
+/// [`MAX_CANVAS_PIXELS`], so a mistyped size can't exhaust memory.
+pub(crate) fn check_canvas_size(width: u32, height: u32) -> Result<(), PatternError> {
+    if width == 0 || height == 0 {
+        return Err(PatternError::invalid_parameter("size", "the canvas must be at least 1 by 1"));
+    }
+    match (width as u64).checked_mul(height as u64) {
+        Some(pixels) if pixels <= MAX_CANVAS_PIXELS => Ok(()),
+        _ => Err(PatternError::invalid_parameter(
+            "size",
+            format!("the canvas is {width} by {height}, more than the {MAX_CANVAS_PIXELS} pixels of 8192 by 8192"),
+        )),
+    }
+}
+
+/// Everything needed to draw a pattern again: the canvas size it starts from and the operations drawn on it.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PatternDescription {
+    pub width: u32,
+    pub height: u32,
+    pub operations: Vec<Operation>,
+}
+
+impl PatternDescription {
+    pub fn render(&self) -> Result<Image, PatternError> {
+        check_canvas_size(self.width, self.height)?;
+        let mut bmp = BmpGenerator::clear(self.width, self.height);
+
+        for operation in &self.operations {
+            operation.apply(&mut bmp)?;
+        }
+
+        Ok(bmp)
+    }
+
+    /// A rough count of the pixel tests drawing the pattern takes, following the canvas size through backgrounds
+    /// and generators. See [`Operation::estimated_work`].
+    pub fn estimated_work(&self) -> u64 {
+        let mut size = [self.width, self.height];
+        let mut total: u64 = 0;
+        for operation in &self.operations {
+            total = total.saturating_add(operation.estimated_work(size[0], size[1]));
+            if let Operation::Background { width, height, .. } | Operation::Generate { width, height, .. } = operation {
+                size = [*width, *height];
+            }
+        }
+        total
+    }
+
+    /// Checks that the pattern only draws, for patterns that came from somewhere else, such as an image's metadata.
+    /// Scripts and image files are refused, since drawing the pattern shouldn't run code or read local files the
+    /// user didn't choose.
+    pub fn check_untrusted(&self) -> Result<(), PatternError> {
+        match self.operations.iter().any(Operation::runs_scripts_or_reads_files) {
+            true => Err(PatternError::invalid_parameter(
+                "operations",
+                "this pattern runs a script or opens image files, which is only allowed for patterns made here",
+            )),
+            false => Ok(()),
+        }
+    }
+
+    /// The pattern for a `width` by `height` canvas, with every shape moved and stretched to keep its place
+    /// relative to the edges.
+    pub fn resized(&self, width: u32, height: u32) -> Result<PatternDescription, PatternError> {
+        check_canvas_size(width, height)?;
+        if self.operations.iter().any(|operation| matches!(operation, Operation::OpenImage { .. })) {
+            let message = "a pattern drawn over an opened image can't be resized";
+            return Err(PatternError::invalid_parameter("size", message));
+        }
+
+        let scale = [
+            width as f64 / self.width.max(1) as f64,
+            height as f64 / self.height.max(1) as f64,
+        ];
+        Ok(PatternDescription {
+            width,
+            height,
+            operations: self
+                .operations
+                .iter()
+                .map(|operation| operation.remapped(scale, width, height))
+                .collect(),
+        })
+    }
+}
@@ -0,0 +1,300 @@
+//! Reading what a display reports about itself from its EDID, including the CTA-861 extension blocks that HDMI
+//! displays use for their color and HDR capabilities.
+//!
+//! On Linux the EDID of each connected display can be read from `/sys/class/drm/*/edid`.
+
+use std::fmt;
+
+use super::colorimetry::Chromaticity;
+
+const BLOCK_SIZE: usize = 128;
+const HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+const CTA_EXTENSION: u8 = 0x02;
+
+/// What a display reports about itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edid {
+    /// The three letter PNP ID of the maker, such as `DEL`.
+    pub manufacturer: String,
+    pub product_code: u16,
+    pub serial_number: u32,
+    /// The year the display was made, or the model year when it doesn't say.
+    pub year: u16,
+    /// The EDID version and revision, such as `[1, 4]`.
+    pub version: [u8; 2],
+    /// The model name from the display descriptors.
+    pub name: Option<String>,
+    /// The transfer gamma, when the display gives one.
+    pub gamma: Option<f64>,
+    pub chromaticity: Chromaticity,
+    /// The detailed timings from every block, the preferred one first.
+    pub timings: Vec<DetailedTiming>,
+    /// The color spaces the CTA-861 colorimetry block says the display takes, beyond sRGB and BT.601/709.
+    pub colorimetry: Vec<Colorimetry>,
+    pub hdr: Option<HdrStaticMetadata>,
+}
+
+/// One video mode described in full.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DetailedTiming {
+    pub pixel_clock_khz: u32,
+    /// The visible frame size, counting both fields of an interlaced mode.
+    pub width: u32,
+    pub height: u32,
+    pub interlaced: bool,
+    /// Frames, or fields for an interlaced mode, per second.
+    pub refresh_rate: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Colorimetry {
+    XvYcc601,
+    XvYcc709,
+    SYcc601,
+    OpYcc601,
+    OpRgb,
+    Bt2020CYcc,
+    Bt2020Ycc,
+    Bt2020Rgb,
+    DciP3,
+}
+
+/// The transfer functions an HDR display takes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Eotf {
+    TraditionalSdr,
+    TraditionalHdr,
+    /// SMPTE ST 2084.
+    Pq,
+    Hlg,
+}
+
+/// The CTA-861 HDR static metadata block. Luminances are in cd/m² and are only there when the display gives them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HdrStaticMetadata {
+    pub eotfs: Vec<Eotf>,
+    pub max_luminance: Option<f64>,
+    pub max_frame_average_luminance: Option<f64>,
+    pub min_luminance: Option<f64>,
+}
+
+#[derive(Debug)]
+pub enum EdidError {
+    Read { path: String, source: std::io::Error },
+    /// Fewer than the 128 bytes of the base block, which is what a disconnected output reports.
+    TooShort(usize),
+    BadHeader,
+    /// Block `0` is the base block, and extensions count up from 1.
+    Checksum(usize),
+}
+
+impl fmt::Display for EdidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EdidError::Read { path, source } => write!(f, "failed to read the EDID from {path}: {source}"),
+            EdidError::TooShort(0) => write!(f, "the EDID is empty, is the display connected?"),
+            EdidError::TooShort(length) => write!(f, "an EDID is at least 128 bytes, but this one is {length}"),
+            EdidError::BadHeader => write!(f, "not an EDID, the header is wrong"),
+            EdidError::Checksum(block) => write!(f, "the checksum of EDID block {block} is wrong"),
+        }
+    }
+}
+
+impl std::error::Error for EdidError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EdidError::Read { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl Edid {
+    /// Reads and parses the EDID in the file at `path`.
+    pub fn read(path: &str) -> Result<Edid, EdidError> {
+        let bytes = std::fs::read(path).map_err(|source| EdidError::Read {
+            path: path.to_owned(),
+            source,
+        })?;
+        Edid::parse(&bytes)
+    }
+
+    /// Parses the base block and any CTA-861 extensions in `bytes`. Extensions the base block counts but that are
+    /// missing from `bytes` are skipped, as are extensions of other kinds.
+    pub fn parse(bytes: &[u8]) -> Result<Edid, EdidError> {
+        if bytes.len() < BLOCK_SIZE {
+            return Err(EdidError::TooShort(bytes.len()));
+        }
+        let base = &bytes[..BLOCK_SIZE];
+        if base[..8] != HEADER {
+            return Err(EdidError::BadHeader);
+        }
+        check_sum(base, 0)?;
+
+        let mut edid = Edid {
+            manufacturer: manufacturer(u16::from_be_bytes([base[8], base[9]])),
+            product_code: u16::from_le_bytes([base[10], base[11]]),
+            serial_number: u32::from_le_bytes([base[12], base[13], base[14], base[15]]),
+            year: 1990 + base[17] as u16,
+            version: [base[18], base[19]],
+            name: None,
+            gamma: (base[23] != 0xff).then(|| (base[23] as f64 + 100.0) / 100.0),
+            chromaticity: chromaticity(&base[25..35]),
+            timings: Vec::new(),
+            colorimetry: Vec::new(),
+            hdr: None,
+        };
+
+        for descriptor in base[54..126].chunks(18) {
+            match descriptor {
+                [0, 0, _, 0xfc, _, text @ ..] => edid.name = Some(descriptor_text(text)),
+                [0, 0, ..] => {}
+                _ => edid.timings.push(detailed_timing(descriptor)),
+            }
+        }
+
+        let extensions = (base[126] as usize).min(bytes.len() / BLOCK_SIZE - 1);
+        for index in 1..=extensions {
+            let block = &bytes[index * BLOCK_SIZE..(index + 1) * BLOCK_SIZE];
+            check_sum(block, index)?;
+            if block[0] == CTA_EXTENSION {
+                edid.parse_cta(block);
+            }
+        }
+
+        Ok(edid)
+    }
+
+    /// The width and height of the preferred timing, which is the display's native resolution.
+    pub fn native_resolution(&self) -> Option<[u32; 2]> {
+        self.timings.first().map(|timing| [timing.width, timing.height])
+    }
+
+    fn parse_cta(&mut self, block: &[u8]) {
+        // data blocks run from byte 4 up to where the detailed timings start
+        let timings_start = (block[2] as usize).clamp(4, BLOCK_SIZE - 1);
+        let mut data = &block[4..timings_start];
+        while let Some((&header, rest)) = data.split_first() {
+            let length = (header & 0x1f) as usize;
+            if length > rest.len() {
+                break;
+            }
+            let (payload, rest) = rest.split_at(length);
+            data = rest;
+
+            // tag 7 is an extended block, with its own tag in the first byte
+            match (header >> 5, payload) {
+                (7, [0x05, flags, more, ..]) => self.colorimetry = colorimetry(*flags, *more),
+                (7, [0x06, eotfs, _, luminances @ ..]) => self.hdr = Some(hdr_static_metadata(*eotfs, luminances)),
+                _ => {}
+            }
+        }
+
+        if block[2] >= 4 {
+            for descriptor in block[timings_start..BLOCK_SIZE - 1].chunks_exact(18) {
+                if descriptor[0] == 0 && descriptor[1] == 0 {
+                    break;
+                }
+                self.timings.push(detailed_timing(descriptor));
+            }
+        }
+    }
+}
+
+fn check_sum(block: &[u8], index: usize) -> Result<(), EdidError> {
+    match block.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) {
+        0 => Ok(()),
+        _ => Err(EdidError::Checksum(index)),
+    }
+}
+
+/// Three letters packed five bits each, with 1 for `A`.
+fn manufacturer(packed: u16) -> String {
+    [10, 5, 0]
+        .iter()
+        .map(|shift| (b'A' - 1 + ((packed >> shift) & 0x1f) as u8) as char)
+        .collect()
+}
+
+/// The ten bit coordinates, with their two low bits packed into the first two bytes.
+fn chromaticity(bytes: &[u8]) -> Chromaticity {
+    let low = ((bytes[0] as u16) << 8) | bytes[1] as u16;
+    let coordinate = |index: usize| {
+        let low_bits = (low >> (14 - 2 * index)) & 0b11;
+        ((bytes[2 + index] as u16) << 2 | low_bits) as f64 / 1024.0
+    };
+
+    Chromaticity {
+        red: [coordinate(0), coordinate(1)],
+        green: [coordinate(2), coordinate(3)],
+        blue: [coordinate(4), coordinate(5)],
+        white: [coordinate(6), coordinate(7)],
+    }
+}
+
+fn descriptor_text(text: &[u8]) -> String {
+    let end = text.iter().position(|&byte| byte == b'\n').unwrap_or(text.len());
+    String::from_utf8_lossy(&text[..end]).trim_end().to_owned()
+}
+
+fn detailed_timing(d: &[u8]) -> DetailedTiming {
+    let pixel_clock_khz = u16::from_le_bytes([d[0], d[1]]) as u32 * 10;
+    let width = d[2] as u32 | ((d[4] as u32 & 0xf0) << 4);
+    let horizontal_blank = d[3] as u32 | ((d[4] as u32 & 0x0f) << 8);
+    let lines = d[5] as u32 | ((d[7] as u32 & 0xf0) << 4);
+    let vertical_blank = d[6] as u32 | ((d[7] as u32 & 0x0f) << 8);
+    let interlaced = d[17] & 0x80 != 0;
+
+    let total = (width + horizontal_blank) as f64 * (lines + vertical_blank) as f64;
+    DetailedTiming {
+        pixel_clock_khz,
+        width,
+        height: if interlaced { lines * 2 } else { lines },
+        interlaced,
+        refresh_rate: if total > 0.0 { pixel_clock_khz as f64 * 1000.0 / total } else { 0.0 },
+    }
+}
+
+fn colorimetry(flags: u8, more: u8) -> Vec<Colorimetry> {
+    let mut spaces: Vec<Colorimetry> = [
+        Colorimetry::XvYcc601,
+        Colorimetry::XvYcc709,
+        Colorimetry::SYcc601,
+        Colorimetry::OpYcc601,
+        Colorimetry::OpRgb,
+        Colorimetry::Bt2020CYcc,
+        Colorimetry::Bt2020Ycc,
+        Colorimetry::Bt2020Rgb,
+    ]
+    .into_iter()
+    .enumerate()
+    .filter(|(bit, _)| flags & (1 << bit) != 0)
+    .map(|(_, space)| space)
+    .collect();
+
+    if more & 0x80 != 0 {
+        spaces.push(Colorimetry::DciP3);
+    }
+    spaces
+}
+
+fn hdr_static_metadata(eotf_flags: u8, luminances: &[u8]) -> HdrStaticMetadata {
+    let eotfs = [Eotf::TraditionalSdr, Eotf::TraditionalHdr, Eotf::Pq, Eotf::Hlg]
+        .into_iter()
+        .enumerate()
+        .filter(|(bit, _)| eotf_flags & (1 << bit) != 0)
+        .map(|(_, eotf)| eotf)
+        .collect();
+
+    // the code values are only there if the block is long enough, and 0 means the display doesn't say
+    let code = |index: usize| luminances.get(index).copied().filter(|&value| value != 0);
+    let max_luminance = code(0).map(|value| 50.0 * 2f64.powf(value as f64 / 32.0));
+    HdrStaticMetadata {
+        eotfs,
+        max_luminance,
+        max_frame_average_luminance: code(1).map(|value| 50.0 * 2f64.powf(value as f64 / 32.0)),
+        min_luminance: code(2)
+            .zip(max_luminance)
+            .map(|(value, max)| max * (value as f64 / 255.0).powi(2) / 100.0),
+    }
+}
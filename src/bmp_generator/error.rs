@@ -0,0 +1,76 @@
+use std::fmt;
+
+use image::ImageError;
+
+use super::edid::EdidError;
+use super::generators::GeneratorError;
+use super::script::ScriptError;
+
+/// Why a pattern couldn't be drawn, opened or saved.
+#[derive(Debug)]
+pub enum PatternError {
+    /// A value the operation can't draw with, such as a stripe spacing of 0.
+    InvalidParameter { parameter: String, message: String },
+    /// A pattern refers to a generator that isn't registered.
+    UnknownGenerator(String),
+    Generator(GeneratorError),
+    Script(ScriptError),
+    Edid(EdidError),
+    /// An image file couldn't be read or decoded.
+    Open { path: String, source: ImageError },
+    /// An image couldn't be encoded or written.
+    Save { path: String, source: ImageError },
+}
+
+impl PatternError {
+    pub fn invalid_parameter(parameter: &str, message: impl Into<String>) -> Self {
+        PatternError::InvalidParameter {
+            parameter: parameter.to_owned(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::InvalidParameter { parameter, message } => write!(f, "invalid {parameter}: {message}"),
+            PatternError::UnknownGenerator(name) => write!(f, "unknown generator '{name}'"),
+            PatternError::Generator(err) => write!(f, "{err}"),
+            PatternError::Script(err) => write!(f, "{err}"),
+            PatternError::Edid(err) => write!(f, "{err}"),
+            PatternError::Open { path, source } => write!(f, "failed to open {path}: {source}"),
+            PatternError::Save { path, source } => write!(f, "failed to save {path}: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for PatternError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PatternError::Generator(err) => Some(err),
+            PatternError::Script(err) => Some(err),
+            PatternError::Edid(err) => Some(err),
+            PatternError::Open { source, .. } | PatternError::Save { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<GeneratorError> for PatternError {
+    fn from(err: GeneratorError) -> Self {
+        PatternError::Generator(err)
+    }
+}
+
+impl From<ScriptError> for PatternError {
+    fn from(err: ScriptError) -> Self {
+        PatternError::Script(err)
+    }
+}
+
+impl From<EdidError> for PatternError {
+    fn from(err: EdidError) -> Self {
+        PatternError::Edid(err)
+    }
+}
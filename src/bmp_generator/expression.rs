@@ -0,0 +1,920 @@
+//! A small expression language for procedural patterns, where each channel is a formula such as
+//! `(x ^ y) & 255` or `sin(x / 8) * 127 + 128`.
+//!
+//! Expressions can only read the pixel variables and call the built-in functions below, so any input is safe to
+//! evaluate: there are no loops, assignments or side effects, nesting is limited, and every operation is total
+//! (division by zero gives infinity or NaN, which end up as 255 or 0 once clamped to a channel).
+//!
+//! Operators, from loosest to tightest binding: `c ? a : b`, `||`, `&&`, `|`, `^`, `&`, `==` `!=`,
+//! `<` `<=` `>` `>=`, `<<` `>>`, `+` `-`, `*` `/` `%`, the prefix `-` `!` `~`, and `**` for powers. Bitwise
+//! operators work on the integer parts of their operands, and comparisons and logic give 1 or 0.
+//!
+//! Variables are `x`, `y`, `width` (`w`), `height` (`h`), `time` (`t`) and `frame`, and the constants `pi`, `tau`
+//! and `e`. Functions are listed in [`Function`].
+
+use std::fmt;
+
+/// Deepest nesting and longest source accepted, so hostile input can't overflow the stack while parsing.
+const MAX_DEPTH: usize = 128;
+const MAX_LENGTH: usize = 4096;
+
+/// The values an expression can read for one pixel.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Variables {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Seconds since the pattern started, for animated patterns.
+    pub time: f64,
+    pub frame: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Variable {
+    X,
+    Y,
+    Width,
+    Height,
+    Time,
+    Frame,
+}
+
+impl Variable {
+    fn get(self, variables: &Variables) -> f64 {
+        match self {
+            Variable::X => variables.x,
+            Variable::Y => variables.y,
+            Variable::Width => variables.width,
+            Variable::Height => variables.height,
+            Variable::Time => variables.time,
+            Variable::Frame => variables.frame,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum UnaryOp {
+    Negate,
+    Not,
+    BitNot,
+}
+
+impl UnaryOp {
+    fn apply(self, value: f64) -> f64 {
+        match self {
+            UnaryOp::Negate => -value,
+            UnaryOp::Not => truth(value == 0.0),
+            UnaryOp::BitNot => !(value as i64) as f64,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryOp {
+    Or,
+    And,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    ShiftLeft,
+    ShiftRight,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Power,
+}
+
+impl BinaryOp {
+    /// Binding powers on the left and right of the operator; a higher right power makes it left associative.
+    fn binding_power(self) -> (u8, u8) {
+        match self {
+            BinaryOp::Or => (2, 3),
+            BinaryOp::And => (4, 5),
+            BinaryOp::BitOr => (6, 7),
+            BinaryOp::BitXor => (8, 9),
+            BinaryOp::BitAnd => (10, 11),
+            BinaryOp::Equal | BinaryOp::NotEqual => (12, 13),
+            BinaryOp::Less | BinaryOp::LessEqual | BinaryOp::Greater | BinaryOp::GreaterEqual => (14, 15),
+            BinaryOp::ShiftLeft | BinaryOp::ShiftRight => (16, 17),
+            BinaryOp::Add | BinaryOp::Subtract => (18, 19),
+            BinaryOp::Multiply | BinaryOp::Divide | BinaryOp::Remainder => (20, 21),
+            BinaryOp::Power => (25, 24),
+        }
+    }
+
+    fn apply(self, a: f64, b: f64) -> f64 {
+        match self {
+            BinaryOp::Or => truth(a != 0.0 || b != 0.0),
+            BinaryOp::And => truth(a != 0.0 && b != 0.0),
+            BinaryOp::BitOr => ((a as i64) | (b as i64)) as f64,
+            BinaryOp::BitXor => ((a as i64) ^ (b as i64)) as f64,
+            BinaryOp::BitAnd => ((a as i64) & (b as i64)) as f64,
+            BinaryOp::Equal => truth(a == b),
+            BinaryOp::NotEqual => truth(a != b),
+            BinaryOp::Less => truth(a < b),
+            BinaryOp::LessEqual => truth(a <= b),
+            BinaryOp::Greater => truth(a > b),
+            BinaryOp::GreaterEqual => truth(a >= b),
+            BinaryOp::ShiftLeft => (a as i64).wrapping_shl(b as i64 as u32) as f64,
+            BinaryOp::ShiftRight => (a as i64).wrapping_shr(b as i64 as u32) as f64,
+            BinaryOp::Add => a + b,
+            BinaryOp::Subtract => a - b,
+            BinaryOp::Multiply => a * b,
+            BinaryOp::Divide => a / b,
+            BinaryOp::Remainder => a % b,
+            BinaryOp::Power => a.powf(b),
+        }
+    }
+}
+
+/// The built-in functions. Angles are in radians.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Sqrt,
+    Abs,
+    Floor,
+    Ceil,
+    Round,
+    /// The fractional part, `x - floor(x)`.
+    Fract,
+    Exp,
+    Ln,
+    Log2,
+    Log10,
+    Sign,
+    Atan2,
+    Pow,
+    Min,
+    Max,
+    Hypot,
+    /// Modulo that takes the sign of the divisor, so `mod(-1, 8)` is 7.
+    Mod,
+    /// 0 below the edge, 1 at or above it: `step(edge, x)`.
+    Step,
+    /// `clamp(x, low, high)`.
+    Clamp,
+    /// Linear interpolation: `mix(a, b, t)`.
+    Mix,
+}
+
+impl Function {
+    const ALL: [(&'static str, Function); 26] = [
+        ("sin", Function::Sin),
+        ("cos", Function::Cos),
+        ("tan", Function::Tan),
+        ("asin", Function::Asin),
+        ("acos", Function::Acos),
+        ("atan", Function::Atan),
+        ("sqrt", Function::Sqrt),
+        ("abs", Function::Abs),
+        ("floor", Function::Floor),
+        ("ceil", Function::Ceil),
+        ("round", Function::Round),
+        ("fract", Function::Fract),
+        ("exp", Function::Exp),
+        ("ln", Function::Ln),
+        ("log2", Function::Log2),
+        ("log10", Function::Log10),
+        ("sign", Function::Sign),
+        ("atan2", Function::Atan2),
+        ("pow", Function::Pow),
+        ("min", Function::Min),
+        ("max", Function::Max),
+        ("hypot", Function::Hypot),
+        ("mod", Function::Mod),
+        ("step", Function::Step),
+        ("clamp", Function::Clamp),
+        ("mix", Function::Mix),
+    ];
+
+    fn from_name(name: &str) -> Option<Function> {
+        Function::ALL
+            .iter()
+            .find(|(function_name, _)| *function_name == name)
+            .map(|(_, function)| *function)
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            Function::Atan2
+            | Function::Pow
+            | Function::Min
+            | Function::Max
+            | Function::Hypot
+            | Function::Mod
+            | Function::Step => 2,
+            Function::Clamp | Function::Mix => 3,
+            _ => 1,
+        }
+    }
+
+    fn apply(self, args: &[f64]) -> f64 {
+        match self {
+            Function::Sin => args[0].sin(),
+            Function::Cos => args[0].cos(),
+            Function::Tan => args[0].tan(),
+            Function::Asin => args[0].asin(),
+            Function::Acos => args[0].acos(),
+            Function::Atan => args[0].atan(),
+            Function::Sqrt => args[0].sqrt(),
+            Function::Abs => args[0].abs(),
+            Function::Floor => args[0].floor(),
+            Function::Ceil => args[0].ceil(),
+            Function::Round => args[0].round(),
+            Function::Fract => args[0] - args[0].floor(),
+            Function::Exp => args[0].exp(),
+            Function::Ln => args[0].ln(),
+            Function::Log2 => args[0].log2(),
+            Function::Log10 => args[0].log10(),
+            Function::Sign => match args[0] {
+                v if v > 0.0 => 1.0,
+                v if v < 0.0 => -1.0,
+                v => v,
+            },
+            Function::Atan2 => args[0].atan2(args[1]),
+            Function::Pow => args[0].powf(args[1]),
+            Function::Min => args[0].min(args[1]),
+            Function::Max => args[0].max(args[1]),
+            Function::Hypot => args[0].hypot(args[1]),
+            Function::Mod => args[0] - args[1] * (args[0] / args[1]).floor(),
+            Function::Step => truth(args[1] >= args[0]),
+            // `f64::clamp` panics when the bounds are the wrong way round, so spell it out
+            Function::Clamp => args[0].max(args[1]).min(args[2]),
+            Function::Mix => args[0] + (args[1] - args[0]) * args[2],
+        }
+    }
+}
+
+fn truth(condition: bool) -> f64 {
+    if condition {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Where and why an expression failed to parse.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    /// Byte offset into the source.
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}: {}", self.position + 1, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier,
+    Binary(BinaryOp),
+    /// `-`, `!` or `~` where an operand is expected.
+    Minus,
+    Not,
+    BitNot,
+    Plus,
+    OpenParen,
+    CloseParen,
+    Comma,
+    Question,
+    Colon,
+    End,
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize, usize)>, ParseError> {
+    const SYMBOLS: [(&str, Token); 24] = [
+        ("**", Token::Binary(BinaryOp::Power)),
+        ("<<", Token::Binary(BinaryOp::ShiftLeft)),
+        (">>", Token::Binary(BinaryOp::ShiftRight)),
+        ("<=", Token::Binary(BinaryOp::LessEqual)),
+        (">=", Token::Binary(BinaryOp::GreaterEqual)),
+        ("==", Token::Binary(BinaryOp::Equal)),
+        ("!=", Token::Binary(BinaryOp::NotEqual)),
+        ("&&", Token::Binary(BinaryOp::And)),
+        ("||", Token::Binary(BinaryOp::Or)),
+        ("<", Token::Binary(BinaryOp::Less)),
+        (">", Token::Binary(BinaryOp::Greater)),
+        ("|", Token::Binary(BinaryOp::BitOr)),
+        ("^", Token::Binary(BinaryOp::BitXor)),
+        ("&", Token::Binary(BinaryOp::BitAnd)),
+        ("*", Token::Binary(BinaryOp::Multiply)),
+        ("/", Token::Binary(BinaryOp::Divide)),
+        ("%", Token::Binary(BinaryOp::Remainder)),
+        ("+", Token::Plus),
+        ("-", Token::Minus),
+        ("!", Token::Not),
+        ("~", Token::BitNot),
+        ("(", Token::OpenParen),
+        (")", Token::CloseParen),
+        (",", Token::Comma),
+    ];
+
+    let bytes = source.as_bytes();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        if c.is_ascii_digit() || (c == b'.' && bytes.get(i + 1).map_or(false, u8::is_ascii_digit)) {
+            if source[i..].starts_with("0x") || source[i..].starts_with("0X") {
+                i += 2;
+                while i < bytes.len() && bytes[i].is_ascii_hexdigit() {
+                    i += 1;
+                }
+                let value = i64::from_str_radix(&source[start + 2..i], 16).map_err(|_| ParseError {
+                    position: start,
+                    message: "invalid hexadecimal number".to_owned(),
+                })?;
+                tokens.push((Token::Number(value as f64), start, i));
+                continue;
+            }
+
+            while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+                i += 1;
+            }
+            if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+                let exponent = if matches!(bytes.get(i + 1), Some(b'+' | b'-')) { i + 2 } else { i + 1 };
+                if bytes.get(exponent).map_or(false, u8::is_ascii_digit) {
+                    i = exponent;
+                    while i < bytes.len() && bytes[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let value = source[start..i].parse().map_err(|_| ParseError {
+                position: start,
+                message: format!("invalid number '{}'", &source[start..i]),
+            })?;
+            tokens.push((Token::Number(value), start, i));
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            tokens.push((Token::Identifier, start, i));
+        } else if c == b'?' || c == b':' {
+            i += 1;
+            let token = if c == b'?' { Token::Question } else { Token::Colon };
+            tokens.push((token, start, i));
+        } else if let Some((symbol, token)) = SYMBOLS.iter().find(|(symbol, _)| source[i..].starts_with(symbol)) {
+            i += symbol.len();
+            tokens.push((*token, start, i));
+        } else {
+            let c = source[i..].chars().next().unwrap_or_default();
+            return Err(ParseError {
+                position: start,
+                message: format!("unexpected character '{c}'"),
+            });
+        }
+    }
+
+    tokens.push((Token::End, source.len(), source.len()));
+    Ok(tokens)
+}
+
+/// A parsed expression, with constant parts already folded.
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Constant(f64),
+    Variable(Variable),
+    Unary(UnaryOp, Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Select(Box<Node>, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+impl Node {
+    fn unary(op: UnaryOp, operand: Node) -> Node {
+        match operand {
+            Node::Constant(value) => Node::Constant(op.apply(value)),
+            operand => Node::Unary(op, Box::new(operand)),
+        }
+    }
+
+    fn binary(op: BinaryOp, a: Node, b: Node) -> Node {
+        match (a, b) {
+            (Node::Constant(a), Node::Constant(b)) => Node::Constant(op.apply(a, b)),
+            (a, b) => Node::Binary(op, Box::new(a), Box::new(b)),
+        }
+    }
+
+    fn select(condition: Node, a: Node, b: Node) -> Node {
+        match condition {
+            Node::Constant(condition) if condition != 0.0 => a,
+            Node::Constant(_) => b,
+            condition => Node::Select(Box::new(condition), Box::new(a), Box::new(b)),
+        }
+    }
+
+    fn call(function: Function, args: Vec<Node>) -> Node {
+        let constants: Option<Vec<f64>> = args
+            .iter()
+            .map(|arg| match arg {
+                Node::Constant(value) => Some(*value),
+                _ => None,
+            })
+            .collect();
+
+        match constants {
+            Some(values) => Node::Constant(function.apply(&values)),
+            None => Node::Call(function, args),
+        }
+    }
+
+    /// Appends the instructions for this node in postfix order.
+    fn compile(&self, instructions: &mut Vec<Instruction>) {
+        match self {
+            Node::Constant(value) => instructions.push(Instruction::Constant(*value)),
+            Node::Variable(variable) => instructions.push(Instruction::Variable(*variable)),
+            Node::Unary(op, operand) => {
+                operand.compile(instructions);
+                instructions.push(Instruction::Unary(*op));
+            }
+            Node::Binary(op, a, b) => {
+                a.compile(instructions);
+                b.compile(instructions);
+                instructions.push(Instruction::Binary(*op));
+            }
+            Node::Select(condition, a, b) => {
+                condition.compile(instructions);
+                a.compile(instructions);
+                b.compile(instructions);
+                instructions.push(Instruction::Select);
+            }
+            Node::Call(function, args) => {
+                for arg in args {
+                    arg.compile(instructions);
+                }
+                instructions.push(Instruction::Call(*function));
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<(Token, usize, usize)>,
+    next: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Token {
+        self.tokens[self.next].0
+    }
+
+    fn advance(&mut self) -> (Token, &'a str, usize) {
+        let (token, start, end) = self.tokens[self.next];
+        if token != Token::End {
+            self.next += 1;
+        }
+        (token, &self.source[start..end], start)
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, ParseError> {
+        Err(ParseError {
+            position: self.tokens[self.next].1,
+            message: message.into(),
+        })
+    }
+
+    fn expect(&mut self, expected: Token, description: &str) -> Result<(), ParseError> {
+        if self.peek() != expected {
+            return self.error(format!("expected {description}"));
+        }
+        self.advance();
+        Ok(())
+    }
+
+    /// Parses operators binding at least as tightly as `min_power`, by precedence climbing.
+    fn expression(&mut self, min_power: u8) -> Result<Node, ParseError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return self.error("expression is nested too deeply");
+        }
+
+        let mut lhs = self.operand()?;
+
+        loop {
+            match self.peek() {
+                Token::Binary(op) => {
+                    let (left, right) = op.binding_power();
+                    if left < min_power {
+                        break;
+                    }
+                    self.advance();
+                    let rhs = self.expression(right)?;
+                    lhs = Node::binary(op, lhs, rhs);
+                }
+                Token::Plus | Token::Minus => {
+                    let op = match self.peek() {
+                        Token::Plus => BinaryOp::Add,
+                        _ => BinaryOp::Subtract,
+                    };
+                    let (left, right) = op.binding_power();
+                    if left < min_power {
+                        break;
+                    }
+                    self.advance();
+                    let rhs = self.expression(right)?;
+                    lhs = Node::binary(op, lhs, rhs);
+                }
+                // the conditional binds loosest of all and is right associative
+                Token::Question if min_power <= 1 => {
+                    self.advance();
+                    let a = self.expression(0)?;
+                    self.expect(Token::Colon, "':' in conditional")?;
+                    let b = self.expression(1)?;
+                    lhs = Node::select(lhs, a, b);
+                }
+                _ => break,
+            }
+        }
+
+        self.depth -= 1;
+        Ok(lhs)
+    }
+
+    fn operand(&mut self) -> Result<Node, ParseError> {
+        const PREFIX_POWER: u8 = 22;
+
+        let (token, text, position) = self.advance();
+        match token {
+            Token::Number(value) => Ok(Node::Constant(value)),
+            Token::Minus => Ok(Node::unary(UnaryOp::Negate, self.expression(PREFIX_POWER)?)),
+            Token::Plus => self.expression(PREFIX_POWER),
+            Token::Not => Ok(Node::unary(UnaryOp::Not, self.expression(PREFIX_POWER)?)),
+            Token::BitNot => Ok(Node::unary(UnaryOp::BitNot, self.expression(PREFIX_POWER)?)),
+            Token::OpenParen => {
+                let node = self.expression(0)?;
+                self.expect(Token::CloseParen, "')'")?;
+                Ok(node)
+            }
+            Token::Identifier if self.peek() == Token::OpenParen => {
+                let name = text.to_owned();
+                let Some(function) = Function::from_name(&name) else {
+                    return Err(ParseError {
+                        position,
+                        message: format!("unknown function '{name}'"),
+                    });
+                };
+                self.advance();
+
+                let mut args = vec![];
+                if self.peek() != Token::CloseParen {
+                    loop {
+                        args.push(self.expression(0)?);
+                        if self.peek() != Token::Comma {
+                            break;
+                        }
+                        self.advance();
+                    }
+                }
+                self.expect(Token::CloseParen, "')' after arguments")?;
+
+                if args.len() != function.arity() {
+                    return Err(ParseError {
+                        position,
+                        message: format!("'{name}' takes {} argument(s), not {}", function.arity(), args.len()),
+                    });
+                }
+                Ok(Node::call(function, args))
+            }
+            Token::Identifier => match text {
+                "x" => Ok(Node::Variable(Variable::X)),
+                "y" => Ok(Node::Variable(Variable::Y)),
+                "width" | "w" => Ok(Node::Variable(Variable::Width)),
+                "height" | "h" => Ok(Node::Variable(Variable::Height)),
+                "time" | "t" => Ok(Node::Variable(Variable::Time)),
+                "frame" => Ok(Node::Variable(Variable::Frame)),
+                "pi" => Ok(Node::Constant(std::f64::consts::PI)),
+                "tau" => Ok(Node::Constant(std::f64::consts::TAU)),
+                "e" => Ok(Node::Constant(std::f64::consts::E)),
+                _ => Err(ParseError {
+                    position,
+                    message: format!("unknown variable '{text}'"),
+                }),
+            },
+            Token::End => Err(ParseError {
+                position,
+                message: "unexpected end of expression".to_owned(),
+            }),
+            _ => Err(ParseError {
+                position,
+                message: format!("unexpected '{text}'"),
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Instruction {
+    Constant(f64),
+    Variable(Variable),
+    Unary(UnaryOp),
+    Binary(BinaryOp),
+    /// Pops the condition and both branches, pushing the chosen branch.
+    Select,
+    Call(Function),
+}
+
+/// An expression compiled to instructions for a small stack machine, ready to be evaluated for every pixel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expression {
+    instructions: Vec<Instruction>,
+}
+
+impl Expression {
+    pub fn compile(source: &str) -> Result<Expression, ParseError> {
+        if source.len() > MAX_LENGTH {
+            return Err(ParseError {
+                position: MAX_LENGTH,
+                message: format!("expression is longer than {MAX_LENGTH} bytes"),
+            });
+        }
+
+        let mut parser = Parser {
+            source,
+            tokens: tokenize(source)?,
+            next: 0,
+            depth: 0,
+        };
+
+        let node = parser.expression(0)?;
+        if parser.peek() != Token::End {
+            let (_, start, end) = parser.tokens[parser.next];
+            return Err(ParseError {
+                position: start,
+                message: format!("unexpected '{}'", &source[start..end]),
+            });
+        }
+
+        let mut instructions = vec![];
+        node.compile(&mut instructions);
+        Ok(Expression { instructions })
+    }
+
+    /// Evaluates the expression, reusing `stack` so evaluating a whole canvas doesn't allocate for every pixel.
+    pub fn evaluate_with(&self, variables: &Variables, stack: &mut Vec<f64>) -> f64 {
+        stack.clear();
+
+        for instruction in &self.instructions {
+            match *instruction {
+                Instruction::Constant(value) => stack.push(value),
+                Instruction::Variable(variable) => stack.push(variable.get(variables)),
+                Instruction::Unary(op) => {
+                    let value = stack.pop().unwrap();
+                    stack.push(op.apply(value));
+                }
+                Instruction::Binary(op) => {
+                    let b = stack.pop().unwrap();
+                    let a = stack.pop().unwrap();
+                    stack.push(op.apply(a, b));
+                }
+                Instruction::Select => {
+                    let b = stack.pop().unwrap();
+                    let a = stack.pop().unwrap();
+                    let condition = stack.pop().unwrap();
+                    stack.push(if condition != 0.0 { a } else { b });
+                }
+                Instruction::Call(function) => {
+                    let args = stack.len() - function.arity();
+                    let value = function.apply(&stack[args..]);
+                    stack.truncate(args);
+                    stack.push(value);
+                }
+            }
+        }
+
+        stack.pop().unwrap()
+    }
+}
+
+/// A formula failing to compile, along with the channel it is for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelError {
+    pub channel: &'static str,
+    pub error: ParseError,
+}
+
+impl fmt::Display for ChannelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.channel, self.error)
+    }
+}
+
+impl std::error::Error for ChannelError {}
+
+/// Compiles the red, green and blue formulas.
+pub fn compile_channels(sources: &[String; 3]) -> Result<[Expression; 3], ChannelError> {
+    let compile = |channel: &'static str, source: &str| {
+        Expression::compile(source).map_err(|error| ChannelError { channel, error })
+    };
+
+    Ok([
+        compile("Red", &sources[0])?,
+        compile("Green", &sources[1])?,
+        compile("Blue", &sources[2])?,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(source: &str, variables: &Variables) -> f64 {
+        let expression = Expression::compile(source).unwrap_or_else(|err| panic!("{source}: {err}"));
+        expression.evaluate_with(variables, &mut Vec::new())
+    }
+
+    fn constant(source: &str) -> f64 {
+        evaluate(source, &Variables::default())
+    }
+
+    fn error(source: &str) -> ParseError {
+        Expression::compile(source).expect_err(source)
+    }
+
+    fn at(x: f64, y: f64) -> Variables {
+        Variables {
+            x,
+            y,
+            width: 1920.0,
+            height: 1080.0,
+            ..Variables::default()
+        }
+    }
+
+    #[test]
+    fn arithmetic_binds_tighter_than_shifts_and_comparisons() {
+        assert_eq!(constant("1 + 2 * 3"), 7.0);
+        assert_eq!(constant("(1 + 2) * 3"), 9.0);
+        assert_eq!(constant("7 - 6 % 4"), 5.0);
+        assert_eq!(constant("1 << 2 + 1"), 8.0);
+        assert_eq!(constant("1 + 1 < 3"), 1.0);
+        assert_eq!(constant("2 < 3 == 1"), 1.0);
+        assert_eq!(constant("1 == 1 && 0 || 1"), 1.0);
+    }
+
+    #[test]
+    fn bitwise_operators_follow_c_precedence() {
+        // `&` before `^` before `|`
+        assert_eq!(constant("1 | 2 ^ 3 & 1"), 3.0);
+        assert_eq!(constant("6 & 3 == 3"), 0.0);
+        assert_eq!(constant("0xff & ~0x0f"), 240.0);
+    }
+
+    #[test]
+    fn caret_is_exclusive_or_on_integer_parts() {
+        assert_eq!(constant("5 ^ 3"), 6.0);
+        assert_eq!(constant("5.7 ^ 3.2"), 6.0);
+        assert_eq!(constant("2 ** 3"), 8.0);
+    }
+
+    #[test]
+    fn binary_operators_are_left_associative() {
+        assert_eq!(constant("10 - 4 - 3"), 3.0);
+        assert_eq!(constant("64 / 4 / 2"), 8.0);
+        assert_eq!(constant("1 << 2 << 3"), 32.0);
+    }
+
+    #[test]
+    fn powers_and_conditionals_are_right_associative() {
+        assert_eq!(constant("2 ** 3 ** 2"), 512.0);
+        assert_eq!(constant("1 ? 2 : 0 ? 3 : 4"), 2.0);
+        assert_eq!(constant("0 ? 2 : 0 ? 3 : 4"), 4.0);
+        assert_eq!(constant("0 ? 2 : 1 ? 3 : 4"), 3.0);
+    }
+
+    #[test]
+    fn unary_minus_binds_looser_than_powers() {
+        assert_eq!(constant("-2 ** 2"), -4.0);
+        assert_eq!(constant("(-2) ** 2"), 4.0);
+        assert_eq!(constant("2 ** -1"), 0.5);
+        assert_eq!(constant("-2 * 3"), -6.0);
+        assert_eq!(constant("3 - -2"), 5.0);
+        assert_eq!(constant("--3"), 3.0);
+        assert_eq!(constant("+3"), 3.0);
+        assert_eq!(constant("!0 + !5"), 1.0);
+        assert_eq!(evaluate("-x", &at(5.0, 0.0)), -5.0);
+    }
+
+    #[test]
+    fn constant_parts_are_folded() {
+        let expression = Expression::compile("1 + 2 * 3").unwrap();
+        assert_eq!(expression.instructions, [Instruction::Constant(7.0)]);
+
+        let expression = Expression::compile("x * (2 + 2)").unwrap();
+        assert_eq!(
+            expression.instructions,
+            [
+                Instruction::Variable(Variable::X),
+                Instruction::Constant(4.0),
+                Instruction::Binary(BinaryOp::Multiply),
+            ]
+        );
+    }
+
+    #[test]
+    fn evaluates_the_example_formulas() {
+        let red = Expression::compile("(x ^ y) & 255").unwrap();
+        let green = Expression::compile("sin(x/8)*127+128").unwrap();
+        let mut stack = Vec::new();
+
+        for (x, y) in [(0.0, 0.0), (300.0, 7.0), (1919.0, 1079.0), (37.0, 512.0)] {
+            let expected = ((x as i64 ^ y as i64) & 255) as f64;
+            assert_eq!(red.evaluate_with(&at(x, y), &mut stack), expected);
+            assert_eq!(green.evaluate_with(&at(x, y), &mut stack), (x / 8.0).sin() * 127.0 + 128.0);
+        }
+    }
+
+    #[test]
+    fn reads_variables_and_constants() {
+        let variables = Variables {
+            x: 1.0,
+            y: 2.0,
+            width: 3.0,
+            height: 4.0,
+            time: 5.0,
+            frame: 6.0,
+        };
+        assert_eq!(evaluate("x + 10*y + 100*width + 1000*h + 10000*t + 100000*frame", &variables), 654321.0);
+        assert_eq!(constant("tau / pi"), 2.0);
+        assert_eq!(constant("mod(-1, 8) + clamp(9, 0, 2) + step(1, 0)"), 9.0);
+        assert_eq!(constant("1 / 0"), f64::INFINITY);
+    }
+
+    #[test]
+    fn rejects_unknown_identifiers() {
+        let err = error("x + foo");
+        assert_eq!(err.position, 4);
+        assert_eq!(err.message, "unknown variable 'foo'");
+
+        assert_eq!(error("bar(1)").message, "unknown function 'bar'");
+        assert_eq!(error("sin(1, 2)").message, "'sin' takes 1 argument(s), not 2");
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert_eq!(error("1 +").message, "unexpected end of expression");
+        assert_eq!(error("(1 + 2").message, "expected ')'");
+        assert_eq!(error("1 ? 2").message, "expected ':' in conditional");
+        assert_eq!(error("1 2").message, "unexpected '2'");
+        assert_eq!(error("1 $ 2").message, "unexpected character '$'");
+    }
+
+    #[test]
+    fn rejects_deep_nesting() {
+        let nested = |depth: usize| format!("{}x{}", "(".repeat(depth), ")".repeat(depth));
+
+        assert!(Expression::compile(&nested(MAX_DEPTH / 2)).is_ok());
+        assert_eq!(error(&nested(MAX_DEPTH + 1)).message, "expression is nested too deeply");
+        assert_eq!(error(&"-".repeat(MAX_DEPTH + 1)).message, "expression is nested too deeply");
+    }
+
+    #[test]
+    fn rejects_long_sources() {
+        let long = "1+".repeat(MAX_LENGTH / 2) + "1";
+        let err = error(&long);
+        assert_eq!(err.position, MAX_LENGTH);
+        assert_eq!(err.message, format!("expression is longer than {MAX_LENGTH} bytes"));
+
+        let longest = "1+".repeat(MAX_LENGTH / 2 - 1) + "1";
+        assert!(longest.len() <= MAX_LENGTH);
+        assert_eq!(constant(&longest), (MAX_LENGTH / 2) as f64);
+    }
+
+    #[test]
+    fn channel_errors_name_the_channel() {
+        let sources = ["x".to_owned(), "y +".to_owned(), "0".to_owned()];
+        let err = compile_channels(&sources).unwrap_err();
+        assert_eq!(err.channel, "Green");
+        assert_eq!(err.to_string(), "Green column 4: unexpected end of expression");
+    }
+}
@@ -0,0 +1,36 @@
+use bmp::Image;
+
+use super::{GeneratorError, Parameter, PatternGenerator, Values};
+use crate::bmp_generator::bmp_generator::BmpGenerator;
+use crate::bmp_generator::paint::{Paint, Pattern, PatternKind};
+
+/// Square cells alternating between two colors, starting from the top left corner.
+pub struct Checkerboard;
+
+impl PatternGenerator for Checkerboard {
+    fn name(&self) -> &str {
+        "Checkerboard"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::int("size", "Cell size", 60, 1, 1080),
+            Parameter::color("first", "First color", [255, 255, 255]),
+            Parameter::color("second", "Second color", [0, 0, 0]),
+        ]
+    }
+
+    fn render(&self, bmp: &mut Image, values: &Values) -> Result<(), GeneratorError> {
+        let paint = Paint::Pattern(Pattern {
+            kind: PatternKind::Checkerboard,
+            origin: [0.0, 0.0],
+            spacing: values.int("size") as u32,
+            num_colors: 2,
+            foreground: values.color("first"),
+            background: values.color("second"),
+        });
+
+        *bmp = BmpGenerator::background(bmp.get_width(), bmp.get_height(), &paint);
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use bmp::Image;
+
+use super::{GeneratorError, Parameter, PatternGenerator, Values};
+use crate::bmp_generator::bmp_generator::BmpGenerator;
+use crate::bmp_generator::expression::compile_channels;
+
+/// Each channel given by a formula of the pixel position, see [`crate::bmp_generator::expression`].
+pub struct ExpressionGenerator;
+
+impl PatternGenerator for ExpressionGenerator {
+    fn name(&self) -> &str {
+        "Expression"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::text("red", "R =", "(x ^ y) & 255"),
+            Parameter::text("green", "G =", "sin(x / 8) * 127 + 128"),
+            Parameter::text("blue", "B =", "y * 255 / height"),
+            Parameter::float("time", "Time", 0.0, 0.0, 86400.0),
+            Parameter::int("frame", "Frame", 0, 0, i64::from(u32::MAX)),
+        ]
+    }
+
+    fn render(&self, bmp: &mut Image, values: &Values) -> Result<(), GeneratorError> {
+        let sources = ["red", "green", "blue"].map(|channel| values.text(channel).to_owned());
+        let channels = compile_channels(&sources).map_err(|err| GeneratorError(err.to_string()))?;
+
+        *bmp = BmpGenerator::generate_expression(
+            bmp.get_width(),
+            bmp.get_height(),
+            &channels,
+            values.float("time"),
+            values.int("frame") as u64,
+        );
+        Ok(())
+    }
+}
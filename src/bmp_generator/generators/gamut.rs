@@ -0,0 +1,99 @@
+use bmp::Image;
+
+use super::{GeneratorError, Parameter, PatternGenerator, Values};
+use crate::bmp_generator::bmp_generator::{BmpGenerator, Outline};
+use crate::bmp_generator::colorimetry::{multiply_vector, Chromaticity};
+use crate::bmp_generator::paint::Paint;
+use crate::bmp_generator::transform::Transform;
+
+/// The source color spaces by the names the `source` parameter takes.
+const SOURCES: [(&str, Chromaticity); 4] = [
+    ("BT.709", Chromaticity::BT709),
+    ("DCI-P3", Chromaticity::DCI_P3),
+    ("Display P3", Chromaticity::DISPLAY_P3),
+    ("BT.2020", Chromaticity::BT2020),
+];
+
+/// Red, green, blue, cyan, magenta and yellow at full saturation in a source color space, sent as they are along
+/// the top and converted for the display's primaries and gamma along the bottom. Colors the display can't show are
+/// clipped and marked with a black dot.
+pub struct Gamut;
+
+impl PatternGenerator for Gamut {
+    fn name(&self) -> &str {
+        "Gamut"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let display = Chromaticity::BT709;
+        vec![
+            Parameter::text("source", "Source space", "BT.709"),
+            Parameter::float("source_gamma", "Source gamma", 2.4, 1.0, 3.0),
+            Parameter::float("red_x", "Display red x", display.red[0], 0.0, 1.0),
+            Parameter::float("red_y", "Display red y", display.red[1], 0.0, 1.0),
+            Parameter::float("green_x", "Display green x", display.green[0], 0.0, 1.0),
+            Parameter::float("green_y", "Display green y", display.green[1], 0.0, 1.0),
+            Parameter::float("blue_x", "Display blue x", display.blue[0], 0.0, 1.0),
+            Parameter::float("blue_y", "Display blue y", display.blue[1], 0.0, 1.0),
+            Parameter::float("white_x", "Display white x", display.white[0], 0.0, 1.0),
+            Parameter::float("white_y", "Display white y", display.white[1], 0.0, 1.0),
+            Parameter::float("display_gamma", "Display gamma", 2.2, 1.0, 3.0),
+        ]
+    }
+
+    fn render(&self, bmp: &mut Image, values: &Values) -> Result<(), GeneratorError> {
+        let source = values.text("source");
+        let Some((_, source_space)) = SOURCES.iter().find(|(name, _)| name.eq_ignore_ascii_case(source.trim())) else {
+            let names: Vec<&str> = SOURCES.iter().map(|(name, _)| *name).collect();
+            return Err(GeneratorError(format!("unknown source space '{source}', use one of {}", names.join(", "))));
+        };
+        let display = Chromaticity {
+            red: [values.float("red_x"), values.float("red_y")],
+            green: [values.float("green_x"), values.float("green_y")],
+            blue: [values.float("blue_x"), values.float("blue_y")],
+            white: [values.float("white_x"), values.float("white_y")],
+        };
+        let Some(conversion) = source_space.conversion_to(&display) else {
+            return Err(GeneratorError("the display primaries don't span a color space".to_owned()));
+        };
+        let (source_gamma, display_gamma) = (values.float("source_gamma"), values.float("display_gamma"));
+
+        let (width, height) = (bmp.get_width() as f64, bmp.get_height() as f64);
+        *bmp = BmpGenerator::clear(bmp.get_width(), bmp.get_height());
+
+        let colors: [[f64; 3]; 6] = [
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [0.0, 1.0, 1.0],
+            [1.0, 0.0, 1.0],
+            [1.0, 1.0, 0.0],
+        ];
+        let column_width = width / colors.len() as f64;
+        for (i, color) in colors.into_iter().enumerate() {
+            let left = column_width * i as f64;
+
+            let sent = color.map(|c| (c * 255.0).round() as u8);
+            fill(bmp, [left, 0.0], [left + column_width, height / 2.0], sent);
+
+            let linear = multiply_vector(&conversion, color.map(|c| c.powf(source_gamma)));
+            let clipped = linear.iter().any(|&c| !(-1e-6..=1.0 + 1e-6).contains(&c));
+            let converted = linear.map(|c| (c.clamp(0.0, 1.0).powf(1.0 / display_gamma) * 255.0).round() as u8);
+            fill(bmp, [left, height / 2.0], [left + column_width, height], converted);
+
+            if clipped {
+                let radius = column_width.min(height / 2.0) / 16.0;
+                let center = [left + column_width / 2.0, height * 0.75];
+                let (dot, outline) = (Paint::Solid([0, 0, 0]), Outline::default());
+                BmpGenerator::add_ellipse(bmp, center, [radius; 2], 0.0, &dot, outline, &Transform::IDENTITY);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn fill(bmp: &mut Image, start: [f64; 2], end: [f64; 2], color: [u8; 3]) {
+    let paint = Paint::Solid(color);
+    BmpGenerator::add_rect(bmp, start, end, &paint, 0.0, [0.0; 4], Outline::default(), &Transform::IDENTITY);
+}
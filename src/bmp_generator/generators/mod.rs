@@ -0,0 +1,290 @@
+//! Patterns that fill the canvas from a few typed parameters. Each generator lives in its own file and is listed
+//! once in the `builtin_generators!` invocation below, which declares its module and adds it to
+//! [`Registry::builtin`]; the UI, saved patterns and the command line find generators through the registry, so
+//! adding one needs no other wiring.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use bmp::Image;
+
+use super::description::Operation;
+
+/// Declares the module of each built in generator and lists the generators in order, so a new generator is a
+/// single `module::Type` line.
+macro_rules! builtin_generators {
+    ($($module:ident::$generator:ident),* $(,)?) => {
+        $(mod $module;)*
+
+        fn builtin_generators() -> Vec<Box<dyn PatternGenerator>> {
+            vec![$(Box::new($module::$generator)),*]
+        }
+    };
+}
+
+builtin_generators! {
+    stripes::Stripes,
+    checkerboard::Checkerboard,
+    expression::ExpressionGenerator,
+    gamut::Gamut,
+}
+
+/// What a parameter holds, and the range for numbers.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParameterKind {
+    Int { min: i64, max: i64 },
+    Float { min: f64, max: f64 },
+    Bool,
+    Color,
+    Text,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Parameter {
+    pub name: String,
+    pub label: String,
+    pub kind: ParameterKind,
+    pub default: ParameterValue,
+}
+
+impl Parameter {
+    pub fn int(name: &str, label: &str, default: i64, min: i64, max: i64) -> Parameter {
+        Parameter::new(name, label, ParameterKind::Int { min, max }, ParameterValue::Int(default))
+    }
+
+    pub fn float(name: &str, label: &str, default: f64, min: f64, max: f64) -> Parameter {
+        Parameter::new(name, label, ParameterKind::Float { min, max }, ParameterValue::Float(default))
+    }
+
+    pub fn bool(name: &str, label: &str, default: bool) -> Parameter {
+        Parameter::new(name, label, ParameterKind::Bool, ParameterValue::Bool(default))
+    }
+
+    pub fn color(name: &str, label: &str, default: [u8; 3]) -> Parameter {
+        Parameter::new(name, label, ParameterKind::Color, ParameterValue::Color(default))
+    }
+
+    pub fn text(name: &str, label: &str, default: &str) -> Parameter {
+        Parameter::new(name, label, ParameterKind::Text, ParameterValue::Text(default.to_owned()))
+    }
+
+    fn new(name: &str, label: &str, kind: ParameterKind, default: ParameterValue) -> Parameter {
+        Parameter {
+            name: name.to_owned(),
+            label: label.to_owned(),
+            kind,
+            default,
+        }
+    }
+
+    /// `value` if it has the right type, clamped to the parameter's range, otherwise the default.
+    fn accept(&self, value: Option<&ParameterValue>) -> ParameterValue {
+        match (&self.kind, value) {
+            (ParameterKind::Int { min, max }, Some(ParameterValue::Int(value))) => {
+                ParameterValue::Int((*value).clamp(*min, (*max).max(*min)))
+            }
+            (ParameterKind::Float { min, max }, Some(ParameterValue::Float(value))) => {
+                ParameterValue::Float(value.max(*min).min(max.max(*min)))
+            }
+            // JSON and scripts don't always tell whole numbers and floats apart
+            (ParameterKind::Int { .. }, Some(ParameterValue::Float(value))) if value.fract() == 0.0 => {
+                self.accept(Some(&ParameterValue::Int(*value as i64)))
+            }
+            (ParameterKind::Float { .. }, Some(ParameterValue::Int(value))) => {
+                self.accept(Some(&ParameterValue::Float(*value as f64)))
+            }
+            (ParameterKind::Bool, Some(value @ ParameterValue::Bool(_)))
+            | (ParameterKind::Color, Some(value @ ParameterValue::Color(_)))
+            | (ParameterKind::Text, Some(value @ ParameterValue::Text(_))) => value.clone(),
+            _ => self.default.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(untagged)]
+pub enum ParameterValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Color([u8; 3]),
+    Text(String),
+}
+
+impl From<bool> for ParameterValue {
+    fn from(value: bool) -> Self {
+        ParameterValue::Bool(value)
+    }
+}
+
+impl From<i64> for ParameterValue {
+    fn from(value: i64) -> Self {
+        ParameterValue::Int(value)
+    }
+}
+
+impl From<i32> for ParameterValue {
+    fn from(value: i32) -> Self {
+        ParameterValue::Int(value.into())
+    }
+}
+
+impl From<u32> for ParameterValue {
+    fn from(value: u32) -> Self {
+        ParameterValue::Int(value.into())
+    }
+}
+
+impl From<f64> for ParameterValue {
+    fn from(value: f64) -> Self {
+        ParameterValue::Float(value)
+    }
+}
+
+impl From<[u8; 3]> for ParameterValue {
+    fn from(value: [u8; 3]) -> Self {
+        ParameterValue::Color(value)
+    }
+}
+
+impl From<&str> for ParameterValue {
+    fn from(value: &str) -> Self {
+        ParameterValue::Text(value.to_owned())
+    }
+}
+
+impl From<String> for ParameterValue {
+    fn from(value: String) -> Self {
+        ParameterValue::Text(value)
+    }
+}
+
+/// A value for every parameter of a generator, each of the type the generator declared.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Values(BTreeMap<String, ParameterValue>);
+
+impl Values {
+    pub fn int(&self, name: &str) -> i64 {
+        match self.0.get(name) {
+            Some(ParameterValue::Int(value)) => *value,
+            _ => 0,
+        }
+    }
+
+    pub fn float(&self, name: &str) -> f64 {
+        match self.0.get(name) {
+            Some(ParameterValue::Float(value)) => *value,
+            _ => 0.0,
+        }
+    }
+
+    pub fn bool(&self, name: &str) -> bool {
+        matches!(self.0.get(name), Some(ParameterValue::Bool(true)))
+    }
+
+    pub fn color(&self, name: &str) -> [u8; 3] {
+        match self.0.get(name) {
+            Some(ParameterValue::Color(color)) => *color,
+            _ => [0, 0, 0],
+        }
+    }
+
+    pub fn text(&self, name: &str) -> &str {
+        match self.0.get(name) {
+            Some(ParameterValue::Text(text)) => text,
+            _ => "",
+        }
+    }
+
+    pub fn as_map(&self) -> &BTreeMap<String, ParameterValue> {
+        &self.0
+    }
+
+    pub fn into_map(self) -> BTreeMap<String, ParameterValue> {
+        self.0
+    }
+}
+
+/// A generator failing to render, such as an expression that doesn't compile.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeneratorError(pub String);
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for GeneratorError {}
+
+pub trait PatternGenerator {
+    /// Shown in the UI, and used to refer to the generator in saved patterns and on the command line.
+    fn name(&self) -> &str;
+
+    fn parameters(&self) -> Vec<Parameter>;
+
+    /// Draws the pattern over the whole of `bmp`.
+    fn render(&self, bmp: &mut Image, values: &Values) -> Result<(), GeneratorError>;
+
+    fn default_values(&self) -> BTreeMap<String, ParameterValue> {
+        self.parameters()
+            .into_iter()
+            .map(|parameter| (parameter.name, parameter.default))
+            .collect()
+    }
+
+    /// Checks `values` against the parameters, filling in defaults for anything missing or of the wrong type.
+    fn values(&self, values: &BTreeMap<String, ParameterValue>) -> Values {
+        Values(
+            self.parameters()
+                .iter()
+                .map(|parameter| (parameter.name.clone(), parameter.accept(values.get(&parameter.name))))
+                .collect(),
+        )
+    }
+
+    /// The operation that renders this generator on a new `width` by `height` canvas, for recording in the
+    /// pattern description.
+    fn operation(&self, width: u32, height: u32, values: &BTreeMap<String, ParameterValue>) -> Operation {
+        Operation::Generate {
+            generator: self.name().to_owned(),
+            width,
+            height,
+            parameters: self.values(values).into_map(),
+        }
+    }
+}
+
+/// The generators available by name.
+pub struct Registry {
+    generators: Vec<Box<dyn PatternGenerator>>,
+}
+
+impl Registry {
+    /// The generators built into the app.
+    pub fn builtin() -> Registry {
+        Registry {
+            generators: builtin_generators(),
+        }
+    }
+
+    /// Adds `generator`, replacing any generator with the same name.
+    pub fn register(&mut self, generator: Box<dyn PatternGenerator>) {
+        self.generators.retain(|existing| existing.name() != generator.name());
+        self.generators.push(generator);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn PatternGenerator> {
+        self.iter().find(|generator| generator.name() == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn PatternGenerator> {
+        self.generators.iter().map(|generator| generator.as_ref())
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Registry::builtin()
+    }
+}
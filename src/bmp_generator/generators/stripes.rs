@@ -0,0 +1,34 @@
+use bmp::Image;
+
+use super::{GeneratorError, Parameter, PatternGenerator, Values};
+use crate::bmp_generator::bmp_generator::BmpGenerator;
+
+/// Stripes cycling through the primary and secondary colors, then black and white.
+pub struct Stripes;
+
+impl PatternGenerator for Stripes {
+    fn name(&self) -> &str {
+        "Stripes"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::int("num_colors", "Num Colors", 8, 1, 8),
+            Parameter::int("spacing", "Spacing", 1, 1, 2160),
+            Parameter::bool("horizontal", "Horizontal", false),
+        ]
+    }
+
+    fn render(&self, bmp: &mut Image, values: &Values) -> Result<(), GeneratorError> {
+        let (width, height) = (bmp.get_width(), bmp.get_height());
+        let spacing = values.int("spacing") as u32;
+        let num_colors = values.int("num_colors") as u32;
+
+        *bmp = match values.bool("horizontal") {
+            true => BmpGenerator::generate_stripes_horizontal(width, height, spacing, num_colors),
+            false => BmpGenerator::generate_stripes(width, height, spacing, num_colors),
+        }
+        .map_err(|err| GeneratorError(err.to_string()))?;
+        Ok(())
+    }
+}
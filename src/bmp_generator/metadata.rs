@@ -0,0 +1,309 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bmp::Image;
+use image::error::{EncodingError, ImageFormatHint};
+use image::{ImageError, ImageFormat, ImageResult};
+
+use super::bmp_generator::BmpGenerator;
+use super::description::PatternDescription;
+use super::error::PatternError;
+
+/// Key of the PNG text chunk, and marker in the TIFF software tag, that holds the pattern metadata.
+pub const METADATA_KEY: &str = "TestPatternGenerator";
+
+/// The record written into exported files so the pattern can be reproduced and edited again.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Metadata {
+    pub generator: String,
+    pub version: String,
+    pub color_space: String,
+    /// UTC time the file was written, in RFC 3339 form.
+    pub timestamp: String,
+    pub pattern: PatternDescription,
+}
+
+impl Metadata {
+    pub fn new(pattern: PatternDescription) -> Self {
+        Self {
+            generator: env!("CARGO_PKG_NAME").to_owned(),
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            color_space: "sRGB".to_owned(),
+            timestamp: rfc3339(SystemTime::now()),
+            pattern,
+        }
+    }
+
+    /// The metadata as JSON using only ASCII, since TIFF text tags can't hold anything else.
+    pub fn to_json(&self) -> String {
+        let json = serde_json::to_string(self).expect("metadata is always serializable");
+
+        let mut ascii = String::with_capacity(json.len());
+        for c in json.chars() {
+            if c.is_ascii() {
+                ascii.push(c);
+            } else {
+                // Non-ASCII characters can only appear inside JSON strings, where escapes are valid.
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    ascii.push_str(&format!("\\u{unit:04x}"));
+                }
+            }
+        }
+        ascii
+    }
+
+    pub fn from_json(json: &str) -> Option<Self> {
+        serde_json::from_str(json).ok()
+    }
+}
+
+/// Saves `bmp` like [`BmpGenerator::save_image`], embedding `metadata` when the format supports it: an iTXt chunk
+/// plus the standard tEXt keys for PNG, and the image description, software and date tags for TIFF.
+pub fn save_with_metadata(bmp: &Image, metadata: &Metadata, path: &str) -> Result<(), PatternError> {
+    let save_error = |source| PatternError::Save {
+        path: path.to_owned(),
+        source,
+    };
+
+    match ImageFormat::from_path(path).map_err(save_error)? {
+        ImageFormat::Png => save_png(bmp, metadata, path).map_err(save_error),
+        ImageFormat::Tiff => save_tiff(bmp, metadata, path).map_err(save_error),
+        _ => BmpGenerator::save_image(bmp, path),
+    }
+}
+
+/// Reads the metadata written by [`save_with_metadata`], if `path` has any.
+pub fn read_metadata(path: &str) -> Option<Metadata> {
+    match ImageFormat::from_path(path).ok()? {
+        ImageFormat::Png => read_png(path),
+        ImageFormat::Tiff => read_tiff(path),
+        _ => None,
+    }
+}
+
+fn save_png(bmp: &Image, metadata: &Metadata, path: &str) -> ImageResult<()> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, bmp.get_width(), bmp.get_height());
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_srgb(png::SrgbRenderingIntent::Perceptual);
+
+    let encode = |encoder: &mut png::Encoder<'_, _>| -> Result<(), png::EncodingError> {
+        encoder.add_text_chunk(
+            "Software".to_owned(),
+            format!("{} {}", metadata.generator, metadata.version),
+        )?;
+        encoder.add_text_chunk("Creation Time".to_owned(), metadata.timestamp.clone())?;
+        encoder.add_text_chunk("Comment".to_owned(), format!("Color space: {}", metadata.color_space))?;
+        encoder.add_itxt_chunk(METADATA_KEY.to_owned(), metadata.to_json())
+    };
+    encode(&mut encoder).map_err(|error| encoding_error(ImageFormat::Png, error))?;
+
+    let mut writer = encoder
+        .write_header()
+        .map_err(|error| encoding_error(ImageFormat::Png, error))?;
+    writer
+        .write_image_data(&rgb_bytes(bmp))
+        .map_err(|error| encoding_error(ImageFormat::Png, error))
+}
+
+fn read_png(path: &str) -> Option<Metadata> {
+    let decoder = png::Decoder::new(File::open(path).ok()?);
+    let reader = decoder.read_info().ok()?;
+    let info = reader.info();
+
+    let itxt = info.utf8_text.iter().find(|chunk| chunk.keyword == METADATA_KEY);
+    if let Some(text) = itxt.and_then(|chunk| chunk.get_text().ok()) {
+        return Metadata::from_json(&text);
+    }
+
+    info.uncompressed_latin1_text
+        .iter()
+        .find(|chunk| chunk.keyword == METADATA_KEY)
+        .and_then(|chunk| Metadata::from_json(&chunk.text))
+}
+
+fn save_tiff(bmp: &Image, metadata: &Metadata, path: &str) -> ImageResult<()> {
+    use tiff::encoder::{colortype, TiffEncoder};
+    use tiff::tags::Tag;
+
+    let encode = || -> tiff::TiffResult<()> {
+        let mut encoder = TiffEncoder::new(BufWriter::new(File::create(path)?))?;
+        let mut image = encoder.new_image::<colortype::RGB8>(bmp.get_width(), bmp.get_height())?;
+
+        let directory = image.encoder();
+        directory.write_tag(Tag::ImageDescription, metadata.to_json().as_str())?;
+        directory.write_tag(
+            Tag::Software,
+            format!("{} {} ({METADATA_KEY})", metadata.generator, metadata.version).as_str(),
+        )?;
+        directory.write_tag(Tag::DateTime, tiff_date_time(&metadata.timestamp).as_str())?;
+
+        image.write_data(&rgb_bytes(bmp))
+    };
+
+    encode().map_err(|error| encoding_error(ImageFormat::Tiff, error))
+}
+
+fn read_tiff(path: &str) -> Option<Metadata> {
+    let mut decoder = tiff::decoder::Decoder::new(File::open(path).ok()?).ok()?;
+
+    let software = decoder.get_tag_ascii_string(tiff::tags::Tag::Software).ok()?;
+    if !software.contains(METADATA_KEY) {
+        return None;
+    }
+
+    let description = decoder.get_tag_ascii_string(tiff::tags::Tag::ImageDescription).ok()?;
+    Metadata::from_json(&description)
+}
+
+fn rgb_bytes(bmp: &Image) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(bmp.get_width() as usize * bmp.get_height() as usize * 3);
+    for y in 0..bmp.get_height() {
+        for x in 0..bmp.get_width() {
+            let pixel = bmp.get_pixel(x, y);
+            bytes.extend([pixel.r, pixel.g, pixel.b]);
+        }
+    }
+    bytes
+}
+
+fn encoding_error(format: ImageFormat, error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> ImageError {
+    ImageError::Encoding(EncodingError::new(ImageFormatHint::Exact(format), error))
+}
+
+/// Formats `time` as `YYYY-MM-DDTHH:MM:SSZ`.
+fn rfc3339(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+    let (days, seconds_of_day) = (seconds / 86_400, seconds % 86_400);
+
+    // Civil date from days since the epoch, following Howard Hinnant's `civil_from_days`.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60
+    )
+}
+
+/// Converts an RFC 3339 timestamp to the `YYYY:MM:DD HH:MM:SS` form TIFF uses.
+fn tiff_date_time(timestamp: &str) -> String {
+    let date_time = timestamp.trim_end_matches('Z');
+    let (date, time) = date_time.split_once('T').unwrap_or((date_time, "00:00:00"));
+    format!("{} {time}", date.replace('-', ":"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bmp_generator::description::Operation;
+    use crate::bmp_generator::paint::Paint;
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("test_pattern_generator_{}_{name}", std::process::id()));
+        path.to_string_lossy().into_owned()
+    }
+
+    fn pattern() -> PatternDescription {
+        PatternDescription {
+            width: 8,
+            height: 6,
+            operations: vec![
+                Operation::Background {
+                    width: 8,
+                    height: 6,
+                    paint: Paint::Solid([10, 20, 30]),
+                },
+                Operation::Text {
+                    text: "Grüße".to_owned(),
+                    position: [1.0, 1.0],
+                    size: 4.0,
+                    paint: Paint::Solid([255, 255, 255]),
+                },
+            ],
+        }
+    }
+
+    fn round_trip(extension: &str) {
+        let path = temp_path(&format!("round_trip.{extension}"));
+        let metadata = Metadata::new(pattern());
+        let bmp = metadata.pattern.render().unwrap();
+
+        save_with_metadata(&bmp, &metadata, &path).unwrap();
+        let read = read_metadata(&path);
+        let reopened = BmpGenerator::open_image(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(read, Some(metadata));
+        assert_eq!(reopened.unwrap().get_pixel(0, 0), bmp.get_pixel(0, 0));
+    }
+
+    #[test]
+    fn png_metadata_round_trips() {
+        round_trip("png");
+    }
+
+    #[test]
+    fn tiff_metadata_round_trips() {
+        round_trip("tiff");
+    }
+
+    #[test]
+    fn png_metadata_is_read_from_a_text_chunk() {
+        let path = temp_path("text_chunk.png");
+        let metadata = Metadata::new(pattern());
+
+        let mut encoder = png::Encoder::new(File::create(&path).unwrap(), 1, 1);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.add_text_chunk(METADATA_KEY.to_owned(), metadata.to_json()).unwrap();
+        encoder.write_header().unwrap().write_image_data(&[0, 0, 0]).unwrap();
+        let read = read_metadata(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(read, Some(metadata));
+    }
+
+    #[test]
+    fn files_without_metadata_have_none() {
+        let path = temp_path("plain.png");
+        BmpGenerator::save_image(&BmpGenerator::clear(2, 2), &path).unwrap();
+        let read = read_metadata(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(read, None);
+    }
+
+    #[test]
+    fn restored_patterns_cant_run_scripts() {
+        let mut pattern = pattern();
+        pattern.operations.push(Operation::Script {
+            name: "embedded".to_owned(),
+            source: "clear(0, 0, 0);".to_owned(),
+            parameters: BTreeMap::new(),
+        });
+
+        let metadata = Metadata::from_json(&Metadata::new(pattern).to_json()).unwrap();
+        assert!(metadata.pattern.check_untrusted().is_err());
+    }
+
+    #[test]
+    fn timestamps_are_rfc3339() {
+        assert_eq!(rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(rfc3339(UNIX_EPOCH + Duration::from_secs(951_782_400)), "2000-02-29T00:00:00Z");
+        assert_eq!(rfc3339(UNIX_EPOCH + Duration::from_secs(1_700_000_000)), "2023-11-14T22:13:20Z");
+        assert_eq!(tiff_date_time("2023-11-14T22:13:20Z"), "2023:11:14 22:13:20");
+    }
+}
@@ -0,0 +1,21 @@
+pub mod array;
+#[allow(clippy::module_inception)]
+pub mod bmp_generator;
+pub mod builders;
+pub mod canvas;
+pub mod colorimetry;
+pub mod compare;
+pub mod description;
+pub mod edid;
+pub mod error;
+pub mod expression;
+pub mod generators;
+pub mod metadata;
+pub mod paint;
+pub mod raw;
+pub mod resolution;
+pub mod scopes;
+pub mod script;
+pub mod symmetry;
+pub mod transform;
+pub mod work;
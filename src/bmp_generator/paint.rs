@@ -0,0 +1,243 @@
+use std::f64::consts::PI;
+use bmp::Pixel;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum GradientKind {
+    Linear,
+    Radial,
+    Conic,
+}
+
+/// Whether colors between two stops are blended on the stored 8-bit code values or in linear light.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Interpolation {
+    CodeValues,
+    LinearLight,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct GradientStop {
+    pub position: f64,
+    pub color: [u8; 3],
+}
+
+/// A gradient laid out between `start` and `end`:
+/// - linear: along the line from `start` to `end`
+/// - radial: outward from `start`, reaching the last stop at the distance to `end`
+/// - conic: clockwise around `start`, beginning in the direction of `end`
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    pub start: [f64; 2],
+    pub end: [f64; 2],
+    pub stops: Vec<GradientStop>,
+    pub interpolation: Interpolation,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum PatternKind {
+    VerticalStripes,
+    HorizontalStripes,
+    Checkerboard,
+    Crosshatch,
+    Dots,
+}
+
+/// A pattern repeating every `spacing` pixels from `origin`. Stripes cycle through the first `num_colors`
+/// stripe colors, the other kinds draw `foreground` over `background`.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub origin: [f64; 2],
+    pub spacing: u32,
+    pub num_colors: u32,
+    pub foreground: [u8; 3],
+    pub background: [u8; 3],
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum Paint {
+    Solid([u8; 3]),
+    Gradient(Gradient),
+    Pattern(Pattern),
+}
+
+/// The colors `generate_stripes` cycles through, in order.
+pub fn stripe_color(index: u32) -> [u8; 3] {
+    match index {
+        0 => [255, 0, 0],
+        1 => [0, 255, 0],
+        2 => [0, 0, 255],
+        3 => [255, 255, 0],
+        4 => [0, 255, 255],
+        5 => [255, 0, 255],
+        6 => [0, 0, 0],
+        7 => [255, 255, 255],
+        _ => [0, 0, 0],
+    }
+}
+
+pub fn srgb_to_linear(value: u8) -> f64 {
+    let v = value as f64 / 255.0;
+    match v <= 0.04045 {
+        true => v / 12.92,
+        false => ((v + 0.055) / 1.055).powf(2.4),
+    }
+}
+
+pub fn linear_to_srgb(value: f64) -> u8 {
+    let v = value.clamp(0.0, 1.0);
+    let v = match v <= 0.0031308 {
+        true => v * 12.92,
+        false => 1.055 * v.powf(1.0 / 2.4) - 0.055,
+    };
+    (v * 255.0).round() as u8
+}
+
+impl Gradient {
+    /// Position of `point` along the gradient, where 0.0 is the start and 1.0 the end.
+    pub fn position_at(&self, point: (f64, f64)) -> f64 {
+        let dx = self.end[0] - self.start[0];
+        let dy = self.end[1] - self.start[1];
+        let px = point.0 - self.start[0];
+        let py = point.1 - self.start[1];
+
+        match self.kind {
+            GradientKind::Linear => match dx * dx + dy * dy {
+                l if l == 0.0 => 0.0,
+                l => (px * dx + py * dy) / l,
+            },
+            GradientKind::Radial => match dx.hypot(dy) {
+                r if r == 0.0 => 0.0,
+                r => px.hypot(py) / r,
+            },
+            GradientKind::Conic => {
+                let angle = py.atan2(px) - dy.atan2(dx);
+                angle.rem_euclid(2.0 * PI) / (2.0 * PI)
+            }
+        }
+    }
+
+    /// Color at `position`, clamped to the first and last stops. Stops don't need to be sorted.
+    pub fn color_at_position(&self, position: f64) -> [u8; 3] {
+        let below = self
+            .stops
+            .iter()
+            .filter(|s| s.position <= position)
+            .max_by(|a, b| a.position.total_cmp(&b.position));
+        let above = self
+            .stops
+            .iter()
+            .filter(|s| s.position >= position)
+            .min_by(|a, b| a.position.total_cmp(&b.position));
+
+        let (below, above) = match (below, above) {
+            (Some(below), Some(above)) => (below, above),
+            (Some(stop), None) | (None, Some(stop)) => return stop.color,
+            (None, None) => return [0, 0, 0],
+        };
+
+        let t = match above.position - below.position {
+            d if d == 0.0 => 0.0,
+            d => (position - below.position) / d,
+        };
+
+        let mut color = [0; 3];
+        for (i, channel) in color.iter_mut().enumerate() {
+            *channel = match self.interpolation {
+                Interpolation::CodeValues => {
+                    (below.color[i] as f64 + (above.color[i] as f64 - below.color[i] as f64) * t).round() as u8
+                }
+                Interpolation::LinearLight => {
+                    let a = srgb_to_linear(below.color[i]);
+                    let b = srgb_to_linear(above.color[i]);
+                    linear_to_srgb(a + (b - a) * t)
+                }
+            };
+        }
+
+        color
+    }
+}
+
+impl Pattern {
+    pub fn color_at(&self, x: u32, y: u32) -> [u8; 3] {
+        self.color_at_point([x as f64 + 0.5, y as f64 + 0.5])
+    }
+
+    /// Color of the pattern pixel that `point` falls in, which can be anywhere, not only on the canvas.
+    pub fn color_at_point(&self, point: [f64; 2]) -> [u8; 3] {
+        let spacing = self.spacing.max(1) as f64;
+
+        // pixel offset from the origin, and which repeat of the pattern it falls in
+        let dx = point[0].floor() - self.origin[0].floor();
+        let dy = point[1].floor() - self.origin[1].floor();
+        let cell_x = (dx / spacing).floor() as i64;
+        let cell_y = (dy / spacing).floor() as i64;
+
+        let foreground = match self.kind {
+            PatternKind::VerticalStripes => {
+                return stripe_color(cell_x.rem_euclid(self.num_colors.max(1) as i64) as u32);
+            }
+            PatternKind::HorizontalStripes => {
+                return stripe_color(cell_y.rem_euclid(self.num_colors.max(1) as i64) as u32);
+            }
+            PatternKind::Checkerboard => (cell_x + cell_y).rem_euclid(2) == 0,
+            PatternKind::Crosshatch => (dx + dy).rem_euclid(spacing) < 1.0 || (dx - dy).rem_euclid(spacing) < 1.0,
+            PatternKind::Dots => {
+                let center_x = (cell_x as f64 + 0.5) * spacing;
+                let center_y = (cell_y as f64 + 0.5) * spacing;
+                (dx + 0.5 - center_x).hypot(dy + 0.5 - center_y) <= spacing / 4.0
+            }
+        };
+
+        match foreground {
+            true => self.foreground,
+            false => self.background,
+        }
+    }
+}
+
+impl Paint {
+    /// The color this paint gives the pixel at `x`, `y`, sampled at the pixel's center.
+    pub fn pixel_at(&self, x: u32, y: u32) -> Pixel {
+        self.pixel_at_point([x as f64 + 0.5, y as f64 + 0.5])
+    }
+
+    /// The color this paint gives at `point`. Shapes sample their paint in their own coordinates, so gradients
+    /// and patterns move, turn and scale with them.
+    pub fn pixel_at_point(&self, point: [f64; 2]) -> Pixel {
+        let color = match self {
+            Paint::Solid(color) => *color,
+            Paint::Gradient(gradient) => gradient.color_at_position(gradient.position_at((point[0], point[1]))),
+            Paint::Pattern(pattern) => pattern.color_at_point(point),
+        };
+
+        Pixel::new(color[0], color[1], color[2])
+    }
+
+    /// The same paint with its points scaled by `scale`, for a canvas that has been resized.
+    pub fn remapped(&self, scale: [f64; 2]) -> Paint {
+        let mut paint = self.clone();
+        match &mut paint {
+            Paint::Solid(_) => {}
+            Paint::Gradient(gradient) => {
+                gradient.start = remap(gradient.start, scale);
+                gradient.end = remap(gradient.end, scale);
+            }
+            Paint::Pattern(pattern) => pattern.origin = remap(pattern.origin, scale),
+        }
+        paint
+    }
+}
+
+/// `point` scaled by `scale` on each axis.
+pub fn remap(point: [f64; 2], scale: [f64; 2]) -> [f64; 2] {
+    [point[0] * scale[0], point[1] * scale[1]]
+}
+
+impl From<[u8; 3]> for Paint {
+    fn from(color: [u8; 3]) -> Self {
+        Paint::Solid(color)
+    }
+}
@@ -0,0 +1,148 @@
+//! Raw video frames, laid out the way ffmpeg's `rawvideo` format and GStreamer's `rawvideoparse` expect them.
+
+use std::fmt;
+use std::str::FromStr;
+
+use bmp::Image;
+
+use super::scopes::LumaCoefficients;
+
+/// How the pixels of a frame are laid out, named as in ffmpeg's `-pix_fmt`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// Red, green and blue bytes for each pixel.
+    Rgb24,
+    /// Red, green, blue and an opaque alpha byte for each pixel.
+    Rgba,
+    /// A full size Y plane, then full size Cb and Cr planes.
+    Yuv444p,
+    /// A full size Y plane, then Cb and Cr planes at half the width and height, rounded up.
+    Yuv420p,
+}
+
+impl PixelFormat {
+    pub const ALL: [PixelFormat; 4] = [
+        PixelFormat::Rgb24,
+        PixelFormat::Rgba,
+        PixelFormat::Yuv444p,
+        PixelFormat::Yuv420p,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PixelFormat::Rgb24 => "rgb24",
+            PixelFormat::Rgba => "rgba",
+            PixelFormat::Yuv444p => "yuv444p",
+            PixelFormat::Yuv420p => "yuv420p",
+        }
+    }
+
+    /// The number of bytes in a `width` by `height` frame.
+    pub fn frame_size(&self, width: u32, height: u32) -> usize {
+        let pixels = width as usize * height as usize;
+        match self {
+            PixelFormat::Rgb24 | PixelFormat::Yuv444p => pixels * 3,
+            PixelFormat::Rgba => pixels * 4,
+            PixelFormat::Yuv420p => pixels + 2 * ((width as usize + 1) / 2) * ((height as usize + 1) / 2),
+        }
+    }
+
+    pub fn is_yuv(&self) -> bool {
+        matches!(self, PixelFormat::Yuv444p | PixelFormat::Yuv420p)
+    }
+}
+
+impl fmt::Display for PixelFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for PixelFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        PixelFormat::ALL.into_iter().find(|format| format.name() == name).ok_or_else(|| {
+            let names: Vec<&str> = PixelFormat::ALL.iter().map(PixelFormat::name).collect();
+            format!("unknown pixel format `{name}`, use one of {}", names.join(", "))
+        })
+    }
+}
+
+/// How RGB is turned into YUV.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct YuvEncoding {
+    pub coefficients: LumaCoefficients,
+    /// Luma from 0 to 255 instead of the 16 to 235 of video levels, with the color differences to match.
+    pub full_range: bool,
+}
+
+impl Default for YuvEncoding {
+    fn default() -> Self {
+        Self {
+            coefficients: LumaCoefficients::Bt709,
+            full_range: false,
+        }
+    }
+}
+
+impl YuvEncoding {
+    /// The 8-bit Y, Cb and Cr of `pixel`.
+    fn encode(&self, pixel: bmp::Pixel) -> [u8; 3] {
+        let (y, cb, cr) = self.coefficients.ycbcr(pixel);
+        let (y, chroma_scale) = match self.full_range {
+            true => (y, 255.0),
+            false => (16.0 + y * 219.0 / 255.0, 224.0),
+        };
+        [y, 128.0 + cb * chroma_scale, 128.0 + cr * chroma_scale].map(|value| value.round().clamp(0.0, 255.0) as u8)
+    }
+}
+
+/// `bmp` as one raw frame in `format`. `yuv` is only used for the YUV formats.
+pub fn encode(bmp: &Image, format: PixelFormat, yuv: YuvEncoding) -> Vec<u8> {
+    let (width, height) = (bmp.get_width(), bmp.get_height());
+    let mut frame = Vec::with_capacity(format.frame_size(width, height));
+    let rows = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)));
+
+    match format {
+        PixelFormat::Rgb24 => {
+            for (x, y) in rows {
+                let pixel = bmp.get_pixel(x, y);
+                frame.extend([pixel.r, pixel.g, pixel.b]);
+            }
+        }
+        PixelFormat::Rgba => {
+            for (x, y) in rows {
+                let pixel = bmp.get_pixel(x, y);
+                frame.extend([pixel.r, pixel.g, pixel.b, 255]);
+            }
+        }
+        PixelFormat::Yuv444p => {
+            let pixels: Vec<[u8; 3]> = rows.map(|(x, y)| yuv.encode(bmp.get_pixel(x, y))).collect();
+            for plane in 0..3 {
+                frame.extend(pixels.iter().map(|pixel| pixel[plane]));
+            }
+        }
+        PixelFormat::Yuv420p => {
+            let pixels: Vec<[u8; 3]> = rows.map(|(x, y)| yuv.encode(bmp.get_pixel(x, y))).collect();
+            frame.extend(pixels.iter().map(|pixel| pixel[0]));
+
+            // each chroma sample is the average of the up to four pixels it covers
+            for plane in 1..3 {
+                for y in (0..height).step_by(2) {
+                    for x in (0..width).step_by(2) {
+                        let covered: Vec<u32> = [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)]
+                            .into_iter()
+                            .filter(|&(x, y)| x < width && y < height)
+                            .map(|(x, y)| pixels[(y * width + x) as usize][plane] as u32)
+                            .collect();
+                        let count = covered.len() as u32;
+                        frame.push(((covered.iter().sum::<u32>() + count / 2) / count) as u8);
+                    }
+                }
+            }
+        }
+    }
+
+    frame
+}
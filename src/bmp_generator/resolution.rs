@@ -0,0 +1,85 @@
+//! Common canvas sizes.
+
+/// A named canvas size.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Resolution {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Resolution {
+    pub fn new(name: impl Into<String>, width: u32, height: u32) -> Self {
+        Self {
+            name: name.into(),
+            width,
+            height,
+        }
+    }
+}
+
+/// A built in size, as `(name, width, height)`.
+pub type Preset = (&'static str, u32, u32);
+
+/// The built in sizes by group.
+pub const PRESETS: &[(&str, &[Preset])] = &[
+    (
+        "Broadcast",
+        &[
+            ("SD NTSC", 720, 480),
+            ("SD PAL", 720, 576),
+            ("HD 720p", 1280, 720),
+            ("HD 1080", 1920, 1080),
+            ("UHD 4K", 3840, 2160),
+            ("UHD 8K", 7680, 4320),
+        ],
+    ),
+    (
+        "DCI",
+        &[
+            ("2K", 2048, 1080),
+            ("2K flat", 1998, 1080),
+            ("2K scope", 2048, 858),
+            ("4K", 4096, 2160),
+            ("4K flat", 3996, 2160),
+            ("4K scope", 4096, 1716),
+        ],
+    ),
+    (
+        "Monitor",
+        &[
+            ("XGA", 1024, 768),
+            ("WXGA", 1280, 800),
+            ("SXGA", 1280, 1024),
+            ("HD", 1366, 768),
+            ("WXGA+", 1440, 900),
+            ("HD+", 1600, 900),
+            ("WSXGA+", 1680, 1050),
+            ("WUXGA", 1920, 1200),
+            ("QHD", 2560, 1440),
+            ("WQXGA", 2560, 1600),
+            ("UWQHD", 3440, 1440),
+            ("5K", 5120, 2880),
+        ],
+    ),
+    (
+        "Phone",
+        &[
+            ("iPhone SE", 750, 1334),
+            ("iPhone 15", 1179, 2556),
+            ("iPhone 15 Pro Max", 1290, 2796),
+            ("Pixel 8", 1080, 2400),
+            ("Galaxy S24", 1080, 2340),
+        ],
+    ),
+    (
+        "Tablet",
+        &[
+            ("iPad", 1620, 2160),
+            ("iPad Air", 1640, 2360),
+            ("iPad Pro 11\"", 1668, 2388),
+            ("iPad Pro 12.9\"", 2048, 2732),
+            ("Galaxy Tab S9", 1600, 2560),
+        ],
+    ),
+];
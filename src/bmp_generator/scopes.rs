@@ -0,0 +1,254 @@
+use bmp::{Image, Pixel};
+
+/// Which luma coefficients, and so which color difference axes, the scopes use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum LumaCoefficients {
+    Bt709,
+    Bt601,
+}
+
+impl LumaCoefficients {
+    /// The red and blue weights `(kr, kb)`; green gets the rest.
+    pub fn weights(&self) -> (f64, f64) {
+        match self {
+            LumaCoefficients::Bt709 => (0.2126, 0.0722),
+            LumaCoefficients::Bt601 => (0.299, 0.114),
+        }
+    }
+
+    /// Luma and the two color differences of an 8-bit RGB value, with luma from 0 to 255 and the
+    /// differences from -0.5 to 0.5.
+    pub fn ycbcr(&self, pixel: Pixel) -> (f64, f64, f64) {
+        let (kr, kb) = self.weights();
+        let (r, g, b) = (pixel.r as f64, pixel.g as f64, pixel.b as f64);
+
+        let y = kr * r + (1.0 - kr - kb) * g + kb * b;
+        let cb = (b - y) / (2.0 * (1.0 - kb)) / 255.0;
+        let cr = (r - y) / (2.0 * (1.0 - kr)) / 255.0;
+
+        (y, cb, cr)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    pub red: [u32; 256],
+    pub green: [u32; 256],
+    pub blue: [u32; 256],
+    pub luma: [u32; 256],
+}
+
+pub fn histogram(bmp: &Image, coefficients: LumaCoefficients) -> Histogram {
+    let mut histogram = Histogram {
+        red: [0; 256],
+        green: [0; 256],
+        blue: [0; 256],
+        luma: [0; 256],
+    };
+
+    for (x, y) in bmp.coordinates() {
+        let pixel = bmp.get_pixel(x, y);
+        histogram.red[pixel.r as usize] += 1;
+        histogram.green[pixel.g as usize] += 1;
+        histogram.blue[pixel.b as usize] += 1;
+        histogram.luma[coefficients.ycbcr(pixel).0.round().min(255.0) as usize] += 1;
+    }
+
+    histogram
+}
+
+/// Brightness for a scope cell hit `count` times when the busiest cell was hit `max` times. The square root
+/// keeps rarely hit levels visible next to large flat areas.
+fn intensity(count: u32, max: u32) -> f64 {
+    match max {
+        0 => 0.0,
+        _ => (count as f64 / max as f64).sqrt(),
+    }
+}
+
+fn scaled(color: [u8; 3], intensity: f64) -> [u8; 3] {
+    color.map(|c| (c as f64 * intensity).round() as u8)
+}
+
+fn add(bmp: &mut Image, x: u32, y: u32, color: [u8; 3]) {
+    let pixel = bmp.get_pixel(x, y);
+    bmp.set_pixel(
+        x,
+        y,
+        Pixel::new(
+            pixel.r.saturating_add(color[0]),
+            pixel.g.saturating_add(color[1]),
+            pixel.b.saturating_add(color[2]),
+        ),
+    );
+}
+
+impl Histogram {
+    /// Draws the red, green and blue histograms added together with the luma histogram as a gray outline,
+    /// one column per level and `height` pixels tall.
+    pub fn render(&self, height: u32) -> Image {
+        let mut bmp = Image::new(256, height);
+        let max = [&self.red, &self.green, &self.blue, &self.luma]
+            .iter()
+            .flat_map(|channel| channel.iter())
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        let bar = |count: u32| ((count as f64 / max as f64) * height as f64).round() as u32;
+
+        for level in 0..256 {
+            for (channel, color) in [(&self.red, [160, 0, 0]), (&self.green, [0, 160, 0]), (&self.blue, [0, 0, 160])] {
+                for y in height - bar(channel[level]).min(height)..height {
+                    add(&mut bmp, level as u32, y, color);
+                }
+            }
+
+            let luma_top = height - bar(self.luma[level]).min(height);
+            if luma_top < height {
+                bmp.set_pixel(level as u32, luma_top, Pixel::new(220, 220, 220));
+            }
+        }
+
+        bmp
+    }
+}
+
+/// Draws how often each level of `channel` appears in each column band of `bmp`, 256 pixels tall with 255 at
+/// the top, into `width` columns of `scope` starting at `left`.
+fn draw_waveform(
+    scope: &mut Image,
+    bmp: &Image,
+    left: u32,
+    width: u32,
+    color: [u8; 3],
+    channel: impl Fn(Pixel) -> f64,
+) {
+    if width == 0 {
+        return;
+    }
+    let mut counts = vec![0u32; width as usize * 256];
+
+    for (x, y) in bmp.coordinates() {
+        let column = (x as u64 * width as u64 / bmp.get_width().max(1) as u64) as usize;
+        let level = channel(bmp.get_pixel(x, y)).round().clamp(0.0, 255.0) as usize;
+        counts[level * width as usize + column] += 1;
+    }
+
+    let max = counts.iter().copied().max().unwrap_or(0);
+
+    for level in 0..256 {
+        for column in 0..width {
+            let count = counts[level * width as usize + column as usize];
+            if count > 0 {
+                let color = scaled(color, intensity(count, max).max(0.25));
+                scope.set_pixel(left + column, 255 - level as u32, Pixel::new(color[0], color[1], color[2]));
+            }
+        }
+    }
+}
+
+/// Draws dim lines across a waveform every 10% of the signal range.
+fn draw_waveform_graticule(scope: &mut Image) {
+    for step in 0..=10 {
+        let y = 255 - (step as f64 * 25.5).round() as u32;
+        for x in 0..scope.get_width() {
+            add(scope, x, y, [40, 40, 40]);
+        }
+    }
+}
+
+/// Luma waveform monitor, `width` columns wide and 256 pixels tall. A width of 0 gives an empty scope.
+pub fn waveform(bmp: &Image, coefficients: LumaCoefficients, width: u32) -> Image {
+    let mut scope = Image::new(width, 256);
+
+    draw_waveform_graticule(&mut scope);
+    draw_waveform(&mut scope, bmp, 0, width, [200, 255, 200], |pixel| coefficients.ycbcr(pixel).0);
+
+    scope
+}
+
+/// Red, green and blue waveforms side by side, each a third of `width`.
+pub fn parade(bmp: &Image, width: u32) -> Image {
+    let third = (width / 3).max(1);
+    let mut scope = Image::new(third * 3, 256);
+
+    draw_waveform_graticule(&mut scope);
+    draw_waveform(&mut scope, bmp, 0, third, [255, 80, 80], |pixel| pixel.r as f64);
+    draw_waveform(&mut scope, bmp, third, third, [80, 255, 80], |pixel| pixel.g as f64);
+    draw_waveform(&mut scope, bmp, third * 2, third, [80, 80, 255], |pixel| pixel.b as f64);
+
+    scope
+}
+
+/// Vectorscope plotting Cb to the right and Cr up on a `size` by `size` square, with target boxes for 75% and
+/// 100% color bars under `coefficients`. A size of 0 gives an empty scope.
+pub fn vectorscope(bmp: &Image, coefficients: LumaCoefficients, size: u32) -> Image {
+    let mut scope = Image::new(size, size);
+    if size == 0 {
+        return scope;
+    }
+    let half = size as f64 / 2.0;
+    // color differences run from -0.5 to 0.5, leave a small margin around them
+    let to_scope = |cb: f64, cr: f64| {
+        [
+            (half + cb * size as f64 * 0.9).clamp(0.0, size as f64 - 1.0) as u32,
+            (half - cr * size as f64 * 0.9).clamp(0.0, size as f64 - 1.0) as u32,
+        ]
+    };
+
+    // graticule: outer circle, cross hairs and a box around every color bar target
+    for step in 0..720 {
+        let angle = step as f64 / 720.0 * std::f64::consts::TAU;
+        let [x, y] = to_scope(0.5 * angle.cos(), 0.5 * angle.sin());
+        add(&mut scope, x, y, [60, 60, 60]);
+    }
+    for i in 0..size {
+        add(&mut scope, i, half as u32, [40, 40, 40]);
+        add(&mut scope, half as u32, i, [40, 40, 40]);
+    }
+
+    let targets = [[1, 0, 0], [1, 0, 1], [0, 0, 1], [0, 1, 1], [0, 1, 0], [1, 1, 0]];
+    for (level, box_half) in [(255.0 * 0.75, 3), (255.0, 5)] {
+        for target in targets {
+            let pixel = Pixel::new(
+                (target[0] as f64 * level) as u8,
+                (target[1] as f64 * level) as u8,
+                (target[2] as f64 * level) as u8,
+            );
+            let (_, cb, cr) = coefficients.ycbcr(pixel);
+            let [cx, cy] = to_scope(cb, cr);
+
+            for d in -box_half..=box_half {
+                for (x, y) in [
+                    (cx as i32 + d, cy as i32 - box_half),
+                    (cx as i32 + d, cy as i32 + box_half),
+                    (cx as i32 - box_half, cy as i32 + d),
+                    (cx as i32 + box_half, cy as i32 + d),
+                ] {
+                    if x >= 0 && y >= 0 && (x as u32) < size && (y as u32) < size {
+                        add(&mut scope, x as u32, y as u32, [120, 120, 40]);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut counts = vec![0u32; (size * size) as usize];
+    for (x, y) in bmp.coordinates() {
+        let (_, cb, cr) = coefficients.ycbcr(bmp.get_pixel(x, y));
+        let [sx, sy] = to_scope(cb, cr);
+        counts[(sy * size + sx) as usize] += 1;
+    }
+
+    let max = counts.iter().copied().max().unwrap_or(0);
+    for (i, count) in counts.iter().enumerate() {
+        if *count > 0 {
+            let color = scaled([200, 255, 200], intensity(*count, max).max(0.25));
+            add(&mut scope, i as u32 % size, i as u32 / size, color);
+        }
+    }
+
+    scope
+}
@@ -0,0 +1,472 @@
+//! Custom pattern generators written in [Rhai](https://rhai.rs).
+//!
+//! A script draws onto the canvas with the same shapes as [`BmpGenerator`], and can read `width` and `height`
+//! along with its own parameters. Parameters are declared by an optional `parameters()` function returning an
+//! array of maps, each with a `name`, a `value` giving the default and the type, and optionally a `label` and a
+//! `min` and `max` for numbers:
+//!
+//! ```text
+//! fn parameters() {
+//!     [
+//!         #{ name: "cells", value: 8, min: 1, max: 64 },
+//!         #{ name: "color", value: [255, 255, 255] },
+//!         #{ name: "caption", label: "Caption", value: "Grid" },
+//!     ]
+//! }
+//!
+//! let size = width / cells;
+//! for i in 0..cells {
+//!     rect(i * size, 0, i * size + size / 2, height, color);
+//! }
+//! text(16, 16, 48, caption, color);
+//! ```
+//!
+//! Drawing functions take coordinates as integers or floats and colors as `[r, g, b]` arrays:
+//! `clear(color)`, `pixel(x, y, color)`, `rect(x0, y0, x1, y1, color)` and `rect(x0, y0, x1, y1, color, rotation)`,
+//! `ellipse(cx, cy, rx, ry, color)` and `ellipse(cx, cy, rx, ry, color, rotation)`,
+//! `triangle(x0, y0, x1, y1, x2, y2, color)`, `polygon([[x, y], ...], color)`, `line(x0, y0, x1, y1, thickness, color)`,
+//! `stripes(spacing, num_colors)`, `horizontal_stripes(spacing, num_colors)` and `text(x, y, size, string, color)`.
+//! `rgb(r, g, b)` builds a color.
+//!
+//! Scripts can't reach the file system, and are stopped if they run too long, draw too much or nest too deeply.
+
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
+
+use bmp::{Image, Pixel};
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, Scope, AST};
+
+use super::bmp_generator::{BmpGenerator, FillRule, LineCap, Outline};
+use super::description::Operation;
+use super::generators::{GeneratorError, Parameter, ParameterKind, ParameterValue, PatternGenerator, Values};
+use super::paint::Paint;
+use super::transform::Transform;
+
+/// Enough for a script to visit every pixel of a 4K canvas a few times, but not to hang the app.
+const MAX_OPERATIONS: u64 = 200_000_000;
+/// How many pixels a script's drawing calls can cover in total, enough to fill a 4K canvas about 60 times. A
+/// drawing call is a single operation to Rhai however much of the canvas it covers, so it's limited separately.
+const MAX_PIXELS_DRAWN: u64 = 500_000_000;
+
+impl ParameterValue {
+    fn to_dynamic(&self) -> Dynamic {
+        match self {
+            ParameterValue::Bool(value) => Dynamic::from_bool(*value),
+            ParameterValue::Int(value) => Dynamic::from_int(*value),
+            ParameterValue::Float(value) => Dynamic::from_float(*value),
+            ParameterValue::Color(color) => Dynamic::from_array(color.iter().map(|c| Dynamic::from_int(*c as i64)).collect()),
+            ParameterValue::Text(text) => Dynamic::from(text.clone()),
+        }
+    }
+}
+
+/// A script failing to load, declare its parameters or run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScriptError(pub String);
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+/// A compiled script along with the parameters it declares.
+#[derive(Clone, Debug)]
+pub struct Script {
+    pub name: String,
+    pub source: String,
+    pub parameters: Vec<Parameter>,
+    ast: AST,
+}
+
+impl Script {
+    pub fn new(name: &str, source: &str) -> Result<Script, ScriptError> {
+        let engine = new_engine(Rc::new(RefCell::new(Image::new(0, 0))));
+        let ast = engine
+            .compile(source)
+            .map_err(|error| ScriptError(format!("{name}: {error}")))?;
+
+        let mut parameters = vec![];
+        if ast.iter_functions().any(|function| function.name == "parameters" && function.params.is_empty()) {
+            let options = CallFnOptions::new().eval_ast(false);
+            let declarations: Array = engine
+                .call_fn_with_options(options, &mut Scope::new(), &ast, "parameters", ())
+                .map_err(|error| ScriptError(format!("{name}: {error}")))?;
+
+            for declaration in declarations {
+                let parameter = parse_parameter(declaration).map_err(|error| ScriptError(format!("{name}: {error}")))?;
+                parameters.push(parameter);
+            }
+        }
+
+        Ok(Script {
+            name: name.to_owned(),
+            source: source.to_owned(),
+            parameters,
+            ast,
+        })
+    }
+
+    /// Loads a script file, naming it after the file.
+    pub fn load(path: &Path) -> Result<Script, ScriptError> {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let source = std::fs::read_to_string(path).map_err(|error| ScriptError(format!("{}: {error}", path.display())))?;
+        Script::new(&name, &source)
+    }
+
+    /// Runs the script on `bmp`. `values` are checked against the declared parameters like a built in
+    /// generator's, so anything missing or of the wrong type takes its default and numbers are clamped to their
+    /// range. The canvas is left alone if the script fails.
+    pub fn run(&self, bmp: &mut Image, values: &BTreeMap<String, ParameterValue>) -> Result<(), ScriptError> {
+        let canvas = Rc::new(RefCell::new(bmp.clone()));
+        let engine = new_engine(canvas.clone());
+
+        let mut scope = Scope::new();
+        scope.push_constant("width", bmp.get_width() as i64);
+        scope.push_constant("height", bmp.get_height() as i64);
+        for (name, value) in self.values(values).into_map() {
+            scope.push_constant_dynamic(name.as_str(), value.to_dynamic());
+        }
+
+        engine
+            .run_ast_with_scope(&mut scope, &self.ast)
+            .map_err(|error| ScriptError(format!("{}: {error}", self.name)))?;
+
+        drop(engine);
+        *bmp = canvas.replace(Image::new(0, 0));
+        Ok(())
+    }
+}
+
+impl PatternGenerator for Script {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        self.parameters.clone()
+    }
+
+    fn render(&self, bmp: &mut Image, values: &Values) -> Result<(), GeneratorError> {
+        self.run(bmp, values.as_map()).map_err(|err| GeneratorError(err.0))
+    }
+
+    /// Scripts draw over the canvas rather than replacing it, and keep their source so the pattern can be drawn
+    /// again without the script file.
+    fn operation(&self, _width: u32, _height: u32, values: &BTreeMap<String, ParameterValue>) -> Operation {
+        Operation::Script {
+            name: self.name.clone(),
+            source: self.source.clone(),
+            parameters: self.values(values).into_map(),
+        }
+    }
+}
+
+/// Loads every `.rhai` file in `folder`, sorted by name. Scripts that fail to load are returned as errors so one
+/// broken script doesn't hide the others.
+pub fn load_folder(folder: &str) -> Vec<Result<Script, ScriptError>> {
+    let Ok(entries) = std::fs::read_dir(folder) else {
+        return vec![];
+    };
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map_or(false, |ext| ext.eq_ignore_ascii_case("rhai")))
+        .collect();
+    paths.sort();
+
+    paths.iter().map(|path| Script::load(path)).collect()
+}
+
+fn parse_parameter(declaration: Dynamic) -> Result<Parameter, String> {
+    let Some(map) = declaration.try_cast::<rhai::Map>() else {
+        return Err("each parameter must be a map like #{ name: \"size\", value: 10 }".to_owned());
+    };
+
+    let field = |key: &str| map.get(key).cloned();
+    let name = field("name")
+        .and_then(|name| name.into_string().ok())
+        .ok_or("parameter is missing a `name`")?;
+    let label = field("label")
+        .and_then(|label| label.into_string().ok())
+        .unwrap_or_else(|| name.clone());
+    let value = field("value").ok_or(format!("parameter `{name}` is missing a `value`"))?;
+
+    let (kind, default) = if let Ok(value) = value.as_int() {
+        let min = field("min").and_then(|min| number(&min).ok()).unwrap_or(0.0) as i64;
+        let max = field("max").and_then(|max| number(&max).ok()).unwrap_or(255.0) as i64;
+        (ParameterKind::Int { min, max }, ParameterValue::Int(value))
+    } else if let Ok(value) = value.as_float() {
+        let min = field("min").and_then(|min| number(&min).ok()).unwrap_or(0.0);
+        let max = field("max").and_then(|max| number(&max).ok()).unwrap_or(1.0);
+        (ParameterKind::Float { min, max }, ParameterValue::Float(value))
+    } else if let Ok(value) = value.as_bool() {
+        (ParameterKind::Bool, ParameterValue::Bool(value))
+    } else if value.is_array() {
+        let color = color(&value).map_err(|error| format!("parameter `{name}`: {error}"))?;
+        (ParameterKind::Color, ParameterValue::Color(color))
+    } else if value.is_string() {
+        (ParameterKind::Text, ParameterValue::Text(value.into_string().unwrap_or_default()))
+    } else {
+        return Err(format!("parameter `{name}` has an unsupported type {}", value.type_name()));
+    };
+
+    Ok(Parameter {
+        name,
+        label,
+        kind,
+        default,
+    })
+}
+
+fn number(value: &Dynamic) -> Result<f64, Box<EvalAltResult>> {
+    value
+        .as_float()
+        .or_else(|_| value.as_int().map(|value| value as f64))
+        .map_err(|type_name| format!("expected a number, not {type_name}").into())
+}
+
+fn color(value: &Dynamic) -> Result<[u8; 3], Box<EvalAltResult>> {
+    let channels = value
+        .clone()
+        .try_cast::<Array>()
+        .filter(|channels| channels.len() == 3)
+        .ok_or_else(|| format!("expected a color like [255, 0, 0], not {}", value.type_name()))?;
+
+    Ok([
+        number(&channels[0])?.clamp(0.0, 255.0) as u8,
+        number(&channels[1])?.clamp(0.0, 255.0) as u8,
+        number(&channels[2])?.clamp(0.0, 255.0) as u8,
+    ])
+}
+
+fn point(x: &Dynamic, y: &Dynamic) -> Result<[f64; 2], Box<EvalAltResult>> {
+    Ok([number(x)?, number(y)?])
+}
+
+/// Takes `pixels` from what's left of a script's drawing budget, failing once it has drawn too much.
+fn spend(budget: &Cell<u64>, pixels: f64) -> Result<(), Box<EvalAltResult>> {
+    // at least one pixel, so even calls that draw nothing can't run forever for free
+    let pixels = pixels.max(1.0).min(u64::MAX as f64) as u64;
+    match budget.get().checked_sub(pixels) {
+        Some(left) => {
+            budget.set(left);
+            Ok(())
+        }
+        None => Err(format!("the script drew more than {MAX_PIXELS_DRAWN} pixels").into()),
+    }
+}
+
+/// How many pixels of `bmp` the box around `points`, grown by `margin`, covers.
+fn area(bmp: &Image, points: &[[f64; 2]], margin: f64) -> f64 {
+    let extent = |axis: usize, size: u32| {
+        let low = points.iter().map(|p| p[axis]).fold(f64::INFINITY, f64::min) - margin;
+        let high = points.iter().map(|p| p[axis]).fold(f64::NEG_INFINITY, f64::max) + margin;
+        (high.min(size as f64) - low.max(0.0)).max(0.0) + 1.0
+    };
+    extent(0, bmp.get_width()) * extent(1, bmp.get_height())
+}
+
+/// The whole canvas, for calls that redraw all of it.
+fn canvas_area(bmp: &Image) -> f64 {
+    bmp.get_width() as f64 * bmp.get_height() as f64
+}
+
+fn stripe_arguments(spacing: &Dynamic, num_colors: &Dynamic) -> Result<(u32, u32), Box<EvalAltResult>> {
+    let spacing = number(spacing)?;
+    let num_colors = number(num_colors)?;
+    if spacing < 1.0 || num_colors < 1.0 {
+        return Err("stripes need a spacing and number of colors of at least 1".into());
+    }
+    Ok((spacing as u32, num_colors as u32))
+}
+
+/// An engine with the drawing functions registered against `canvas`, and limits so scripts can't hang the app.
+/// Every drawing call is charged for the pixels it could cover against [`MAX_PIXELS_DRAWN`].
+fn new_engine(canvas: Rc<RefCell<Image>>) -> Engine {
+    let budget = Rc::new(Cell::new(MAX_PIXELS_DRAWN));
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new())
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(64)
+        .set_max_expr_depths(64, 64)
+        .set_max_string_size(1 << 16)
+        .set_max_array_size(1 << 20)
+        .set_max_map_size(1 << 16)
+        .on_print(|text| log::info!("{text}"))
+        .on_debug(|text, _, _| log::debug!("{text}"));
+
+    engine.register_fn("rgb", |r: Dynamic, g: Dynamic, b: Dynamic| -> Result<Array, Box<EvalAltResult>> {
+        [r, g, b].iter().map(|c| number(c).map(|c| Dynamic::from_int(c as i64))).collect()
+    });
+
+    let (bmp, spent) = (canvas.clone(), budget.clone());
+    // typed as an array so it's picked over Rhai's own `clear`, which empties an array
+    engine.register_fn("clear", move |color_value: Array| -> Result<(), Box<EvalAltResult>> {
+        let mut bmp = bmp.borrow_mut();
+        spend(&spent, canvas_area(&bmp))?;
+        let paint = Paint::Solid(color(&Dynamic::from_array(color_value))?);
+        *bmp = BmpGenerator::background(bmp.get_width(), bmp.get_height(), &paint);
+        Ok(())
+    });
+
+    let (bmp, spent) = (canvas.clone(), budget.clone());
+    engine.register_fn("pixel", move |x: Dynamic, y: Dynamic, color_value: Dynamic| -> Result<(), Box<EvalAltResult>> {
+        spend(&spent, 1.0)?;
+        let [x, y] = point(&x, &y)?;
+        let [r, g, b] = color(&color_value)?;
+        let mut bmp = bmp.borrow_mut();
+        if x >= 0.0 && y >= 0.0 && x < bmp.get_width() as f64 && y < bmp.get_height() as f64 {
+            bmp.set_pixel(x as u32, y as u32, Pixel::new(r, g, b));
+        }
+        Ok(())
+    });
+
+    let rect = |bmp: &RefCell<Image>, spent: &Cell<u64>, corners: [&Dynamic; 4], color_value: &Dynamic, rotation: f64| {
+        let start = point(corners[0], corners[1])?;
+        let end = point(corners[2], corners[3])?;
+        // a rotated rect stays within the circle through its corners
+        let pixels = match rotation == 0.0 {
+            true => area(&bmp.borrow(), &[start, end], 0.0),
+            false => {
+                let center = [(start[0] + end[0]) / 2.0, (start[1] + end[1]) / 2.0];
+                area(&bmp.borrow(), &[center], (end[0] - start[0]).hypot(end[1] - start[1]) / 2.0)
+            }
+        };
+        spend(spent, pixels)?;
+        let paint = Paint::Solid(color(color_value)?);
+        let (corner_radii, outline) = ([0.0; 4], Outline::default());
+        let transform = Transform::IDENTITY;
+        BmpGenerator::add_rect(&mut bmp.borrow_mut(), start, end, &paint, rotation, corner_radii, outline, &transform);
+        Ok::<_, Box<EvalAltResult>>(())
+    };
+    let (bmp, spent) = (canvas.clone(), budget.clone());
+    engine.register_fn(
+        "rect",
+        move |x0: Dynamic, y0: Dynamic, x1: Dynamic, y1: Dynamic, color_value: Dynamic| {
+            rect(&bmp, &spent, [&x0, &y0, &x1, &y1], &color_value, 0.0)
+        },
+    );
+    let (bmp, spent) = (canvas.clone(), budget.clone());
+    engine.register_fn(
+        "rect",
+        move |x0: Dynamic, y0: Dynamic, x1: Dynamic, y1: Dynamic, color_value: Dynamic, rotation: Dynamic| {
+            rect(&bmp, &spent, [&x0, &y0, &x1, &y1], &color_value, number(&rotation)?)
+        },
+    );
+
+    let ellipse = |bmp: &RefCell<Image>, spent: &Cell<u64>, shape: [&Dynamic; 4], color_value: &Dynamic, rotation: f64| {
+        let center = point(shape[0], shape[1])?;
+        let size = point(shape[2], shape[3])?;
+        spend(spent, area(&bmp.borrow(), &[center], size[0].abs().max(size[1].abs())))?;
+        let paint = Paint::Solid(color(color_value)?);
+        let outline = Outline::default();
+        BmpGenerator::add_ellipse(&mut bmp.borrow_mut(), center, size, rotation, &paint, outline, &Transform::IDENTITY);
+        Ok::<_, Box<EvalAltResult>>(())
+    };
+    let (bmp, spent) = (canvas.clone(), budget.clone());
+    engine.register_fn(
+        "ellipse",
+        move |cx: Dynamic, cy: Dynamic, rx: Dynamic, ry: Dynamic, color_value: Dynamic| {
+            ellipse(&bmp, &spent, [&cx, &cy, &rx, &ry], &color_value, 0.0)
+        },
+    );
+    let (bmp, spent) = (canvas.clone(), budget.clone());
+    engine.register_fn(
+        "ellipse",
+        move |cx: Dynamic, cy: Dynamic, rx: Dynamic, ry: Dynamic, color_value: Dynamic, rotation: Dynamic| {
+            ellipse(&bmp, &spent, [&cx, &cy, &rx, &ry], &color_value, number(&rotation)?)
+        },
+    );
+
+    let (bmp, spent) = (canvas.clone(), budget.clone());
+    engine.register_fn(
+        "triangle",
+        move |x0: Dynamic, y0: Dynamic, x1: Dynamic, y1: Dynamic, x2: Dynamic, y2: Dynamic, color_value: Dynamic| {
+            let points = [point(&x0, &y0)?, point(&x1, &y1)?, point(&x2, &y2)?];
+            spend(&spent, area(&bmp.borrow(), &points, 0.0))?;
+            let paint = Paint::Solid(color(&color_value)?);
+            BmpGenerator::add_triangle(&mut bmp.borrow_mut(), points, &paint, Outline::default(), &Transform::IDENTITY);
+            Ok::<_, Box<EvalAltResult>>(())
+        },
+    );
+
+    let (bmp, spent) = (canvas.clone(), budget.clone());
+    engine.register_fn("polygon", move |points: Array, color_value: Dynamic| {
+        let points = points
+            .iter()
+            .map(|p| match p.clone().try_cast::<Array>() {
+                Some(p) if p.len() == 2 => point(&p[0], &p[1]),
+                _ => Err("polygon points must be [x, y] arrays".into()),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // every edge is tested for every pixel
+        spend(&spent, area(&bmp.borrow(), &points, 0.0) * points.len().max(1) as f64)?;
+        let paint = Paint::Solid(color(&color_value)?);
+        let (fill_rule, outline) = (FillRule::NonZero, Outline::default());
+        BmpGenerator::add_polygon(&mut bmp.borrow_mut(), &points, fill_rule, &paint, outline, &Transform::IDENTITY);
+        Ok::<_, Box<EvalAltResult>>(())
+    });
+
+    let (bmp, spent) = (canvas.clone(), budget.clone());
+    engine.register_fn(
+        "line",
+        move |x0: Dynamic, y0: Dynamic, x1: Dynamic, y1: Dynamic, thickness: Dynamic, color_value: Dynamic| {
+            let (start, end, thickness) = (point(&x0, &y0)?, point(&x1, &y1)?, number(&thickness)?);
+            spend(&spent, area(&bmp.borrow(), &[start, end], thickness.abs()))?;
+            let paint = Paint::Solid(color(&color_value)?);
+            BmpGenerator::add_line(
+                &mut bmp.borrow_mut(),
+                start,
+                end,
+                thickness,
+                LineCap::Butt,
+                &paint,
+                &Transform::IDENTITY,
+            );
+            Ok::<_, Box<EvalAltResult>>(())
+        },
+    );
+
+    let (bmp, spent) = (canvas.clone(), budget.clone());
+    engine.register_fn("stripes", move |spacing: Dynamic, num_colors: Dynamic| {
+        let (spacing, num_colors) = stripe_arguments(&spacing, &num_colors)?;
+        let mut bmp = bmp.borrow_mut();
+        spend(&spent, canvas_area(&bmp))?;
+        *bmp = BmpGenerator::generate_stripes(bmp.get_width(), bmp.get_height(), spacing, num_colors)
+            .map_err(|err| err.to_string())?;
+        Ok::<_, Box<EvalAltResult>>(())
+    });
+
+    let (bmp, spent) = (canvas.clone(), budget.clone());
+    engine.register_fn("horizontal_stripes", move |spacing: Dynamic, num_colors: Dynamic| {
+        let (spacing, num_colors) = stripe_arguments(&spacing, &num_colors)?;
+        let mut bmp = bmp.borrow_mut();
+        spend(&spent, canvas_area(&bmp))?;
+        *bmp = BmpGenerator::generate_stripes_horizontal(bmp.get_width(), bmp.get_height(), spacing, num_colors)
+            .map_err(|err| err.to_string())?;
+        Ok::<_, Box<EvalAltResult>>(())
+    });
+
+    let (bmp, spent) = (canvas, budget);
+    engine.register_fn(
+        "text",
+        move |x: Dynamic, y: Dynamic, size: Dynamic, text: &str, color_value: Dynamic| {
+            // each glyph is rasterized whole, even where it's off the canvas
+            let size = number(&size)?;
+            spend(&spent, text.chars().count() as f64 * size * size)?;
+            let paint = Paint::Solid(color(&color_value)?);
+            BmpGenerator::add_text(&mut bmp.borrow_mut(), text, point(&x, &y)?, size, &paint, &Transform::IDENTITY)
+                .map_err(|err| err.to_string())?;
+            Ok::<_, Box<EvalAltResult>>(())
+        },
+    );
+
+    engine
+}
@@ -0,0 +1,45 @@
+//! Mirroring and rotating shapes about the middle of the canvas.
+
+use super::transform::Transform;
+
+/// How a shape is copied about the middle of the canvas. The middle is taken from the canvas each time the shape is
+/// drawn, so a pattern drawn again at another size keeps its copies in place relative to the edges.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Symmetry {
+    /// Mirrored left to right.
+    Horizontal,
+    /// Mirrored top to bottom.
+    Vertical,
+    /// Mirrored both ways, so a shape in one corner appears in all four.
+    Both,
+    /// Turned `folds` times by equal steps about the middle.
+    Rotational { folds: u32 },
+}
+
+impl Symmetry {
+    /// How many copies there are, counting the shape itself.
+    pub fn count(&self) -> u32 {
+        match *self {
+            Symmetry::Horizontal | Symmetry::Vertical => 2,
+            Symmetry::Both => 4,
+            Symmetry::Rotational { folds } => folds,
+        }
+    }
+
+    /// The transform for each copy on a `width` by `height` canvas, starting with the shape itself.
+    pub fn transforms(&self, width: u32, height: u32) -> Vec<Transform> {
+        let center = [width as f64 / 2.0, height as f64 / 2.0];
+        let flip_x = Transform::scale(-1.0, 1.0).around(center);
+        let flip_y = Transform::scale(1.0, -1.0).around(center);
+
+        match *self {
+            Symmetry::Horizontal => vec![Transform::IDENTITY, flip_x],
+            Symmetry::Vertical => vec![Transform::IDENTITY, flip_y],
+            Symmetry::Both => vec![Transform::IDENTITY, flip_x, flip_y, flip_x.then(flip_y)],
+            Symmetry::Rotational { folds } => (0..folds)
+                .map(|i| Transform::rotate(360.0 * i as f64 / folds as f64).around(center))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::f64::consts::PI;
+
+/// A 2-D affine transform, stored as a 3×3 matrix that maps the column vector `[x, y, 1]`. The last row is
+/// always `[0, 0, 1]`.
+///
+/// Transforms are built from the basic ones and chained with [`Transform::then`], so
+/// `Transform::scale(2.0, 2.0).then(Transform::rotate(30.0)).around([100.0, 100.0])` first doubles a shape's size
+/// and then turns it clockwise by 30 degrees, both about the point `100, 100`.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(transparent)]
+pub struct Transform {
+    pub matrix: [[f64; 3]; 3],
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        matrix: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+    };
+
+    fn affine(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Transform {
+        Transform {
+            matrix: [[a, b, c], [d, e, f], [0.0, 0.0, 1.0]],
+        }
+    }
+
+    pub fn translate(dx: f64, dy: f64) -> Transform {
+        Transform::affine(1.0, 0.0, dx, 0.0, 1.0, dy)
+    }
+
+    /// Rotates clockwise on the canvas, since y points down, by `degrees` about the origin.
+    pub fn rotate(degrees: f64) -> Transform {
+        let (sin, cos) = (degrees * PI / 180.0).sin_cos();
+        Transform::affine(cos, -sin, 0.0, sin, cos, 0.0)
+    }
+
+    pub fn scale(sx: f64, sy: f64) -> Transform {
+        Transform::affine(sx, 0.0, 0.0, 0.0, sy, 0.0)
+    }
+
+    /// Shears x by the tangent of `x_degrees` for every pixel down, and y by the tangent of `y_degrees` for every
+    /// pixel across.
+    pub fn skew(x_degrees: f64, y_degrees: f64) -> Transform {
+        let x = (x_degrees * PI / 180.0).tan();
+        let y = (y_degrees * PI / 180.0).tan();
+        Transform::affine(1.0, x, 0.0, y, 1.0, 0.0)
+    }
+
+    /// This transform followed by `next`.
+    pub fn then(&self, next: Transform) -> Transform {
+        let (a, b) = (next.matrix, self.matrix);
+        let mut matrix = [[0.0; 3]; 3];
+        for (row, out) in matrix.iter_mut().enumerate() {
+            for (column, value) in out.iter_mut().enumerate() {
+                *value = (0..3).map(|i| a[row][i] * b[i][column]).sum();
+            }
+        }
+        Transform { matrix }
+    }
+
+    /// The same transform done about `pivot` instead of the origin, so `pivot` stays where it is.
+    pub fn around(&self, pivot: [f64; 2]) -> Transform {
+        Transform::translate(-pivot[0], -pivot[1])
+            .then(*self)
+            .then(Transform::translate(pivot[0], pivot[1]))
+    }
+
+    pub fn apply(&self, point: [f64; 2]) -> [f64; 2] {
+        let m = self.matrix;
+        [
+            m[0][0] * point[0] + m[0][1] * point[1] + m[0][2],
+            m[1][0] * point[0] + m[1][1] * point[1] + m[1][2],
+        ]
+    }
+
+    pub fn apply_all(&self, points: &[[f64; 2]]) -> Vec<[f64; 2]> {
+        points.iter().map(|p| self.apply(*p)).collect()
+    }
+
+    /// The transform that undoes this one, or `None` when it squashes everything onto a line or a point.
+    pub fn inverse(&self) -> Option<Transform> {
+        let [[a, b, c], [d, e, f], _] = self.matrix;
+        let determinant = a * e - b * d;
+        if determinant.abs() < 1e-12 || !determinant.is_finite() {
+            return None;
+        }
+
+        Some(Transform::affine(
+            e / determinant,
+            -b / determinant,
+            (b * f - c * e) / determinant,
+            -d / determinant,
+            a / determinant,
+            (c * d - a * f) / determinant,
+        ))
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Transform::IDENTITY
+    }
+
+    /// The offset this transform moves by, if moving is all it does.
+    pub fn translation(&self) -> Option<[f64; 2]> {
+        let [[a, b, c], [d, e, f], _] = self.matrix;
+        (a == 1.0 && b == 0.0 && d == 0.0 && e == 1.0).then_some([c, f])
+    }
+
+    /// The `[min_x, min_y, max_x, max_y]` box around the axis-aligned box `bounds` once transformed.
+    pub fn bounds(&self, bounds: [f64; 4]) -> [f64; 4] {
+        let corners = self.apply_all(&[
+            [bounds[0], bounds[1]],
+            [bounds[2], bounds[1]],
+            [bounds[2], bounds[3]],
+            [bounds[0], bounds[3]],
+        ]);
+
+        [
+            corners.iter().map(|p| p[0]).fold(f64::INFINITY, f64::min),
+            corners.iter().map(|p| p[1]).fold(f64::INFINITY, f64::min),
+            corners.iter().map(|p| p[0]).fold(f64::NEG_INFINITY, f64::max),
+            corners.iter().map(|p| p[1]).fold(f64::NEG_INFINITY, f64::max),
+        ]
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::IDENTITY
+    }
+}
@@ -0,0 +1,101 @@
+//! Rough estimates of how long a shape takes to draw, counted in pixel tests, so patterns from elsewhere can be
+//! refused before drawing them.
+
+use super::bmp_generator::{Outline, PaintMode};
+use super::transform::Transform;
+
+/// The canvas pixels a shape drawn over `bounds`, grown by `margin`, goes through on a `size` canvas.
+pub fn clipped_area(bounds: [f64; 4], margin: f64, size: [u32; 2]) -> f64 {
+    let span = |min: f64, max: f64, end: u32| {
+        let start = (min - margin).floor().clamp(0.0, end as f64);
+        let stop = ((max + margin).ceil() + 1.0).clamp(0.0, end as f64);
+        (stop - start).max(0.0)
+    };
+    span(bounds[0], bounds[2], size[0]) * span(bounds[1], bounds[3], size[1])
+}
+
+/// The `[min_x, min_y, max_x, max_y]` extent of `points`.
+pub fn points_bounds(points: &[[f64; 2]]) -> [f64; 4] {
+    [
+        points.iter().map(|p| p[0]).fold(f64::INFINITY, f64::min),
+        points.iter().map(|p| p[1]).fold(f64::INFINITY, f64::min),
+        points.iter().map(|p| p[0]).fold(f64::NEG_INFINITY, f64::max),
+        points.iter().map(|p| p[1]).fold(f64::NEG_INFINITY, f64::max),
+    ]
+}
+
+/// Sums over the edges of an outline, enough to bound the pixels that stroking it goes through under any transform
+/// without going through the edges again for every copy.
+pub struct Edges {
+    count: f64,
+    x: f64,
+    y: f64,
+    xx: f64,
+    xy: f64,
+    yy: f64,
+}
+
+impl Edges {
+    /// The edges between consecutive `points`, and back to the first one when `closed`.
+    pub fn new(points: &[[f64; 2]], closed: bool) -> Edges {
+        let mut edges = Edges {
+            count: 0.0,
+            x: 0.0,
+            y: 0.0,
+            xx: 0.0,
+            xy: 0.0,
+            yy: 0.0,
+        };
+        let closing = match (closed, points.first(), points.last()) {
+            (true, Some(first), Some(last)) if points.len() > 2 => Some([*last, *first]),
+            _ => None,
+        };
+
+        for [a, b] in points.windows(2).map(|w| [w[0], w[1]]).chain(closing) {
+            let (dx, dy) = ((b[0] - a[0]).abs(), (b[1] - a[1]).abs());
+            edges.count += 1.0;
+            edges.x += dx;
+            edges.y += dy;
+            edges.xx += dx * dx;
+            edges.xy += dx * dy;
+            edges.yy += dy * dy;
+        }
+        edges
+    }
+
+    /// The pixels gone through testing every edge against the pixels within `reach` of it once placed by
+    /// `transform`: the box around each edge, and never more than `area` per edge.
+    pub fn work(&self, transform: &Transform, reach: f64, area: f64) -> f64 {
+        let [[a, b, _], [d, e, _], _] = transform.matrix.map(|row| row.map(f64::abs));
+        // each edge's box is its placed width and height, grown by the reach and rounding on every side
+        let grow = 2.0 * reach + 2.0;
+        let widths = a * self.x + b * self.y;
+        let heights = d * self.x + e * self.y;
+        let boxes = a * d * self.xx + (a * e + b * d) * self.xy + b * e * self.yy;
+
+        (boxes + grow * (widths + heights) + self.count * grow * grow).min(self.count * area)
+    }
+}
+
+/// The work of a filled and outlined shape placed by `transform`: `per_pixel` edge tests at each pixel around
+/// `bounds`, plus stroking `edges` when the outline is drawn.
+pub fn shape_work(
+    transform: &Transform,
+    bounds: [f64; 4],
+    per_pixel: f64,
+    outline: &Outline,
+    edges: Option<&Edges>,
+    size: [u32; 2],
+) -> f64 {
+    let reach = match outline.mode {
+        PaintMode::Fill => 0.0,
+        _ => outline.width.abs(),
+    };
+    let area = clipped_area(transform.bounds(bounds), reach, size);
+    let stroke = match (outline.mode, edges) {
+        (PaintMode::Fill, _) | (_, None) => 0.0,
+        (_, Some(edges)) => edges.work(transform, reach, area),
+    };
+
+    area * per_pixel + stroke
+}
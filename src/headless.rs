@@ -0,0 +1,390 @@
+//! Commands that run without opening the GUI, for scripting and CI.
+
+use crate::bmp_generator::bmp_generator::BmpGenerator;
+use crate::bmp_generator::compare::{self, Region};
+use crate::bmp_generator::description::check_canvas_size;
+use crate::bmp_generator::generators::{ParameterValue, Registry};
+use crate::bmp_generator::metadata;
+use crate::bmp_generator::raw::{self, PixelFormat, YuvEncoding};
+use crate::bmp_generator::scopes::LumaCoefficients;
+use crate::bmp_generator::script;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "Usage:
+  test_pattern_generator                      open the GUI
+  test_pattern_generator compare <reference> <captured> [options]
+  test_pattern_generator stream <generator> [options]
+  test_pattern_generator serve [--port N]     serve the HTTP render API on localhost (default port 8080)
+
+Compare options:
+  --offset DX,DY      shift applied to the captured frame before comparing
+  --align N           search up to N pixels in each direction for the best offset
+  --crop X,Y,W,H      only compare this part of the reference, which must lie inside both images once shifted
+  --diff PATH         write the per-pixel difference image
+  --diff-gain N       multiply the difference image by N (default 1)
+  --heatmap PATH      write a heatmap of the largest channel error per pixel
+  --min-psnr DB       pass when the PSNR is at least DB instead of requiring a bit-exact match
+  --min-ssim VALUE    pass when the SSIM is at least VALUE instead of requiring a bit-exact match
+
+Stream options:
+  --size WxH          frame size (default 1920x1080)
+  --set NAME=VALUE    set a generator parameter, colors as R,G,B (repeatable)
+  --scripts DIR       also load the script generators in DIR
+  --pattern PATH      stream a saved pattern (JSON, or an image with pattern metadata) instead of a generator
+  --trust-pattern     let the pattern run scripts and open image files, which are refused otherwise
+  --format FORMAT     rgb24, rgba, yuv444p or yuv420p (default rgb24)
+  --matrix MATRIX     bt709 or bt601 luma coefficients for YUV (default bt709)
+  --full-range        full range YUV instead of video levels
+  --fps N             frames per second (default 30)
+  --frames N          stop after N frames instead of running until the reader goes away
+  --output PATH       write to a file or named pipe instead of stdout
+
+Generators with `time` or `frame` parameters are animated: each frame is drawn with the seconds since the stream
+started and the frame number. Anything else is drawn once and repeated.
+
+Exit codes: 0 when the images match or the stream ends, 1 when they don't, 2 on bad arguments or unreadable files.";
+
+/// Runs the command given by `args` (without the program name) and returns the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    let result = match args.first().map(String::as_str) {
+        Some("compare") => compare_command(&args[1..]),
+        Some("stream") => stream_command(&args[1..]),
+        Some("serve") => serve_command(&args[1..]),
+        _ => Err(USAGE.to_owned()),
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(message) => {
+            eprintln!("{message}");
+            2
+        }
+    }
+}
+
+/// Parses a comma separated list of exactly `N` values, such as `10,20`.
+fn parse_list<T: FromStr, const N: usize>(option: &str, value: &str) -> Result<[T; N], String> {
+    let values: Vec<T> = value
+        .split(',')
+        .map(|v| v.trim().parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("{option}: expected {N} comma separated numbers, got `{value}`"))?;
+
+    values
+        .try_into()
+        .map_err(|_| format!("{option}: expected {N} comma separated numbers, got `{value}`"))
+}
+
+fn parse_value<T: FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{option}: invalid value `{value}`"))
+}
+
+fn compare_command(args: &[String]) -> Result<i32, String> {
+    let mut paths = vec![];
+    let mut offset = [0, 0];
+    let mut align = None;
+    let mut crop = None;
+    let mut diff_path = None;
+    let mut diff_gain = 1;
+    let mut heatmap_path = None;
+    let mut min_psnr = None;
+    let mut min_ssim = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            paths.push(arg.as_str());
+            continue;
+        }
+
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{arg}: missing value"))?;
+
+        match arg.as_str() {
+            "--offset" => offset = parse_list(arg, value)?,
+            "--align" => align = Some(parse_value(arg, value)?),
+            "--crop" => crop = Some(parse_list(arg, value)?),
+            "--diff" => diff_path = Some(value.as_str()),
+            "--diff-gain" => diff_gain = parse_value(arg, value)?,
+            "--heatmap" => heatmap_path = Some(value.as_str()),
+            "--min-psnr" => min_psnr = Some(parse_value::<f64>(arg, value)?),
+            "--min-ssim" => min_ssim = Some(parse_value::<f64>(arg, value)?),
+            _ => return Err(format!("Unknown option {arg}\n\n{USAGE}")),
+        }
+    }
+
+    let [reference_path, captured_path] = paths[..] else {
+        return Err(USAGE.to_owned());
+    };
+
+    let reference = BmpGenerator::open_image(reference_path).map_err(|e| e.to_string())?;
+    let captured = BmpGenerator::open_image(captured_path).map_err(|e| e.to_string())?;
+
+    if let Some(max_shift) = align {
+        offset = compare::find_alignment(&reference, &captured, crop, max_shift);
+    }
+
+    if let Err(message) = compare::check_region(&reference, &captured, offset, crop) {
+        println!("{message}");
+        println!("FAIL");
+        return Ok(1);
+    }
+
+    let region = Region::new(&reference, &captured, offset, crop);
+    let comparison = compare::compare(&reference, &captured, &region);
+
+    println!("offset: {},{}", offset[0], offset[1]);
+    println!(
+        "region: {},{} {}x{}",
+        region.crop[0], region.crop[1], comparison.width, comparison.height
+    );
+    println!("differing pixels: {}", comparison.differing_pixels);
+    for (i, channel) in ["red", "green", "blue"].iter().enumerate() {
+        println!(
+            "{channel}: max error {}, mean error {:.4}",
+            comparison.max_error[i], comparison.mean_error[i]
+        );
+    }
+    println!("psnr: {:.4} dB", comparison.psnr);
+    println!("ssim: {:.6}", comparison.ssim);
+
+    if let Some(path) = diff_path {
+        BmpGenerator::save_image(&compare::diff_image(&reference, &captured, &region, diff_gain), path)
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(path) = heatmap_path {
+        BmpGenerator::save_image(&compare::heatmap(&reference, &captured, &region, 255), path)
+            .map_err(|e| e.to_string())?;
+    }
+
+    let passed = match (min_psnr, min_ssim) {
+        (None, None) => comparison.is_identical(),
+        _ => {
+            min_psnr.map_or(true, |min| comparison.psnr >= min)
+                && min_ssim.map_or(true, |min| comparison.ssim >= min)
+        }
+    };
+
+    println!("{}", if passed { "PASS" } else { "FAIL" });
+
+    Ok(if passed { 0 } else { 1 })
+}
+
+#[cfg(feature = "http")]
+fn serve_command(args: &[String]) -> Result<i32, String> {
+    let port = match args {
+        [] => 8080,
+        [option, port] if option == "--port" => parse_value(option, port)?,
+        _ => return Err(USAGE.to_owned()),
+    };
+
+    crate::server::serve(port)?;
+    Ok(0)
+}
+
+#[cfg(not(feature = "http"))]
+fn serve_command(_args: &[String]) -> Result<i32, String> {
+    Err("serve: this build doesn't include the HTTP API, rebuild with `--features http`".to_owned())
+}
+
+/// A command line parameter value: a number, `true` or `false`, a color as `R,G,B`, or otherwise text. Generators
+/// convert between whole numbers and floats as they need.
+fn parse_parameter(value: &str) -> ParameterValue {
+    if let Ok(int) = value.parse::<i64>() {
+        return ParameterValue::Int(int);
+    }
+    if let Ok(float) = value.parse::<f64>() {
+        return ParameterValue::Float(float);
+    }
+    if let Ok(boolean) = value.parse::<bool>() {
+        return ParameterValue::Bool(boolean);
+    }
+    match parse_list::<u8, 3>("", value) {
+        Ok(color) => ParameterValue::Color(color),
+        Err(_) => ParameterValue::Text(value.to_owned()),
+    }
+}
+
+/// Draws the frame with the given number as raw bytes.
+type RenderFrame<'a> = Box<dyn FnMut(u64) -> Result<Vec<u8>, String> + 'a>;
+
+/// What a stream draws its frames from.
+enum Source {
+    Generator(String),
+    Pattern(String),
+}
+
+fn stream_command(args: &[String]) -> Result<i32, String> {
+    let mut source = None;
+    let mut size = [1920, 1080];
+    let mut parameters = BTreeMap::new();
+    let mut script_folder = None;
+    let mut format = PixelFormat::Rgb24;
+    let mut yuv = YuvEncoding::default();
+    let mut fps = 30.0;
+    let mut max_frames = None;
+    let mut output = None;
+    let mut trust_pattern = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            source = Some(Source::Generator(arg.clone()));
+            continue;
+        }
+        if arg == "--full-range" {
+            yuv.full_range = true;
+            continue;
+        }
+        if arg == "--trust-pattern" {
+            trust_pattern = true;
+            continue;
+        }
+
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{arg}: missing value"))?;
+
+        match arg.as_str() {
+            "--size" => size = parse_list(arg, &value.replace('x', ","))?,
+            "--set" => {
+                let (name, value) = value
+                    .split_once('=')
+                    .ok_or_else(|| format!("{arg}: expected NAME=VALUE, got `{value}`"))?;
+                parameters.insert(name.to_owned(), parse_parameter(value));
+            }
+            "--scripts" => script_folder = Some(value.as_str()),
+            "--pattern" => source = Some(Source::Pattern(value.clone())),
+            "--format" => format = value.parse()?,
+            "--matrix" => {
+                yuv.coefficients = match value.as_str() {
+                    "bt709" => LumaCoefficients::Bt709,
+                    "bt601" => LumaCoefficients::Bt601,
+                    _ => return Err(format!("{arg}: expected bt709 or bt601, got `{value}`")),
+                }
+            }
+            "--fps" => fps = parse_value::<f64>(arg, value)?,
+            "--frames" => max_frames = Some(parse_value::<u64>(arg, value)?),
+            "--output" => output = Some(value.as_str()),
+            _ => return Err(format!("Unknown option {arg}\n\n{USAGE}")),
+        }
+    }
+
+    let Some(source) = source else {
+        return Err(USAGE.to_owned());
+    };
+    if !(fps > 0.0 && fps.is_finite()) {
+        return Err(format!("--fps: must be more than 0, got {fps}"));
+    }
+    check_canvas_size(size[0], size[1]).map_err(|e| format!("--size: {e}"))?;
+
+    let mut registry = Registry::builtin();
+    if let Some(folder) = script_folder {
+        for script in script::load_folder(folder) {
+            registry.register(Box::new(script.map_err(|e| e.to_string())?));
+        }
+    }
+
+    // `render` draws the frame with the given number, and is only called once when nothing is animated
+    let (animated, mut render): (bool, RenderFrame<'_>) = match source {
+        Source::Generator(name) => {
+            let generator = registry
+                .get(&name)
+                .ok_or_else(|| format!("Unknown generator `{name}`"))?;
+            let animated = generator
+                .parameters()
+                .iter()
+                .any(|parameter| parameter.name == "time" || parameter.name == "frame");
+
+            let render = move |frame: u64| {
+                let mut parameters = parameters.clone();
+                if animated {
+                    parameters.insert("time".to_owned(), ParameterValue::Float(frame as f64 / fps));
+                    parameters.insert("frame".to_owned(), ParameterValue::Int(frame as i64));
+                }
+                let mut bmp = BmpGenerator::clear(size[0], size[1]);
+                generator
+                    .render(&mut bmp, &generator.values(&parameters))
+                    .map_err(|e| e.to_string())?;
+                Ok(raw::encode(&bmp, format, yuv))
+            };
+            (animated, Box::new(render))
+        }
+        Source::Pattern(path) => {
+            let pattern = match metadata::read_metadata(&path) {
+                Some(metadata) => metadata.pattern,
+                None => {
+                    let json = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {path}: {e}"))?;
+                    serde_json::from_str(&json).map_err(|e| format!("{path} isn't a pattern: {e}"))?
+                }
+            };
+            if !trust_pattern {
+                pattern
+                    .check_untrusted()
+                    .map_err(|e| format!("{path}: {e}; pass --trust-pattern to stream it anyway"))?;
+            }
+            let pattern = match [pattern.width, pattern.height] == size {
+                true => pattern,
+                false => pattern.resized(size[0], size[1]).map_err(|e| e.to_string())?,
+            };
+            (false, Box::new(move |_| Ok(raw::encode(&pattern.render().map_err(|e| e.to_string())?, format, yuv))))
+        }
+    };
+
+    let mut writer: Box<dyn Write> = match output {
+        Some(path) => Box::new(File::create(path).map_err(|e| format!("Failed to open {path}: {e}"))?),
+        None => Box::new(io::stdout().lock()),
+    };
+
+    let range = if yuv.full_range { "pc" } else { "tv" };
+    let color = match format.is_yuv() {
+        true => format!(" -color_range {range}"),
+        false => String::new(),
+    };
+    let [width, height] = size;
+    eprintln!("Streaming {width}x{height} {format} at {fps} fps, read it with:");
+    eprintln!(
+        "  ffmpeg -f rawvideo -pix_fmt {format} -video_size {width}x{height} -framerate {fps}{color} -i {}",
+        output.unwrap_or("-")
+    );
+
+    let still = match animated {
+        true => None,
+        false => Some(render(0)?),
+    };
+    let start = Instant::now();
+    let mut frame = 0;
+    while max_frames.map_or(true, |max| frame < max) {
+        let rendered;
+        let bytes = match &still {
+            Some(bytes) => bytes,
+            None => {
+                rendered = render(frame)?;
+                &rendered
+            }
+        };
+
+        // keep to the frame rate, but don't try to catch up by dropping frames when drawing falls behind
+        let due = start + Duration::from_secs_f64(frame as f64 / fps);
+        if let Some(wait) = due.checked_duration_since(Instant::now()) {
+            std::thread::sleep(wait);
+        }
+
+        match writer.write_all(bytes).and_then(|_| writer.flush()) {
+            Ok(()) => {}
+            // the reader closing the pipe is the normal way for an endless stream to stop
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Ok(0),
+            Err(err) => return Err(format!("Failed to write frame {frame}: {err}")),
+        }
+        frame += 1;
+    }
+
+    Ok(0)
+}
@@ -1,4 +1,53 @@
+//! Test patterns for checking displays, capture chains and image pipelines.
+//!
+//! Patterns are drawn on a [`Canvas`], either from one of the [generators](bmp_generator::generators) or shape by
+//! shape with the [builders](bmp_generator::builders), and then saved or read back as a pixel buffer:
+//!
+//! ```
+//! use test_pattern_generator::{Canvas, Ellipse, Generate, Rect};
+//!
+//! let mut canvas = Canvas::new(320, 240);
+//! canvas
+//!     .draw(Generate::new("Checkerboard", 320, 240))?
+//!     .draw(Rect::new([40.0, 40.0], [120.0, 100.0]).paint([255, 0, 0]))?
+//!     .draw(Ellipse::new([220.0, 120.0], [60.0, 40.0]).paint([0, 0, 255]))?;
+//!
+//! let rgb = canvas.to_rgb8();
+//! assert_eq!(rgb.len(), 320 * 240 * 3);
+//! assert_eq!(canvas.pixel(80, 70), [255, 0, 0]);
+//! # Ok::<(), test_pattern_generator::PatternError>(())
+//! ```
+//!
+//! Coordinates are in pixels from the top left corner of the canvas. They can be negative or fractional, so shapes
+//! can hang off any edge or sit at sub-pixel offsets, and whatever falls outside the canvas is clipped. Shapes can
+//! be moved, rotated, scaled and skewed together by putting them in a [`Group`] with a [`Transform`].
+//!
+//! Every canvas records how it was drawn as a [`PatternDescription`], which [`Canvas::save`] embeds in PNG and TIFF
+//! files so the pattern can be drawn again later.
+//!
+//! The egui app is behind the `gui` feature, on by default. Build with `default-features = false` to use only the
+//! library. The `http` feature adds a local HTTP API for rendering patterns, see [`server`].
+
 #![warn(clippy::all, rust_2018_idioms)]
 
+#[cfg(feature = "gui")]
 mod app;
+pub mod bmp_generator;
+pub mod headless;
+#[cfg(feature = "http")]
+pub mod server;
+
+#[cfg(feature = "gui")]
 pub use app::TestPatternGenerator;
+pub use bmp_generator::array::Arrangement;
+pub use bmp_generator::bmp_generator::{FillRule, LineCap, Outline, PaintMode, StrokeAlignment};
+pub use bmp_generator::builders::{Arc, Array, Bezier, Ellipse, Generate, Group, Line, Polygon, Rect, Text, Triangle};
+pub use bmp_generator::canvas::Canvas;
+pub use bmp_generator::colorimetry::Chromaticity;
+pub use bmp_generator::description::{Operation, PatternDescription};
+pub use bmp_generator::edid::Edid;
+pub use bmp_generator::error::PatternError;
+pub use bmp_generator::generators::{ParameterValue, PatternGenerator, Registry};
+pub use bmp_generator::paint::Paint;
+pub use bmp_generator::symmetry::Symmetry;
+pub use bmp_generator::transform::Transform;
@@ -2,10 +2,16 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
 // When compiling natively:
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "gui"))]
 fn main() -> eframe::Result<()> {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
+    // Any arguments run a headless command instead of the GUI.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(test_pattern_generator::headless::run(&args));
+    }
+
     let native_options = eframe::NativeOptions::default();
     eframe::run_native(
         "Test Pattern Generator",
@@ -14,6 +20,13 @@ fn main() -> eframe::Result<()> {
     )
 }
 
+// Without the GUI only the headless commands are available.
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "gui")))]
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    std::process::exit(test_pattern_generator::headless::run(&args));
+}
+
 // When compiling to web using trunk:
 #[cfg(target_arch = "wasm32")]
 fn main() {
@@ -27,7 +40,7 @@ fn main() {
             .start(
                 "the_canvas_id", // hardcode it
                 web_options,
-                Box::new(|cc| Box::new(eframe_template::TemplateApp::new(cc))),
+                Box::new(|cc| Box::new(test_pattern_generator::TestPatternGenerator::new(cc))),
             )
             .await
             .expect("failed to start eframe");
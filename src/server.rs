@@ -0,0 +1,263 @@
+//! A local HTTP API for drawing patterns from other programs, started with the `serve` command.
+//!
+//! - `GET /generators` lists the generators a pattern can use, with their parameters, as JSON.
+//! - `POST /render?format=png` takes a [`PatternDescription`] as JSON and returns the drawn image. The format is
+//!   `png` (the default), `bmp`, `tiff` or `jpeg`, or one of the raw [`PixelFormat`]s for the bare pixels.
+//!
+//! Patterns are only drawn, never run: anything with a script or an image file is refused, as are patterns that
+//! would take too long to draw. Each request is drawn on a worker thread and given up on after
+//! [`RENDER_TIMEOUT`], in case the estimate was wrong. The server still only listens on `127.0.0.1`, since it has
+//! no authentication.
+
+use std::io::{Cursor, Read};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+use image::ImageOutputFormat;
+use serde_json::{json, Value};
+
+use crate::bmp_generator::bmp_generator::MAX_TEXT_SIZE;
+use crate::bmp_generator::canvas::Canvas;
+use crate::bmp_generator::description::{Operation, PatternDescription, MAX_CANVAS_PIXELS};
+use crate::bmp_generator::error::PatternError;
+use crate::bmp_generator::generators::{ParameterKind, Registry};
+use crate::bmp_generator::raw::{self, PixelFormat, YuvEncoding};
+
+/// The largest pattern body accepted.
+const MAX_BODY: u64 = 16 * 1024 * 1024;
+/// The most shapes a pattern may draw, counting every copy made by arrays and symmetry and every character of text.
+const MAX_SHAPES: u64 = 1_000_000;
+/// The most pixel tests a pattern may take to draw, as estimated by [`PatternDescription::estimated_work`]. Enough
+/// to fill an 8K canvas 16 times over.
+const MAX_WORK: u64 = 16 * MAX_CANVAS_PIXELS;
+/// How long a request may take before it's answered with an error. A worker that runs over carries on drawing in
+/// the background, and still counts as busy until it's done.
+pub const RENDER_TIMEOUT: Duration = Duration::from_secs(10);
+/// The most requests answered at once. More are turned away until one finishes, rather than piling up.
+const MAX_WORKERS: usize = 4;
+
+/// The number of workers answering requests right now.
+static WORKERS: AtomicUsize = AtomicUsize::new(0);
+
+/// A claim on one of the [`MAX_WORKERS`], given back when it's dropped.
+struct Worker;
+
+impl Worker {
+    fn claim() -> Option<Worker> {
+        match WORKERS.fetch_add(1, Ordering::SeqCst) < MAX_WORKERS {
+            true => Some(Worker),
+            false => {
+                WORKERS.fetch_sub(1, Ordering::SeqCst);
+                None
+            }
+        }
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        WORKERS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// What to send back for a request.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reply {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Reply {
+    fn json(status: u16, value: &Value) -> Reply {
+        Reply {
+            status,
+            content_type: "application/json",
+            body: value.to_string().into_bytes(),
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Reply {
+        Reply::json(status, &json!({ "error": message.into() }))
+    }
+}
+
+/// Serves the API on `127.0.0.1:port` until the process is stopped.
+pub fn serve(port: u16) -> Result<(), String> {
+    let address = format!("127.0.0.1:{port}");
+    let server = tiny_http::Server::http(&address).map_err(|e| format!("Failed to listen on {address}: {e}"))?;
+    eprintln!("Listening on http://{address}");
+
+    // each request is read and answered on its own thread, so a slow one doesn't hold up the rest
+    for request in server.incoming_requests() {
+        std::thread::spawn(move || handle(request));
+    }
+
+    Ok(())
+}
+
+fn handle(mut request: tiny_http::Request) {
+    let mut body = Vec::new();
+    let reply = match request.as_reader().take(MAX_BODY + 1).read_to_end(&mut body) {
+        Ok(_) if body.len() as u64 > MAX_BODY => Reply::error(413, "the pattern is too large"),
+        Ok(_) => respond_in_time(request.method().as_str(), request.url(), body, RENDER_TIMEOUT),
+        Err(err) => Reply::error(400, format!("failed to read the request: {err}")),
+    };
+    log::info!("{} {} {}", request.method(), request.url(), reply.status);
+
+    let content_type = tiny_http::Header::from_bytes("Content-Type", reply.content_type).expect("valid header");
+    let response = tiny_http::Response::from_data(reply.body)
+        .with_status_code(reply.status)
+        .with_header(content_type);
+    if let Err(err) = request.respond(response) {
+        log::warn!("Failed to send a response: {err}");
+    }
+}
+
+/// [`respond`] on a worker thread, answering 503 when the worker takes longer than `timeout` or too many workers
+/// are busy already.
+pub fn respond_in_time(method: &str, url: &str, body: Vec<u8>, timeout: Duration) -> Reply {
+    let Some(worker) = Worker::claim() else {
+        return Reply::error(503, "the server is busy drawing other patterns, try again later");
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let (method, url) = (method.to_owned(), url.to_owned());
+    std::thread::spawn(move || {
+        let _worker = worker;
+        // the receiver is gone when the request already timed out
+        let _ = sender.send(respond(&method, &url, &body));
+    });
+
+    match receiver.recv_timeout(timeout) {
+        Ok(reply) => reply,
+        Err(RecvTimeoutError::Timeout) => {
+            Reply::error(503, format!("drawing the pattern took longer than {} seconds", timeout.as_secs_f64()))
+        }
+        Err(RecvTimeoutError::Disconnected) => Reply::error(500, "drawing the pattern failed"),
+    }
+}
+
+/// Answers a request for `url` with `body`, without any networking so it can be called directly.
+pub fn respond(method: &str, url: &str, body: &[u8]) -> Reply {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    match (method, path) {
+        ("GET", "/generators") => Reply::json(200, &generators(&Registry::builtin())),
+        ("POST", "/render") => render(query, body),
+        (_, "/generators" | "/render") => Reply::error(405, format!("{method} isn't supported on {path}")),
+        _ => Reply::error(404, format!("no such endpoint {path}, use GET /generators or POST /render")),
+    }
+}
+
+fn generators(registry: &Registry) -> Value {
+    let generators: Vec<Value> = registry
+        .iter()
+        .map(|generator| {
+            let parameters: Vec<Value> = generator
+                .parameters()
+                .into_iter()
+                .map(|parameter| {
+                    let mut value = json!({
+                        "name": parameter.name,
+                        "label": parameter.label,
+                        "default": parameter.default,
+                    });
+                    let kind = match parameter.kind {
+                        ParameterKind::Int { min, max } => json!({ "type": "int", "min": min, "max": max }),
+                        ParameterKind::Float { min, max } => json!({ "type": "float", "min": min, "max": max }),
+                        ParameterKind::Bool => json!({ "type": "bool" }),
+                        ParameterKind::Color => json!({ "type": "color" }),
+                        ParameterKind::Text => json!({ "type": "text" }),
+                    };
+                    value.as_object_mut().unwrap().extend(kind.as_object().unwrap().clone());
+                    value
+                })
+                .collect();
+            json!({ "name": generator.name(), "parameters": parameters })
+        })
+        .collect();
+    Value::Array(generators)
+}
+
+fn render(query: &str, body: &[u8]) -> Reply {
+    let format = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("format="))
+        .unwrap_or("png");
+
+    let pattern: PatternDescription = match serde_json::from_slice(body) {
+        Ok(pattern) => pattern,
+        Err(err) => return Reply::error(400, format!("not a pattern description: {err}")),
+    };
+    let canvas_sizes = pattern.operations.iter().filter_map(|operation| match operation {
+        Operation::Background { width, height, .. } | Operation::Generate { width, height, .. } => {
+            Some([*width, *height])
+        }
+        _ => None,
+    });
+    let too_large = std::iter::once([pattern.width, pattern.height])
+        .chain(canvas_sizes)
+        .any(|[width, height]| width as u64 * height as u64 > MAX_CANVAS_PIXELS);
+    if too_large {
+        return Reply::error(422, "the canvas is larger than 8192 by 8192 pixels");
+    }
+    if let Err(err) = pattern.check_untrusted() {
+        return Reply::error(422, err.to_string());
+    }
+    let shapes = pattern
+        .operations
+        .iter()
+        .fold(0, |total: u64, operation| total.saturating_add(operation.shapes_drawn()));
+    if shapes > MAX_SHAPES {
+        return Reply::error(422, format!("the pattern draws more than {MAX_SHAPES} shapes"));
+    }
+    if pattern.operations.iter().any(has_large_text) {
+        return Reply::error(422, format!("text must be between 0 and {MAX_TEXT_SIZE} pixels tall"));
+    }
+    if pattern.estimated_work() > MAX_WORK {
+        return Reply::error(422, "the pattern would take too long to draw");
+    }
+
+    let canvas = match Canvas::from_description(&pattern) {
+        Ok(canvas) => canvas,
+        Err(err @ (PatternError::Open { .. } | PatternError::Save { .. })) => return Reply::error(500, err.to_string()),
+        Err(err) => return Reply::error(422, err.to_string()),
+    };
+
+    if let Ok(pixel_format) = format.parse::<PixelFormat>() {
+        return Reply {
+            status: 200,
+            content_type: "application/octet-stream",
+            body: raw::encode(canvas.image(), pixel_format, YuvEncoding::default()),
+        };
+    }
+
+    let (output_format, content_type) = match format {
+        "png" => (ImageOutputFormat::Png, "image/png"),
+        "bmp" => (ImageOutputFormat::Bmp, "image/bmp"),
+        "tiff" => (ImageOutputFormat::Tiff, "image/tiff"),
+        "jpeg" | "jpg" => (ImageOutputFormat::Jpeg(95), "image/jpeg"),
+        _ => return Reply::error(400, format!("unknown format `{format}`")),
+    };
+
+    let mut encoded = Cursor::new(Vec::new());
+    match canvas.to_rgb_image().write_to(&mut encoded, output_format) {
+        Ok(()) => Reply {
+            status: 200,
+            content_type,
+            body: encoded.into_inner(),
+        },
+        Err(err) => Reply::error(500, format!("failed to encode the image: {err}")),
+    }
+}
+
+/// Whether `operation` draws text taller than [`MAX_TEXT_SIZE`], anywhere inside it.
+fn has_large_text(operation: &Operation) -> bool {
+    match operation {
+        Operation::Text { size, .. } => !(0.0..=MAX_TEXT_SIZE).contains(size),
+        Operation::Group { operations, .. } => operations.iter().any(has_large_text),
+        Operation::Array { operation, .. } | Operation::Symmetric { operation, .. } => has_large_text(operation),
+        _ => false,
+    }
+}
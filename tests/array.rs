@@ -0,0 +1,45 @@
+use test_pattern_generator::{Array, Canvas, Group, PatternError, Rect, Transform};
+
+#[test]
+fn grid_copies_are_drawn_where_they_belong() {
+    let mut canvas = Canvas::new(40, 20);
+    canvas.draw(Array::grid(Rect::new([0.0, 0.0], [5.0, 5.0]), 4, 2, [10.0, 10.0])).unwrap();
+
+    assert_eq!(canvas.pixel(32, 12), [255, 255, 255]);
+    assert_eq!(canvas.pixel(37, 17), [0, 0, 0]);
+}
+
+#[test]
+fn too_many_copies_are_refused_before_drawing() {
+    let mut canvas = Canvas::new(40, 20);
+    let err = canvas
+        .draw(Array::grid(Rect::new([0.0, 0.0], [1.0, 1.0]), 1_000_000, 1_000_000, [0.0, 0.0]))
+        .unwrap_err();
+
+    assert!(matches!(err, PatternError::InvalidParameter { .. }), "{err}");
+    assert_eq!(canvas.pixel(0, 0), [0, 0, 0]);
+}
+
+#[test]
+fn nested_arrays_are_limited_by_their_total_copies() {
+    let mut canvas = Canvas::new(40, 20);
+    let inner = Array::linear(Rect::new([0.0, 0.0], [1.0, 1.0]), 100_000, [0.0, 0.0]);
+
+    let err = canvas.draw(Array::linear(inner.clone(), 100, [0.0, 0.0])).unwrap_err();
+    assert!(matches!(err, PatternError::InvalidParameter { .. }), "{err}");
+
+    let grouped = Group::new(Transform::IDENTITY).with(Rect::new([0.0, 0.0], [1.0, 1.0])).with(inner);
+    let err = canvas.draw(Array::linear(grouped, 2, [0.0, 0.0])).unwrap_err();
+    assert!(matches!(err, PatternError::InvalidParameter { .. }), "{err}");
+
+    assert_eq!(canvas.pixel(0, 0), [0, 0, 0]);
+}
+
+#[test]
+fn nested_arrays_within_the_limit_draw_every_copy() {
+    let mut canvas = Canvas::new(40, 20);
+    let row = Array::linear(Rect::new([0.0, 0.0], [1.0, 1.0]), 40, [1.0, 0.0]);
+    canvas.draw(Array::linear(row, 20, [0.0, 1.0])).unwrap();
+
+    assert!((0..40).all(|x| (0..20).all(|y| canvas.pixel(x, y) == [255, 255, 255])));
+}
@@ -0,0 +1,54 @@
+use test_pattern_generator::{Canvas, Generate, Operation, Paint, PatternDescription, PatternError};
+
+fn is_size_error(err: PatternError) -> bool {
+    matches!(err, PatternError::InvalidParameter { ref parameter, .. } if parameter == "size")
+}
+
+#[test]
+fn canvases_must_have_a_pixel() {
+    assert!(is_size_error(Canvas::try_new(0, 1080).unwrap_err()));
+    assert!(is_size_error(Canvas::try_new(1920, 0).unwrap_err()));
+    assert_eq!(Canvas::try_new(1, 1).unwrap().width(), 1);
+}
+
+#[test]
+fn canvases_are_at_most_8192_by_8192_pixels() {
+    assert!(Canvas::try_new(8192, 8192).is_ok());
+    assert!(is_size_error(Canvas::try_new(8193, 8192).unwrap_err()));
+    // the pixel count would overflow 32 bits
+    assert!(is_size_error(Canvas::try_new(70_000, 70_000).unwrap_err()));
+    assert!(is_size_error(Canvas::try_new(u32::MAX, u32::MAX).unwrap_err()));
+}
+
+#[test]
+#[should_panic(expected = "canvas size")]
+fn new_panics_on_sizes_try_new_refuses() {
+    Canvas::new(70_000, 70_000);
+}
+
+#[test]
+fn oversized_operations_are_refused_before_drawing() {
+    let mut canvas = Canvas::new(4, 4);
+
+    let background = Operation::Background {
+        width: 70_000,
+        height: 70_000,
+        paint: Paint::Solid([255, 255, 255]),
+    };
+    assert!(is_size_error(canvas.draw(background).unwrap_err()));
+    assert!(is_size_error(canvas.draw(Generate::new("Stripes", 100_000, 1000)).unwrap_err()));
+
+    assert_eq!([canvas.width(), canvas.height()], [4, 4]);
+}
+
+#[test]
+fn oversized_descriptions_are_refused() {
+    let description = PatternDescription {
+        width: 70_000,
+        height: 70_000,
+        operations: Vec::new(),
+    };
+
+    assert!(is_size_error(Canvas::from_description(&description).unwrap_err()));
+    assert!(is_size_error(description.render().unwrap_err()));
+}
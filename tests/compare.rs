@@ -0,0 +1,101 @@
+use bmp::{Image, Pixel};
+use test_pattern_generator::bmp_generator::compare::{self, Region};
+
+fn filled(width: u32, height: u32, color: [u8; 3]) -> Image {
+    let mut image = Image::new(width, height);
+    for (x, y) in image.coordinates() {
+        image.set_pixel(x, y, Pixel::new(color[0], color[1], color[2]));
+    }
+    image
+}
+
+#[test]
+fn identical_images_match() {
+    let reference = filled(64, 48, [10, 20, 30]);
+    let region = Region::new(&reference, &reference, [0, 0], None);
+    let comparison = compare::compare(&reference, &reference, &region);
+
+    assert!(comparison.is_identical());
+    assert_eq!([comparison.width, comparison.height], [64, 48]);
+    assert_eq!(comparison.psnr, f64::INFINITY);
+    assert_eq!(comparison.ssim, 1.0);
+}
+
+#[test]
+fn a_smaller_capture_does_not_cover_the_reference() {
+    let reference = filled(1920, 1080, [0, 0, 0]);
+    let captured = filled(1280, 720, [0, 0, 0]);
+
+    assert!(compare::check_region(&reference, &captured, [0, 0], None).is_err());
+    assert!(compare::check_region(&captured, &reference, [0, 0], None).is_ok());
+    assert!(compare::check_region(&reference, &reference, [0, 0], None).is_ok());
+}
+
+#[test]
+fn a_crop_must_lie_inside_both_images() {
+    let reference = filled(20, 20, [0, 0, 0]);
+    let captured = filled(10, 20, [0, 0, 0]);
+
+    // the overlap of the crop and the frame would match, but half the crop isn't in the frame
+    assert!(compare::check_region(&reference, &captured, [0, 0], Some([5, 0, 10, 10])).is_err());
+    assert!(compare::check_region(&reference, &captured, [0, 0], Some([0, 0, 10, 10])).is_ok());
+    assert!(compare::check_region(&reference, &reference, [0, 0], Some([15, 0, 10, 10])).is_err());
+    assert!(compare::check_region(&reference, &reference, [0, 0], Some([0, 0, 20, 20])).is_ok());
+}
+
+#[test]
+fn an_offset_must_keep_the_region_inside_the_capture() {
+    let reference = filled(20, 20, [0, 0, 0]);
+
+    assert!(compare::check_region(&reference, &reference, [2, 0], None).is_err());
+    assert!(compare::check_region(&reference, &reference, [0, -1], None).is_err());
+    assert!(compare::check_region(&reference, &reference, [2, 0], Some([0, 0, 10, 10])).is_ok());
+    assert!(compare::check_region(&reference, &reference, [-2, 0], Some([0, 0, 10, 10])).is_err());
+    assert!(compare::check_region(&reference, &filled(22, 20, [0, 0, 0]), [2, 0], None).is_ok());
+}
+
+#[test]
+fn alignment_only_tries_offsets_that_keep_the_region_inside_the_capture() {
+    let mut reference = filled(20, 20, [0, 0, 0]);
+    let mut captured = filled(20, 20, [0, 0, 0]);
+    reference.set_pixel(8, 8, Pixel::new(255, 255, 255));
+    captured.set_pixel(10, 8, Pixel::new(255, 255, 255));
+
+    // the whole reference only fits in the frame unshifted
+    assert_eq!(compare::find_alignment(&reference, &captured, None, 3), [0, 0]);
+    assert_eq!(compare::find_alignment(&reference, &captured, Some([4, 4, 10, 10]), 3), [2, 0]);
+}
+
+#[test]
+fn an_empty_region_is_not_a_match() {
+    let reference = filled(16, 16, [0, 0, 0]);
+    let region = Region::new(&reference, &reference, [32, 0], None);
+    let comparison = compare::compare(&reference, &reference, &region);
+
+    assert_eq!(comparison.width, 0);
+    assert!(!comparison.is_identical());
+    assert_eq!(comparison.psnr, 0.0);
+    assert_eq!(comparison.ssim, 0.0);
+}
+
+#[test]
+fn a_large_crop_is_clipped_to_the_images() {
+    let reference = filled(16, 16, [0, 0, 0]);
+    let region = Region::new(&reference, &reference, [0, 0], Some([4, 4, u32::MAX, u32::MAX]));
+
+    assert_eq!(region.crop, [4, 4, 12, 12]);
+}
+
+#[test]
+fn errors_are_measured_per_channel() {
+    let reference = filled(16, 16, [100, 100, 100]);
+    let captured = filled(16, 16, [104, 100, 98]);
+    let region = Region::new(&reference, &captured, [0, 0], None);
+    let comparison = compare::compare(&reference, &captured, &region);
+
+    assert!(!comparison.is_identical());
+    assert_eq!(comparison.max_error, [4, 0, 2]);
+    assert_eq!(comparison.mean_error, [4.0, 0.0, 2.0]);
+    assert_eq!(comparison.differing_pixels, 256);
+    assert!(comparison.psnr.is_finite() && comparison.psnr > 0.0);
+}
@@ -0,0 +1,110 @@
+use test_pattern_generator::bmp_generator::edid::{Colorimetry, EdidError, Eotf};
+use test_pattern_generator::Edid;
+
+/// A 2560×1440 monitor with only the base block.
+const MONITOR: &[u8] = include_bytes!("data/monitor_qhd.bin");
+/// A 3840×2160 HDR TV with a CTA-861 extension.
+const TV: &[u8] = include_bytes!("data/tv_uhd_hdr.bin");
+
+fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+    assert!((actual - expected).abs() <= tolerance, "{actual} is not within {tolerance} of {expected}");
+}
+
+#[test]
+fn parses_the_base_block() {
+    let edid = Edid::parse(MONITOR).unwrap();
+
+    assert_eq!(edid.manufacturer, "DEL");
+    assert_eq!(edid.product_code, 0xa0c1);
+    assert_eq!(edid.serial_number, 0x1234_5678);
+    assert_eq!(edid.year, 2021);
+    assert_eq!(edid.version, [1, 4]);
+    assert_eq!(edid.name.as_deref(), Some("TEST QHD"));
+    assert_close(edid.gamma.unwrap(), 2.2, 1e-9);
+    assert!(edid.hdr.is_none());
+    assert!(edid.colorimetry.is_empty());
+}
+
+#[test]
+fn reads_the_native_resolution_from_the_preferred_timing() {
+    let edid = Edid::parse(MONITOR).unwrap();
+
+    assert_eq!(edid.native_resolution(), Some([2560, 1440]));
+    assert_eq!(edid.timings.len(), 1);
+    let timing = edid.timings[0];
+    assert_eq!(timing.pixel_clock_khz, 241_500);
+    assert!(!timing.interlaced);
+    assert_close(timing.refresh_rate, 59.95, 0.01);
+}
+
+#[test]
+fn reads_chromaticities_to_ten_bits() {
+    let chromaticity = Edid::parse(MONITOR).unwrap().chromaticity;
+    let step = 1.0 / 1024.0;
+
+    assert_close(chromaticity.red[0], 0.640, step);
+    assert_close(chromaticity.red[1], 0.330, step);
+    assert_close(chromaticity.green[0], 0.300, step);
+    assert_close(chromaticity.green[1], 0.600, step);
+    assert_close(chromaticity.blue[0], 0.150, step);
+    assert_close(chromaticity.blue[1], 0.060, step);
+    assert_close(chromaticity.white[0], 0.3127, step);
+    assert_close(chromaticity.white[1], 0.3290, step);
+}
+
+#[test]
+fn parses_the_cta_extension() {
+    let edid = Edid::parse(TV).unwrap();
+
+    assert_eq!(edid.manufacturer, "SAM");
+    assert_eq!(edid.name.as_deref(), Some("TEST UHD TV"));
+    assert_eq!(edid.native_resolution(), Some([3840, 2160]));
+    assert_close(edid.timings[0].refresh_rate, 60.0, 1e-9);
+
+    // the second timing is in the extension, and interlaced
+    assert_eq!(edid.timings.len(), 2);
+    assert_eq!([edid.timings[1].width, edid.timings[1].height], [1920, 1080]);
+    assert!(edid.timings[1].interlaced);
+
+    assert_eq!(
+        edid.colorimetry,
+        [Colorimetry::Bt2020Ycc, Colorimetry::Bt2020Rgb, Colorimetry::DciP3]
+    );
+}
+
+#[test]
+fn parses_hdr_static_metadata() {
+    let hdr = Edid::parse(TV).unwrap().hdr.unwrap();
+
+    assert_eq!(hdr.eotfs, [Eotf::TraditionalSdr, Eotf::Pq, Eotf::Hlg]);
+    assert_close(hdr.max_luminance.unwrap(), 603.67, 0.01);
+    assert_close(hdr.max_frame_average_luminance.unwrap(), 351.25, 0.01);
+    assert_close(hdr.min_luminance.unwrap(), 0.3342, 1e-4);
+}
+
+#[test]
+fn skips_extensions_missing_from_the_dump() {
+    let edid = Edid::parse(&TV[..128]).unwrap();
+
+    assert_eq!(edid.native_resolution(), Some([3840, 2160]));
+    assert_eq!(edid.timings.len(), 1);
+    assert!(edid.hdr.is_none());
+}
+
+#[test]
+fn rejects_broken_blobs() {
+    assert!(matches!(Edid::parse(&[]), Err(EdidError::TooShort(0))));
+    assert!(matches!(Edid::parse(&MONITOR[..100]), Err(EdidError::TooShort(100))));
+
+    let mut header = MONITOR.to_vec();
+    header[0] = 0x01;
+    assert!(matches!(Edid::parse(&header), Err(EdidError::BadHeader)));
+
+    let mut base = MONITOR.to_vec();
+    base[20] ^= 0x01;
+    assert!(matches!(Edid::parse(&base), Err(EdidError::Checksum(0))));
+
+    let mut extension = TV.to_vec();
+    extension[200] ^= 0x01;
+    assert!(matches!(Edid::parse(&extension), Err(EdidError::Checksum(1))));
+}
@@ -0,0 +1,52 @@
+use bmp::Image;
+use test_pattern_generator::{Canvas, Generate, Registry};
+
+#[test]
+fn every_builtin_generator_renders_with_its_defaults() {
+    let registry = Registry::builtin();
+    assert!(registry.iter().next().is_some());
+
+    for generator in registry.iter() {
+        let values = generator.values(&generator.default_values());
+        let mut bmp = Image::new(320, 180);
+        generator
+            .render(&mut bmp, &values)
+            .unwrap_or_else(|err| panic!("{} failed to render: {err}", generator.name()));
+
+        assert_eq!([bmp.get_width(), bmp.get_height()], [320, 180], "{} changed the canvas size", generator.name());
+        let first = bmp.get_pixel(0, 0);
+        assert!(
+            bmp.coordinates().any(|(x, y)| bmp.get_pixel(x, y) != first),
+            "{} drew a blank canvas",
+            generator.name()
+        );
+    }
+}
+
+#[test]
+fn every_builtin_generator_can_be_drawn_by_name() {
+    for generator in Registry::builtin().iter() {
+        let mut canvas = Canvas::new(64, 48);
+        canvas
+            .draw(Generate::new(generator.name(), 64, 48))
+            .unwrap_or_else(|err| panic!("{} failed to draw: {err}", generator.name()));
+    }
+}
+
+#[test]
+fn generator_names_are_unique() {
+    let registry = Registry::builtin();
+    let mut names: Vec<&str> = registry.iter().map(|generator| generator.name()).collect();
+    let count = names.len();
+    names.sort_unstable();
+    names.dedup();
+    assert_eq!(names.len(), count);
+}
+
+#[test]
+fn generating_an_empty_canvas_is_an_error() {
+    let mut canvas = Canvas::new(64, 48);
+    assert!(canvas.draw(Generate::new("Stripes", 0, 48)).is_err());
+    assert!(canvas.draw(Generate::new("Stripes", 64, 0)).is_err());
+    assert_eq!([canvas.width(), canvas.height()], [64, 48]);
+}
@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use test_pattern_generator::{headless, Operation, PatternDescription, Rect};
+
+/// A file in the temp folder that is removed again when the test ends.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str) -> Self {
+        Self(std::env::temp_dir().join(format!("test_pattern_generator_headless_{}_{name}", std::process::id())))
+    }
+
+    fn path(&self) -> String {
+        self.0.to_string_lossy().into_owned()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn script_pattern() -> PatternDescription {
+    PatternDescription {
+        width: 4,
+        height: 2,
+        operations: vec![Operation::Script {
+            name: "fill".to_owned(),
+            source: "clear([255, 0, 0]);".to_owned(),
+            parameters: BTreeMap::new(),
+        }],
+    }
+}
+
+fn stream(pattern: &TempFile, output: &TempFile, extra: &[&str]) -> i32 {
+    let mut args: Vec<String> = ["stream", "--pattern", &pattern.path(), "--size", "4x2", "--frames", "1"]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+    args.extend(["--output".to_owned(), output.path()]);
+    args.extend(extra.iter().map(|arg| arg.to_string()));
+    headless::run(&args)
+}
+
+#[test]
+fn streamed_patterns_may_not_run_scripts_unless_trusted() {
+    let pattern = TempFile::new("script.json");
+    let output = TempFile::new("script.rgb");
+    std::fs::write(&pattern.0, serde_json::to_string(&script_pattern()).unwrap()).unwrap();
+
+    assert_eq!(stream(&pattern, &output, &[]), 2);
+    assert!(!output.0.exists());
+
+    assert_eq!(stream(&pattern, &output, &["--trust-pattern"]), 0);
+    assert_eq!(std::fs::read(&output.0).unwrap(), [255, 0, 0].repeat(8));
+}
+
+#[test]
+fn streamed_patterns_that_only_draw_need_no_trust() {
+    let pattern = TempFile::new("rect.json");
+    let output = TempFile::new("rect.rgb");
+    let description = PatternDescription {
+        width: 4,
+        height: 2,
+        operations: vec![Rect::new([0.0, 0.0], [2.0, 2.0]).into()],
+    };
+    std::fs::write(&pattern.0, serde_json::to_string(&description).unwrap()).unwrap();
+
+    assert_eq!(stream(&pattern, &output, &[]), 0);
+    let white_then_black = [[255, 255, 255], [255, 255, 255], [0, 0, 0], [0, 0, 0]].concat();
+    assert_eq!(std::fs::read(&output.0).unwrap(), white_then_black.repeat(2));
+}
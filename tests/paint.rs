@@ -0,0 +1,285 @@
+use test_pattern_generator::bmp_generator::paint::{
+    linear_to_srgb, srgb_to_linear, stripe_color, Gradient, GradientKind, GradientStop, Interpolation, Pattern,
+    PatternKind,
+};
+use test_pattern_generator::{Array, Canvas, Group, Line, Operation, Paint, Rect, Transform};
+
+const WHITE: [u8; 3] = [255, 255, 255];
+const BLACK: [u8; 3] = [0, 0, 0];
+
+fn gradient(kind: GradientKind, start: [f64; 2], end: [f64; 2], interpolation: Interpolation) -> Gradient {
+    Gradient {
+        kind,
+        start,
+        end,
+        stops: vec![
+            GradientStop {
+                position: 0.0,
+                color: BLACK,
+            },
+            GradientStop {
+                position: 1.0,
+                color: WHITE,
+            },
+        ],
+        interpolation,
+    }
+}
+
+#[test]
+fn linear_gradients_run_from_start_to_end() {
+    let linear = gradient(GradientKind::Linear, [0.0, 0.0], [100.0, 0.0], Interpolation::CodeValues);
+
+    assert_eq!(linear.position_at((0.0, 0.0)), 0.0);
+    assert_eq!(linear.position_at((50.0, 30.0)), 0.5);
+    assert_eq!(linear.position_at((100.0, -7.0)), 1.0);
+
+    assert_eq!(linear.color_at_position(0.0), BLACK);
+    assert_eq!(linear.color_at_position(0.5), [128; 3]);
+    assert_eq!(linear.color_at_position(1.0), WHITE);
+}
+
+#[test]
+fn colors_past_the_ends_are_clamped_to_the_outer_stops() {
+    let linear = gradient(GradientKind::Linear, [0.0, 0.0], [100.0, 0.0], Interpolation::CodeValues);
+
+    assert_eq!(linear.color_at_position(linear.position_at((-20.0, 0.0))), BLACK);
+    assert_eq!(linear.color_at_position(linear.position_at((150.0, 0.0))), WHITE);
+}
+
+#[test]
+fn stops_can_be_given_in_any_order() {
+    let mut linear = gradient(GradientKind::Linear, [0.0, 0.0], [100.0, 0.0], Interpolation::CodeValues);
+    let sorted = linear.clone();
+    linear.stops.reverse();
+
+    for position in [0.0, 0.25, 0.5, 0.9, 1.0] {
+        assert_eq!(linear.color_at_position(position), sorted.color_at_position(position));
+    }
+}
+
+#[test]
+fn radial_gradients_reach_the_last_stop_at_the_distance_to_the_end() {
+    let radial = gradient(GradientKind::Radial, [50.0, 50.0], [50.0, 100.0], Interpolation::CodeValues);
+
+    assert_eq!(radial.position_at((50.0, 50.0)), 0.0);
+    assert_eq!(radial.position_at((50.0, 75.0)), 0.5);
+    assert_eq!(radial.position_at((20.0, 50.0)), 0.6);
+    assert_eq!(radial.position_at((90.0, 80.0)), 1.0);
+}
+
+#[test]
+fn conic_gradients_turn_clockwise_from_the_end() {
+    let conic = gradient(GradientKind::Conic, [50.0, 50.0], [100.0, 50.0], Interpolation::CodeValues);
+
+    assert_eq!(conic.position_at((80.0, 50.0)), 0.0);
+    assert_eq!(conic.position_at((50.0, 100.0)), 0.25);
+    assert_eq!(conic.position_at((0.0, 50.0)), 0.5);
+    assert_eq!(conic.position_at((50.0, 0.0)), 0.75);
+}
+
+#[test]
+fn linear_light_blends_brighter_than_code_values() {
+    let code_values = gradient(GradientKind::Linear, [0.0, 0.0], [1.0, 0.0], Interpolation::CodeValues);
+    let linear_light = gradient(GradientKind::Linear, [0.0, 0.0], [1.0, 0.0], Interpolation::LinearLight);
+
+    // half the light of white is code value 188, not 128
+    assert_eq!(code_values.color_at_position(0.5), [128; 3]);
+    assert_eq!(linear_light.color_at_position(0.5), [188; 3]);
+    assert_eq!(linear_light.color_at_position(0.0), BLACK);
+    assert_eq!(linear_light.color_at_position(1.0), WHITE);
+}
+
+#[test]
+fn srgb_round_trips_through_linear_light() {
+    for value in 0..=255 {
+        assert_eq!(linear_to_srgb(srgb_to_linear(value)), value);
+    }
+    assert_eq!(srgb_to_linear(0), 0.0);
+    assert_eq!(srgb_to_linear(255), 1.0);
+}
+
+#[test]
+fn gradients_are_sampled_at_pixel_centers() {
+    let mut canvas = Canvas::new(4, 1);
+    let paint = Paint::Gradient(gradient(GradientKind::Linear, [0.0, 0.0], [4.0, 0.0], Interpolation::CodeValues));
+    canvas
+        .draw(Operation::Background {
+            width: 4,
+            height: 1,
+            paint,
+        })
+        .unwrap();
+
+    // 1/8, 3/8, 5/8 and 7/8 of the way along
+    let row: Vec<u8> = (0..4).map(|x| canvas.pixel(x, 0)[0]).collect();
+    assert_eq!(row, [32, 96, 159, 223]);
+}
+
+fn rows(canvas: &Canvas) -> Vec<Vec<[u8; 3]>> {
+    (0..canvas.height())
+        .map(|y| (0..canvas.width()).map(|x| canvas.pixel(x, y)).collect())
+        .collect()
+}
+
+fn linear(start: [f64; 2], end: [f64; 2]) -> Paint {
+    Paint::Gradient(gradient(GradientKind::Linear, start, end, Interpolation::CodeValues))
+}
+
+#[test]
+fn gradients_move_with_their_shape() {
+    let mut moved = Canvas::new(40, 30);
+    moved
+        .draw(
+            Group::new(Transform::translate(20.0, 10.0))
+                .with(Rect::new([0.0, 0.0], [10.0, 10.0]).paint(linear([0.0, 0.0], [10.0, 0.0])))
+                .with(Line::new([0.0, 15.0], [10.0, 15.0]).thickness(3.0).paint(linear([0.0, 0.0], [10.0, 0.0]))),
+        )
+        .unwrap();
+
+    let mut in_place = Canvas::new(40, 30);
+    in_place.draw(Rect::new([20.0, 10.0], [30.0, 20.0]).paint(linear([20.0, 0.0], [30.0, 0.0]))).unwrap();
+    in_place
+        .draw(Line::new([20.0, 25.0], [30.0, 25.0]).thickness(3.0).paint(linear([20.0, 0.0], [30.0, 0.0])))
+        .unwrap();
+
+    assert_eq!(rows(&moved), rows(&in_place));
+    assert_eq!(moved.pixel(20, 10), [13; 3]);
+}
+
+#[test]
+fn every_array_copy_gets_the_whole_gradient() {
+    let mut canvas = Canvas::new(40, 10);
+    canvas
+        .draw(Array::grid(
+            Rect::new([0.0, 0.0], [10.0, 10.0]).paint(linear([0.0, 0.0], [10.0, 0.0])),
+            4,
+            1,
+            [10.0, 0.0],
+        ))
+        .unwrap();
+
+    let first: Vec<[u8; 3]> = (0..10).map(|x| canvas.pixel(x, 5)).collect();
+    for copy in 1..4 {
+        let row: Vec<[u8; 3]> = (0..10).map(|x| canvas.pixel(copy * 10 + x, 5)).collect();
+        assert_eq!(row, first, "copy {copy}");
+    }
+}
+
+#[test]
+fn patterns_turn_with_their_shape() {
+    let stripes = Paint::Pattern(pattern(PatternKind::VerticalStripes, 2));
+    let mut canvas = Canvas::new(20, 20);
+    canvas.draw(Rect::new([4.0, 4.0], [16.0, 16.0]).paint(stripes).rotation(90.0)).unwrap();
+
+    // a quarter turn makes the vertical stripes run across
+    for y in 4..16 {
+        let row: Vec<[u8; 3]> = (4..16).map(|x| canvas.pixel(x, y)).collect();
+        assert!(row.iter().all(|color| *color == row[0]), "row {y}: {row:?}");
+    }
+    let column: Vec<[u8; 3]> = (4..16).step_by(2).map(|y| canvas.pixel(10, y)).collect();
+    for (i, pair) in column.windows(2).enumerate() {
+        assert_ne!(pair[0], pair[1], "stripes {i} and {}", i + 1);
+    }
+    for index in 0..3 {
+        assert!(column.contains(&stripe_color(index)));
+    }
+}
+
+const FOREGROUND: [u8; 3] = [200, 100, 50];
+const BACKGROUND: [u8; 3] = [10, 20, 30];
+
+fn pattern(kind: PatternKind, spacing: u32) -> Pattern {
+    Pattern {
+        kind,
+        origin: [0.0, 0.0],
+        spacing,
+        num_colors: 3,
+        foreground: FOREGROUND,
+        background: BACKGROUND,
+    }
+}
+
+#[test]
+fn stripes_cycle_through_the_stripe_colors() {
+    let stripes = pattern(PatternKind::VerticalStripes, 4);
+    let row: Vec<[u8; 3]> = (0..13).map(|x| stripes.color_at(x, 7)).collect();
+
+    let [red, green, blue] = [0, 1, 2].map(stripe_color);
+    let mut expected = vec![red; 4];
+    expected.extend([green; 4]);
+    expected.extend([blue; 4]);
+    expected.push(red);
+    assert_eq!(row, expected);
+
+    let horizontal = pattern(PatternKind::HorizontalStripes, 4);
+    let column: Vec<[u8; 3]> = (0..13).map(|y| horizontal.color_at(7, y)).collect();
+    assert_eq!(column, expected);
+}
+
+#[test]
+fn patterns_start_at_their_origin() {
+    let mut stripes = pattern(PatternKind::VerticalStripes, 4);
+    stripes.origin = [2.0, 0.0];
+
+    // the two pixels before the origin are the end of the last stripe
+    assert_eq!(stripes.color_at(0, 0), stripe_color(2));
+    assert_eq!(stripes.color_at(1, 0), stripe_color(2));
+    assert_eq!(stripes.color_at(2, 0), stripe_color(0));
+    assert_eq!(stripes.color_at(6, 0), stripe_color(1));
+}
+
+#[test]
+fn checkerboards_alternate_every_square() {
+    let checkerboard = pattern(PatternKind::Checkerboard, 2);
+
+    assert_eq!(checkerboard.color_at(0, 0), FOREGROUND);
+    assert_eq!(checkerboard.color_at(1, 1), FOREGROUND);
+    assert_eq!(checkerboard.color_at(2, 0), BACKGROUND);
+    assert_eq!(checkerboard.color_at(0, 3), BACKGROUND);
+    assert_eq!(checkerboard.color_at(2, 2), FOREGROUND);
+}
+
+#[test]
+fn crosshatches_draw_one_pixel_diagonals() {
+    let crosshatch = pattern(PatternKind::Crosshatch, 4);
+
+    for [x, y] in [[0, 0], [1, 1], [3, 1], [1, 3], [4, 0], [2, 2]] {
+        assert_eq!(crosshatch.color_at(x, y), FOREGROUND, "{x}, {y}");
+    }
+    for [x, y] in [[1, 0], [2, 0], [0, 1], [2, 1]] {
+        assert_eq!(crosshatch.color_at(x, y), BACKGROUND, "{x}, {y}");
+    }
+}
+
+#[test]
+fn dots_sit_in_the_middle_of_each_cell() {
+    let dots = pattern(PatternKind::Dots, 8);
+
+    assert_eq!(dots.color_at(3, 3), FOREGROUND);
+    assert_eq!(dots.color_at(4, 4), FOREGROUND);
+    assert_eq!(dots.color_at(11, 3), FOREGROUND);
+    assert_eq!(dots.color_at(0, 0), BACKGROUND);
+    assert_eq!(dots.color_at(5, 5), BACKGROUND);
+    assert_eq!(dots.color_at(7, 3), BACKGROUND);
+}
+
+#[test]
+fn patterns_repeat_with_their_period() {
+    for (kind, period) in [
+        (PatternKind::VerticalStripes, [15, 1]),
+        (PatternKind::HorizontalStripes, [1, 15]),
+        (PatternKind::Checkerboard, [10, 10]),
+        (PatternKind::Crosshatch, [5, 5]),
+        (PatternKind::Dots, [5, 5]),
+    ] {
+        let fill = pattern(kind, 5);
+        for x in 0..20 {
+            for y in 0..20 {
+                let color = fill.color_at(x, y);
+                assert_eq!(fill.color_at(x + period[0], y), color, "{kind:?} at {x}, {y}");
+                assert_eq!(fill.color_at(x, y + period[1]), color, "{kind:?} at {x}, {y}");
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use bmp::{Image, Pixel};
+use test_pattern_generator::bmp_generator::raw::{self, PixelFormat, YuvEncoding};
+use test_pattern_generator::bmp_generator::scopes::LumaCoefficients;
+
+const RED: Pixel = Pixel { r: 255, g: 0, b: 0 };
+const BLUE: Pixel = Pixel { r: 0, g: 0, b: 255 };
+
+fn image(width: u32, height: u32, pixels: &[Pixel]) -> Image {
+    let mut bmp = Image::new(width, height);
+    for (i, pixel) in pixels.iter().enumerate() {
+        bmp.set_pixel(i as u32 % width, i as u32 / width, *pixel);
+    }
+    bmp
+}
+
+fn full_range() -> YuvEncoding {
+    YuvEncoding {
+        full_range: true,
+        ..YuvEncoding::default()
+    }
+}
+
+#[test]
+fn video_levels_put_white_at_235_and_black_at_16() {
+    let white = image(1, 1, &[Pixel::new(255, 255, 255)]);
+    let black = image(1, 1, &[Pixel::new(0, 0, 0)]);
+
+    assert_eq!(raw::encode(&white, PixelFormat::Yuv444p, YuvEncoding::default()), [235, 128, 128]);
+    assert_eq!(raw::encode(&black, PixelFormat::Yuv444p, YuvEncoding::default()), [16, 128, 128]);
+    assert_eq!(raw::encode(&white, PixelFormat::Yuv444p, full_range()), [255, 128, 128]);
+    assert_eq!(raw::encode(&black, PixelFormat::Yuv444p, full_range()), [0, 128, 128]);
+}
+
+#[test]
+fn colors_follow_the_luma_coefficients() {
+    let red = image(1, 1, &[RED]);
+    let bt601 = YuvEncoding {
+        coefficients: LumaCoefficients::Bt601,
+        full_range: false,
+    };
+
+    assert_eq!(raw::encode(&red, PixelFormat::Yuv444p, YuvEncoding::default()), [63, 102, 240]);
+    assert_eq!(raw::encode(&red, PixelFormat::Yuv444p, bt601), [81, 90, 240]);
+}
+
+#[test]
+fn planar_formats_keep_each_plane_together() {
+    let bmp = image(2, 1, &[RED, BLUE]);
+
+    assert_eq!(raw::encode(&bmp, PixelFormat::Rgb24, YuvEncoding::default()), [255, 0, 0, 0, 0, 255]);
+    assert_eq!(raw::encode(&bmp, PixelFormat::Rgba, YuvEncoding::default()), [255, 0, 0, 255, 0, 0, 255, 255]);
+    // Y Y, Cb Cb, Cr Cr
+    assert_eq!(
+        raw::encode(&bmp, PixelFormat::Yuv444p, YuvEncoding::default()),
+        [63, 32, 102, 240, 240, 118]
+    );
+}
+
+#[test]
+fn yuv420p_averages_the_chroma_of_each_2x2_block() {
+    let bmp = image(2, 2, &[RED, BLUE, BLUE, RED]);
+
+    // (102 + 240 + 240 + 102) / 4 and (240 + 118 + 118 + 240) / 4, rounded
+    assert_eq!(
+        raw::encode(&bmp, PixelFormat::Yuv420p, YuvEncoding::default()),
+        [63, 32, 32, 63, 171, 179]
+    );
+}
+
+#[test]
+fn yuv420p_averages_partial_blocks_at_odd_edges() {
+    let bmp = image(3, 1, &[RED, RED, BLUE]);
+    let frame = raw::encode(&bmp, PixelFormat::Yuv420p, YuvEncoding::default());
+
+    assert_eq!(frame.len(), PixelFormat::Yuv420p.frame_size(3, 1));
+    assert_eq!(frame, [63, 63, 32, 102, 240, 240, 118]);
+}
+
+#[test]
+fn frames_are_the_size_each_format_promises() {
+    let bmp = image(5, 3, &[]);
+    for format in PixelFormat::ALL {
+        let frame = raw::encode(&bmp, format, YuvEncoding::default());
+        assert_eq!(frame.len(), format.frame_size(5, 3), "{format}");
+    }
+}
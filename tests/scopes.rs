@@ -0,0 +1,131 @@
+use bmp::{Image, Pixel};
+use test_pattern_generator::bmp_generator::scopes::{self, LumaCoefficients};
+
+fn filled(width: u32, height: u32, color: [u8; 3]) -> Image {
+    let mut image = Image::new(width, height);
+    for (x, y) in image.coordinates() {
+        image.set_pixel(x, y, Pixel::new(color[0], color[1], color[2]));
+    }
+    image
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+}
+
+#[test]
+fn luma_uses_the_chosen_coefficients() {
+    let red = Pixel::new(255, 0, 0);
+    let green = Pixel::new(0, 255, 0);
+
+    assert_close(LumaCoefficients::Bt709.ycbcr(red).0, 0.2126 * 255.0);
+    assert_close(LumaCoefficients::Bt601.ycbcr(red).0, 0.299 * 255.0);
+    assert_close(LumaCoefficients::Bt709.ycbcr(green).0, 0.7152 * 255.0);
+    assert_close(LumaCoefficients::Bt601.ycbcr(green).0, 0.587 * 255.0);
+}
+
+#[test]
+fn color_differences_reach_half_at_the_primaries() {
+    for coefficients in [LumaCoefficients::Bt709, LumaCoefficients::Bt601] {
+        let (y, cb, cr) = coefficients.ycbcr(Pixel::new(255, 255, 255));
+        assert_close(y, 255.0);
+        assert_close(cb, 0.0);
+        assert_close(cr, 0.0);
+
+        assert_close(coefficients.ycbcr(Pixel::new(255, 0, 0)).2, 0.5);
+        assert_close(coefficients.ycbcr(Pixel::new(0, 0, 255)).1, 0.5);
+        assert_close(coefficients.ycbcr(Pixel::new(0, 255, 255)).2, -0.5);
+        assert_close(coefficients.ycbcr(Pixel::new(255, 255, 0)).1, -0.5);
+    }
+}
+
+#[test]
+fn histograms_count_every_pixel_in_its_level() {
+    let mut image = filled(2, 2, [0, 0, 0]);
+    image.set_pixel(1, 0, Pixel::new(255, 0, 0));
+    image.set_pixel(1, 1, Pixel::new(10, 20, 30));
+
+    let histogram = scopes::histogram(&image, LumaCoefficients::Bt709);
+    assert_eq!((histogram.red[0], histogram.red[10], histogram.red[255]), (2, 1, 1));
+    assert_eq!((histogram.green[0], histogram.green[20]), (3, 1));
+    assert_eq!((histogram.blue[0], histogram.blue[30]), (3, 1));
+    assert_eq!(histogram.red.iter().sum::<u32>(), 4);
+
+    // red is luma 54 under BT.709 and 76 under BT.601
+    assert_eq!(histogram.luma[54], 1);
+    assert_eq!(scopes::histogram(&image, LumaCoefficients::Bt601).luma[76], 1);
+}
+
+#[test]
+fn waveforms_plot_luma_with_white_at_the_top() {
+    let green = filled(8, 8, [0, 255, 0]);
+    let trace = Pixel::new(200, 255, 200);
+
+    // full green is luma 182 under BT.709 and 150 under BT.601
+    let waveform = scopes::waveform(&green, LumaCoefficients::Bt709, 4);
+    assert_eq!([waveform.get_width(), waveform.get_height()], [4, 256]);
+    assert_eq!(waveform.get_pixel(0, 255 - 182), trace);
+    assert_eq!(waveform.get_pixel(3, 255 - 182), trace);
+    assert_ne!(waveform.get_pixel(0, 255 - 150), trace);
+
+    let waveform = scopes::waveform(&green, LumaCoefficients::Bt601, 4);
+    assert_eq!(waveform.get_pixel(0, 255 - 150), trace);
+}
+
+#[test]
+fn waveform_columns_follow_the_image_columns() {
+    let mut image = filled(8, 4, [100, 100, 100]);
+    for y in 0..4 {
+        image.set_pixel(6, y, Pixel::new(200, 200, 200));
+        image.set_pixel(7, y, Pixel::new(200, 200, 200));
+    }
+
+    // two image columns to each waveform column
+    let waveform = scopes::waveform(&image, LumaCoefficients::Bt709, 4);
+    let lit = |x: u32, level: u32| waveform.get_pixel(x, 255 - level).g > 100;
+    assert!((0..3).all(|x| lit(x, 100) && !lit(x, 200)));
+    assert!(lit(3, 200) && !lit(3, 100));
+}
+
+#[test]
+fn parades_show_each_channel_in_its_own_third() {
+    let image = filled(8, 8, [100, 200, 50]);
+    let parade = scopes::parade(&image, 30);
+
+    assert_eq!(parade.get_width(), 30);
+    assert_eq!(parade.get_pixel(5, 255 - 100), Pixel::new(255, 80, 80));
+    assert_eq!(parade.get_pixel(15, 255 - 200), Pixel::new(80, 255, 80));
+    assert_eq!(parade.get_pixel(25, 255 - 50), Pixel::new(80, 80, 255));
+    assert_eq!(parade.get_pixel(5, 255 - 200), Pixel::new(0, 0, 0));
+    assert_eq!(parade.get_pixel(15, 255 - 100), Pixel::new(0, 0, 0));
+}
+
+#[test]
+fn vectorscopes_plot_gray_in_the_middle_and_colors_by_their_hue() {
+    let black = scopes::vectorscope(&filled(4, 4, [0, 0, 0]), LumaCoefficients::Bt709, 256);
+    let gray = scopes::vectorscope(&filled(4, 4, [128, 128, 128]), LumaCoefficients::Bt709, 256);
+    assert_eq!(black.get_pixel(128, 128), gray.get_pixel(128, 128));
+    assert_eq!(black.get_pixel(128, 128).g, 255);
+
+    // red is Cr 0.5 up, and Cb -0.115 left under BT.709 or -0.169 under BT.601
+    let red = filled(4, 4, [255, 0, 0]);
+    let bt709 = scopes::vectorscope(&red, LumaCoefficients::Bt709, 256);
+    let bt601 = scopes::vectorscope(&red, LumaCoefficients::Bt601, 256);
+    assert_eq!(bt709.get_pixel(101, 12).g, 255);
+    assert_eq!(bt601.get_pixel(89, 12).g, 255);
+    assert!(black.get_pixel(101, 12).g < 255);
+    assert!(bt709.get_pixel(89, 12).g < 255);
+}
+
+#[test]
+fn empty_scopes_are_empty_images() {
+    let image = filled(4, 4, [10, 20, 30]);
+
+    let vectorscope = scopes::vectorscope(&image, LumaCoefficients::Bt709, 0);
+    assert_eq!([vectorscope.get_width(), vectorscope.get_height()], [0, 0]);
+    let waveform = scopes::waveform(&image, LumaCoefficients::Bt709, 0);
+    assert_eq!([waveform.get_width(), waveform.get_height()], [0, 256]);
+    assert_eq!(scopes::parade(&image, 0).get_width(), 3);
+    assert_eq!(scopes::histogram(&image, LumaCoefficients::Bt709).render(0).get_height(), 0);
+    assert_eq!(scopes::vectorscope(&image, LumaCoefficients::Bt709, 1).get_width(), 1);
+}
@@ -0,0 +1,84 @@
+use std::collections::BTreeMap;
+
+use bmp::Image;
+use test_pattern_generator::bmp_generator::script::{Script, ScriptError};
+use test_pattern_generator::{Canvas, ParameterValue, PatternError, Text};
+
+fn run(source: &str, width: u32, height: u32) -> Result<Image, ScriptError> {
+    let script = Script::new("test", source)?;
+    let mut bmp = Image::new(width, height);
+    script.run(&mut bmp, &BTreeMap::new())?;
+    Ok(bmp)
+}
+
+#[test]
+fn text_can_be_drawn_repeatedly() {
+    let mut canvas = Canvas::new(200, 100);
+    for i in 0..20 {
+        canvas.draw(Text::new("Test", [i as f64 * 8.0, 10.0]).size(24.0).paint([255, 255, 255])).unwrap();
+    }
+    assert!((0..200).any(|x| (0..100).any(|y| canvas.pixel(x, y) != [0, 0, 0])));
+}
+
+#[test]
+fn huge_text_is_refused() {
+    let mut canvas = Canvas::new(64, 64);
+    let err = canvas.draw(Text::new("A", [0.0, 0.0]).size(1e6)).unwrap_err();
+    assert!(matches!(err, PatternError::InvalidParameter { .. }), "{err}");
+
+    assert!(run("text(0, 0, 1000000, \"A\", [255, 255, 255]);", 64, 64).is_err());
+}
+
+#[test]
+fn scripts_draw_onto_the_canvas() {
+    let bmp = run("clear([0, 0, 255]); rect(2, 2, 6, 6, [255, 0, 0]);", 8, 8).unwrap();
+    assert_eq!(bmp.get_pixel(0, 0), bmp::Pixel::new(0, 0, 255));
+    assert_eq!(bmp.get_pixel(4, 4), bmp::Pixel::new(255, 0, 0));
+}
+
+#[test]
+fn drawing_calls_are_charged_for_the_pixels_they_cover() {
+    // each call is a single Rhai operation, so only the pixel budget stops this
+    let err = run("loop { stripes(1, 8); }", 1920, 1080).unwrap_err();
+    assert!(err.0.contains("pixels"), "{err}");
+}
+
+#[test]
+fn stripes_wider_than_the_canvas_fill_it_with_one_color() {
+    for call in ["stripes(2000000000, 1);", "horizontal_stripes(2000000000, 1);"] {
+        let bmp = run(call, 64, 32).unwrap();
+        assert!(bmp.coordinates().all(|(x, y)| bmp.get_pixel(x, y) == bmp::Pixel::new(255, 0, 0)), "{call}");
+    }
+}
+
+#[test]
+fn small_shapes_can_be_drawn_many_times() {
+    let source = "for i in 0..20000 { pixel(i % 64, i / 64 % 64, [255, 255, 255]); rect(0, 0, 2, 2, [1, 2, 3]); }";
+    assert!(run(source, 64, 64).is_ok());
+}
+
+#[test]
+fn parameters_are_checked_against_their_declarations() {
+    let source = r#"
+        fn parameters() {
+            [
+                #{ name: "size", value: 2, min: 1, max: 4 },
+                #{ name: "color", value: [0, 255, 0] },
+            ]
+        }
+        rect(0, 0, size, size, color);
+    "#;
+    let script = Script::new("test", source).unwrap();
+    let values = BTreeMap::from([
+        ("size".to_owned(), ParameterValue::Int(1_000_000)),
+        ("color".to_owned(), ParameterValue::Text("red".to_owned())),
+        ("unknown".to_owned(), ParameterValue::Int(1)),
+    ]);
+
+    let mut bmp = Image::new(8, 8);
+    script.run(&mut bmp, &values).unwrap();
+
+    // the size is clamped to 4 and the color of the wrong type falls back to its default
+    assert_eq!(bmp.get_pixel(3, 3), bmp::Pixel::new(0, 255, 0));
+    assert_eq!(bmp.get_pixel(4, 4), bmp::Pixel::new(0, 0, 0));
+}
@@ -0,0 +1,196 @@
+#![cfg(feature = "http")]
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde_json::Value;
+use test_pattern_generator::server::{respond, respond_in_time, Reply};
+use test_pattern_generator::{
+    Array, Ellipse, Operation, Outline, Paint, PaintMode, PatternDescription, Polygon, Rect, Text,
+};
+
+fn pattern(width: u32, height: u32, operations: impl IntoIterator<Item = Operation>) -> Vec<u8> {
+    let description = PatternDescription {
+        width,
+        height,
+        operations: operations.into_iter().collect(),
+    };
+    serde_json::to_vec(&description).unwrap()
+}
+
+fn error(reply: &Reply) -> String {
+    assert_eq!(reply.content_type, "application/json");
+    let body: Value = serde_json::from_slice(&reply.body).unwrap();
+    body["error"].as_str().unwrap().to_owned()
+}
+
+#[test]
+fn renders_each_image_format_with_its_content_type() {
+    let body = pattern(16, 8, [Rect::new([0.0, 0.0], [8.0, 8.0]).into()]);
+
+    for (format, content_type, magic) in [
+        ("png", "image/png", &b"\x89PNG"[..]),
+        ("bmp", "image/bmp", b"BM"),
+        ("tiff", "image/tiff", b"II*\0"),
+        ("jpeg", "image/jpeg", b"\xff\xd8"),
+        ("jpg", "image/jpeg", b"\xff\xd8"),
+    ] {
+        let reply = respond("POST", &format!("/render?format={format}"), &body);
+        assert_eq!(reply.status, 200, "{format}: {}", String::from_utf8_lossy(&reply.body));
+        assert_eq!(reply.content_type, content_type, "{format}");
+        assert!(reply.body.starts_with(magic), "{format}");
+    }
+
+    let reply = respond("POST", "/render", &body);
+    assert_eq!(reply.content_type, "image/png");
+}
+
+#[test]
+fn renders_raw_pixels() {
+    let body = pattern(16, 8, [Rect::new([0.0, 0.0], [8.0, 8.0]).into()]);
+    let reply = respond("POST", "/render?format=rgb24", &body);
+
+    assert_eq!(reply.status, 200);
+    assert_eq!(reply.content_type, "application/octet-stream");
+    assert_eq!(reply.body.len(), 16 * 8 * 3);
+    assert_eq!(reply.body[..3], [255, 255, 255]);
+    assert_eq!(reply.body[3 * 15..3 * 16], [0, 0, 0]);
+}
+
+#[test]
+fn bad_requests_are_refused() {
+    let reply = respond("POST", "/render", b"{ not json");
+    assert_eq!(reply.status, 400);
+    assert!(error(&reply).contains("not a pattern description"));
+
+    let reply = respond("POST", "/render?format=gif", &pattern(16, 8, []));
+    assert_eq!(reply.status, 400);
+    assert!(error(&reply).contains("gif"));
+
+    let empty = serde_json::to_vec(&PatternDescription::default()).unwrap();
+    let reply = respond("POST", "/render", &empty);
+    assert_eq!(reply.status, 422, "{}", error(&reply));
+
+    assert_eq!(respond("GET", "/render", b"").status, 405);
+    assert_eq!(respond("POST", "/generators", b"").status, 405);
+    assert_eq!(respond("GET", "/", b"").status, 404);
+}
+
+#[test]
+fn canvases_over_the_size_limit_are_refused() {
+    let reply = respond("POST", "/render", &pattern(8193, 8192, []));
+    assert_eq!(reply.status, 422);
+    assert!(error(&reply).contains("8192"));
+
+    let generate = Operation::Generate {
+        generator: "Stripes".to_owned(),
+        width: 100_000,
+        height: 100_000,
+        parameters: BTreeMap::new(),
+    };
+    let reply = respond("POST", "/render", &pattern(16, 8, [generate]));
+    assert_eq!(reply.status, 422);
+}
+
+#[test]
+fn scripts_and_image_files_are_refused() {
+    let script = Operation::Script {
+        name: "fill".to_owned(),
+        source: "clear([255, 0, 0]);".to_owned(),
+        parameters: BTreeMap::new(),
+    };
+    let open = Operation::OpenImage {
+        path: "/etc/passwd".to_owned(),
+    };
+    let nested = Array::linear(
+        Operation::Group {
+            transform: Default::default(),
+            operations: vec![script.clone()],
+        },
+        2,
+        [1.0, 0.0],
+    );
+
+    for operation in [script, open, nested.into()] {
+        let reply = respond("POST", "/render", &pattern(16, 8, [operation]));
+        assert_eq!(reply.status, 422);
+        assert!(error(&reply).contains("script"));
+    }
+}
+
+#[test]
+fn patterns_that_draw_too_much_are_refused() {
+    let copies = Array::linear(Rect::new([0.0, 0.0], [1.0, 1.0]), 100_000, [0.0, 0.0]);
+    let copies_of_copies = Array::linear(copies, 100_000, [0.0, 0.0]);
+    let reply = respond("POST", "/render", &pattern(16, 8, [copies_of_copies.into()]));
+    assert_eq!(reply.status, 422);
+    assert!(error(&reply).contains("shapes"));
+
+    let huge_text = Text::new("A", [0.0, 0.0]).size(100_000.0);
+    let reply = respond("POST", "/render", &pattern(16, 8, [huge_text.into()]));
+    assert_eq!(reply.status, 422);
+    assert!(error(&reply).contains("text"));
+}
+
+#[test]
+fn patterns_that_would_take_too_long_are_refused() {
+    let points: Vec<[f64; 2]> = (0..100_000)
+        .map(|i| {
+            let angle = i as f64 / 100_000.0 * std::f64::consts::TAU;
+            [500.0 + 400.0 * angle.cos(), 500.0 + 400.0 * angle.sin()]
+        })
+        .collect();
+    let many_points = Polygon::new(points);
+
+    let huge_stroke = Ellipse::new([100.0, 100.0], [10.0, 10.0]).outline(Outline {
+        mode: PaintMode::Stroke,
+        width: 1e6,
+        ..Default::default()
+    });
+
+    let full_canvas = Array::linear(Rect::new([0.0, 0.0], [1920.0, 1080.0]), 100_000, [0.0, 0.0]);
+
+    for (size, operation) in [
+        ([1000, 1000], many_points.into()),
+        ([8192, 8192], huge_stroke.into()),
+        ([1920, 1080], full_canvas.into()),
+    ] {
+        let reply = respond("POST", "/render", &pattern(size[0], size[1], [operation]));
+        assert_eq!(reply.status, 422);
+        assert!(error(&reply).contains("too long"), "{}", error(&reply));
+    }
+}
+
+#[test]
+fn requests_that_run_over_their_time_are_given_up_on() {
+    let background = Operation::Background {
+        width: 4096,
+        height: 4096,
+        paint: Paint::Solid([255, 255, 255]),
+    };
+    let body = pattern(16, 8, [background]);
+    let reply = respond_in_time("POST", "/render?format=rgb24", body, Duration::from_millis(1));
+    assert_eq!(reply.status, 503);
+    assert!(error(&reply).contains("longer than"));
+
+    let body = pattern(16, 8, [Rect::new([0.0, 0.0], [8.0, 8.0]).into()]);
+    let reply = respond_in_time("POST", "/render", body, Duration::from_secs(60));
+    assert_eq!(reply.status, 200);
+}
+
+#[test]
+fn lists_the_generators() {
+    let reply = respond("GET", "/generators", b"");
+    assert_eq!(reply.status, 200);
+    assert_eq!(reply.content_type, "application/json");
+
+    let generators: Value = serde_json::from_slice(&reply.body).unwrap();
+    let names: Vec<&str> = generators
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|generator| generator["name"].as_str().unwrap())
+        .collect();
+    assert!(names.contains(&"Stripes"), "{names:?}");
+    assert!(generators[0]["parameters"].is_array());
+}
@@ -0,0 +1,402 @@
+use bmp::{Image, Pixel};
+use test_pattern_generator::bmp_generator::bmp_generator::BmpGenerator;
+use test_pattern_generator::{
+    Arc, Bezier, Canvas, Ellipse, FillRule, Line, LineCap, Outline, PaintMode, Polygon, Rect, StrokeAlignment,
+    Transform, Triangle,
+};
+
+const WHITE: [u8; 3] = [255, 255, 255];
+const BLACK: [u8; 3] = [0, 0, 0];
+const RED: [u8; 3] = [255, 0, 0];
+
+/// A five pointed star drawn in one stroke, so its middle is wound twice.
+fn star() -> Vec<[f64; 2]> {
+    (0..5)
+        .map(|i| {
+            let angle = (-90.0 + 144.0 * i as f64).to_radians();
+            [50.0 + 40.0 * angle.cos(), 50.0 + 40.0 * angle.sin()]
+        })
+        .collect()
+}
+
+#[test]
+fn non_zero_fills_the_middle_of_a_star() {
+    let mut canvas = Canvas::new(100, 100);
+    canvas.draw(Polygon::new(star()).fill_rule(FillRule::NonZero)).unwrap();
+
+    assert_eq!(canvas.pixel(50, 50), WHITE);
+    assert_eq!(canvas.pixel(50, 20), WHITE);
+    assert_eq!(canvas.pixel(5, 5), BLACK);
+}
+
+#[test]
+fn even_odd_leaves_the_middle_of_a_star_empty() {
+    let mut canvas = Canvas::new(100, 100);
+    canvas.draw(Polygon::new(star()).fill_rule(FillRule::EvenOdd)).unwrap();
+
+    assert_eq!(canvas.pixel(50, 50), BLACK);
+    assert_eq!(canvas.pixel(50, 20), WHITE);
+    assert_eq!(canvas.pixel(5, 5), BLACK);
+}
+
+#[test]
+fn triangles_cover_the_pixels_whose_centers_are_inside() {
+    let mut canvas = Canvas::new(10, 10);
+    canvas.draw(Triangle::new([[0.0, 0.0], [10.0, 0.0], [0.0, 10.0]])).unwrap();
+
+    assert_eq!(canvas.pixel(0, 0), WHITE);
+    assert_eq!(canvas.pixel(8, 0), WHITE);
+    assert_eq!(canvas.pixel(4, 4), WHITE);
+    assert_eq!(canvas.pixel(5, 5), BLACK);
+    assert_eq!(canvas.pixel(9, 9), BLACK);
+}
+
+#[test]
+fn polygons_with_fewer_than_three_points_draw_nothing() {
+    let mut canvas = Canvas::new(10, 10);
+    canvas.draw(Polygon::new([[0.0, 0.0], [10.0, 10.0]])).unwrap();
+
+    assert!(canvas.to_rgb8().iter().all(|&channel| channel == 0));
+}
+
+fn line_with(cap: LineCap) -> Canvas {
+    let mut canvas = Canvas::new(40, 20);
+    canvas.draw(Line::new([10.0, 10.0], [30.0, 10.0]).thickness(4.0).cap(cap)).unwrap();
+    canvas
+}
+
+#[test]
+fn butt_caps_stop_at_the_ends() {
+    let canvas = line_with(LineCap::Butt);
+
+    assert_eq!(canvas.pixel(10, 10), WHITE);
+    assert_eq!(canvas.pixel(29, 10), WHITE);
+    assert_eq!(canvas.pixel(9, 10), BLACK);
+    assert_eq!(canvas.pixel(30, 10), BLACK);
+    // half the thickness either side of the line
+    assert_eq!(canvas.pixel(20, 8), WHITE);
+    assert_eq!(canvas.pixel(20, 11), WHITE);
+    assert_eq!(canvas.pixel(20, 7), BLACK);
+    assert_eq!(canvas.pixel(20, 12), BLACK);
+}
+
+#[test]
+fn square_caps_reach_half_the_thickness_past_the_ends() {
+    let canvas = line_with(LineCap::Square);
+
+    assert_eq!(canvas.pixel(8, 10), WHITE);
+    assert_eq!(canvas.pixel(8, 8), WHITE);
+    assert_eq!(canvas.pixel(31, 11), WHITE);
+    assert_eq!(canvas.pixel(7, 10), BLACK);
+    assert_eq!(canvas.pixel(32, 10), BLACK);
+}
+
+#[test]
+fn round_caps_are_rounded() {
+    let canvas = line_with(LineCap::Round);
+
+    assert_eq!(canvas.pixel(8, 10), WHITE);
+    assert_eq!(canvas.pixel(31, 10), WHITE);
+    // the corner a square cap would fill
+    assert_eq!(canvas.pixel(8, 8), BLACK);
+    assert_eq!(canvas.pixel(31, 11), BLACK);
+    assert_eq!(canvas.pixel(7, 10), BLACK);
+}
+
+#[test]
+fn quadratic_curves_pass_through_their_ends_but_not_their_middle_control_point() {
+    let mut canvas = Canvas::new(100, 60);
+    canvas.draw(Bezier::new([[10.0, 50.0], [50.0, 10.0], [90.0, 50.0]]).thickness(3.0)).unwrap();
+
+    assert_eq!(canvas.pixel(10, 50), WHITE);
+    assert_eq!(canvas.pixel(89, 49), WHITE);
+    // halfway along, a quadratic is halfway between its middle control point and the middle of its ends
+    assert_eq!(canvas.pixel(49, 29), WHITE);
+    assert_eq!(canvas.pixel(50, 10), BLACK);
+    assert_eq!(canvas.pixel(50, 40), BLACK);
+}
+
+#[test]
+fn filled_curves_are_closed_back_to_their_start() {
+    let mut canvas = Canvas::new(100, 60);
+    canvas.draw(Bezier::new([[10.0, 50.0], [50.0, 10.0], [90.0, 50.0]]).filled(true)).unwrap();
+
+    assert_eq!(canvas.pixel(50, 40), WHITE);
+    assert_eq!(canvas.pixel(50, 25), BLACK);
+    assert_eq!(canvas.pixel(50, 52), BLACK);
+}
+
+#[test]
+fn cubic_curves_with_control_points_in_a_row_are_straight() {
+    let mut canvas = Canvas::new(50, 40);
+    canvas.draw(Bezier::new([[10.0, 20.0], [20.0, 20.0], [30.0, 20.0], [40.0, 20.0]]).thickness(2.0)).unwrap();
+
+    for x in 11..39 {
+        assert_eq!(canvas.pixel(x, 19), WHITE, "x = {x}");
+        assert_eq!(canvas.pixel(x, 20), WHITE, "x = {x}");
+        assert_eq!(canvas.pixel(x, 17), BLACK, "x = {x}");
+        assert_eq!(canvas.pixel(x, 22), BLACK, "x = {x}");
+    }
+}
+
+#[test]
+fn curves_need_three_or_four_control_points() {
+    let mut canvas = Canvas::new(20, 20);
+    canvas.draw(Bezier::new([[0.0, 0.0], [20.0, 20.0]]).thickness(4.0)).unwrap();
+
+    assert!(canvas.to_rgb8().iter().all(|&channel| channel == 0));
+}
+
+#[test]
+fn arcs_run_clockwise_from_the_start_angle() {
+    let mut canvas = Canvas::new(100, 100);
+    canvas.draw(Arc::new([50.0, 50.0], [30.0, 30.0], [0.0, 90.0]).thickness(3.0)).unwrap();
+
+    // 0 degrees is to the right, and 90 is straight down
+    assert_eq!(canvas.pixel(79, 50), WHITE);
+    assert_eq!(canvas.pixel(71, 71), WHITE);
+    assert_eq!(canvas.pixel(50, 79), WHITE);
+    assert_eq!(canvas.pixel(28, 28), BLACK);
+    assert_eq!(canvas.pixel(71, 28), BLACK);
+    assert_eq!(canvas.pixel(50, 50), BLACK);
+}
+
+#[test]
+fn filled_arcs_are_pie_slices() {
+    let mut canvas = Canvas::new(100, 100);
+    canvas.draw(Arc::new([50.0, 50.0], [30.0, 30.0], [0.0, 90.0]).filled(true)).unwrap();
+
+    assert_eq!(canvas.pixel(60, 60), WHITE);
+    assert_eq!(canvas.pixel(70, 52), WHITE);
+    assert_eq!(canvas.pixel(40, 40), BLACK);
+    assert_eq!(canvas.pixel(60, 40), BLACK);
+    assert_eq!(canvas.pixel(75, 75), BLACK);
+}
+
+#[test]
+fn elliptical_arcs_follow_both_radii() {
+    let mut canvas = Canvas::new(100, 100);
+    canvas.draw(Arc::new([50.0, 50.0], [40.0, 20.0], [0.0, 180.0]).thickness(3.0)).unwrap();
+
+    assert_eq!(canvas.pixel(89, 50), WHITE);
+    assert_eq!(canvas.pixel(50, 69), WHITE);
+    assert_eq!(canvas.pixel(10, 50), WHITE);
+    assert_eq!(canvas.pixel(50, 89), BLACK);
+    assert_eq!(canvas.pixel(50, 30), BLACK);
+}
+
+fn rect_with(radii: [f64; 4], rotation: f64) -> Canvas {
+    let mut canvas = Canvas::new(40, 40);
+    canvas.draw(Rect::new([0.0, 0.0], [40.0, 40.0]).corner_radii(radii).rotation(rotation)).unwrap();
+    canvas
+}
+
+#[test]
+fn rounded_corners_cut_off_the_pixels_outside_the_radius() {
+    let canvas = rect_with([10.0; 4], 0.0);
+
+    for [x, y] in [[0, 0], [2, 2], [39, 0], [37, 37], [0, 39]] {
+        assert_eq!(canvas.pixel(x, y), BLACK, "{x}, {y}");
+    }
+    for [x, y] in [[3, 3], [20, 0], [0, 20], [36, 36], [39, 20]] {
+        assert_eq!(canvas.pixel(x, y), WHITE, "{x}, {y}");
+    }
+}
+
+#[test]
+fn corner_radii_are_clamped_to_half_the_shorter_side() {
+    let draw = |radius: f64| {
+        let mut canvas = Canvas::new(40, 20);
+        canvas.draw(Rect::new([0.0, 0.0], [40.0, 20.0]).corner_radii([radius; 4])).unwrap();
+        canvas.to_rgb8()
+    };
+
+    let stadium = draw(10.0);
+    assert_eq!(draw(1000.0), stadium);
+    assert_ne!(draw(5.0), stadium);
+}
+
+#[test]
+fn each_corner_has_its_own_radius() {
+    // top left, top right, bottom right, bottom left
+    let canvas = rect_with([0.0, 10.0, 0.0, 10.0], 0.0);
+
+    assert_eq!(canvas.pixel(0, 0), WHITE);
+    assert_eq!(canvas.pixel(39, 0), BLACK);
+    assert_eq!(canvas.pixel(39, 39), WHITE);
+    assert_eq!(canvas.pixel(0, 39), BLACK);
+}
+
+#[test]
+fn rounded_corners_turn_with_the_rect() {
+    let canvas = rect_with([10.0, 0.0, 0.0, 0.0], 90.0);
+
+    // turned clockwise, the rounded top left corner ends up at the top right
+    assert_eq!(canvas.pixel(0, 0), WHITE);
+    assert_eq!(canvas.pixel(39, 0), BLACK);
+    assert_eq!(canvas.pixel(39, 39), WHITE);
+    assert_eq!(canvas.pixel(0, 39), WHITE);
+
+    assert_eq!(rect_with([10.0, 0.0, 0.0, 0.0], 180.0).to_rgb8(), rect_with([0.0, 0.0, 10.0, 0.0], 0.0).to_rgb8());
+}
+
+fn outline(mode: PaintMode, width: f64, alignment: StrokeAlignment) -> Outline {
+    Outline {
+        mode,
+        width,
+        alignment,
+        color: RED,
+    }
+}
+
+#[test]
+fn an_inside_stroke_on_the_canvas_edge_covers_every_border_pixel() {
+    let (width, height) = (33, 17);
+    let mut canvas = Canvas::new(width, height);
+    let border = outline(PaintMode::Stroke, 1.0, StrokeAlignment::Inside);
+    canvas.draw(Rect::new([0.0, 0.0], [width as f64, height as f64]).outline(border)).unwrap();
+
+    for x in 0..width {
+        for y in 0..height {
+            let on_border = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+            let expected = if on_border { RED } else { BLACK };
+            assert_eq!(canvas.pixel(x, y), expected, "{x}, {y}");
+        }
+    }
+}
+
+/// The colors along the middle row of a 10 to 30 white square on a 40 by 40 canvas with a red `border`.
+fn stroked_row(border: Outline) -> Vec<[u8; 3]> {
+    let mut canvas = Canvas::new(40, 40);
+    canvas.draw(Rect::new([10.0, 10.0], [30.0, 30.0]).outline(border)).unwrap();
+    (0..40).map(|x| canvas.pixel(x, 20)).collect()
+}
+
+fn expected_row(stroked: &[u32], filled: std::ops::Range<u32>) -> Vec<[u8; 3]> {
+    (0..40)
+        .map(|x| match (stroked.contains(&x), filled.contains(&x)) {
+            (true, _) => RED,
+            (false, true) => WHITE,
+            (false, false) => BLACK,
+        })
+        .collect()
+}
+
+#[test]
+fn inside_strokes_cover_the_edge_pixels_of_the_shape() {
+    let row = stroked_row(outline(PaintMode::FillAndStroke, 1.0, StrokeAlignment::Inside));
+    assert_eq!(row, expected_row(&[10, 29], 10..30));
+
+    let row = stroked_row(outline(PaintMode::FillAndStroke, 3.0, StrokeAlignment::Inside));
+    assert_eq!(row, expected_row(&[10, 11, 12, 27, 28, 29], 10..30));
+}
+
+#[test]
+fn centered_strokes_straddle_the_edge() {
+    let row = stroked_row(outline(PaintMode::FillAndStroke, 2.0, StrokeAlignment::Center));
+    assert_eq!(row, expected_row(&[9, 10, 29, 30], 10..30));
+
+    let row = stroked_row(outline(PaintMode::FillAndStroke, 4.0, StrokeAlignment::Center));
+    assert_eq!(row, expected_row(&[8, 9, 10, 11, 28, 29, 30, 31], 10..30));
+}
+
+#[test]
+fn outside_strokes_leave_the_shape_untouched() {
+    let row = stroked_row(outline(PaintMode::FillAndStroke, 1.0, StrokeAlignment::Outside));
+    assert_eq!(row, expected_row(&[9, 30], 10..30));
+
+    let row = stroked_row(outline(PaintMode::Stroke, 2.0, StrokeAlignment::Outside));
+    assert_eq!(row, expected_row(&[8, 9, 30, 31], 0..0));
+}
+
+#[test]
+fn outside_strokes_round_the_corners() {
+    let mut canvas = Canvas::new(40, 40);
+    let border = outline(PaintMode::Stroke, 2.0, StrokeAlignment::Outside);
+    canvas.draw(Rect::new([10.0, 10.0], [30.0, 30.0]).outline(border)).unwrap();
+
+    assert_eq!(canvas.pixel(9, 9), RED);
+    assert_eq!(canvas.pixel(8, 9), RED);
+    assert_eq!(canvas.pixel(8, 8), BLACK);
+    assert_eq!(canvas.pixel(10, 10), BLACK);
+}
+
+fn row(canvas: &Canvas, y: u32) -> Vec<[u8; 3]> {
+    (0..canvas.width()).map(|x| canvas.pixel(x, y)).collect()
+}
+
+#[test]
+fn shapes_can_hang_off_any_edge() {
+    let mut canvas = Canvas::new(10, 10);
+    canvas.draw(Rect::new([-5.0, -5.0], [3.0, 3.0])).unwrap();
+    canvas.draw(Rect::new([8.0, 8.0], [15.0, 15.0])).unwrap();
+
+    assert_eq!(canvas.pixel(0, 0), WHITE);
+    assert_eq!(canvas.pixel(2, 2), WHITE);
+    assert_eq!(canvas.pixel(3, 0), BLACK);
+    assert_eq!(canvas.pixel(9, 9), WHITE);
+    assert_eq!(canvas.pixel(7, 9), BLACK);
+}
+
+#[test]
+fn ellipses_centered_off_the_canvas_draw_the_part_on_it() {
+    let mut canvas = Canvas::new(10, 10);
+    canvas.draw(Ellipse::new([-2.0, 5.0], [5.0, 5.0])).unwrap();
+
+    // pixel centers 4.5 and 5.5 across from the middle
+    assert_eq!(canvas.pixel(2, 5), WHITE);
+    assert_eq!(canvas.pixel(3, 5), BLACK);
+    assert_eq!(canvas.pixel(0, 0), BLACK);
+}
+
+#[test]
+fn shapes_entirely_off_the_canvas_draw_nothing() {
+    let mut canvas = Canvas::new(10, 10);
+    canvas.draw(Rect::new([-1e12, -1e12], [-20.0, -20.0])).unwrap();
+    canvas.draw(Ellipse::new([1e9, 5.0], [3.0, 3.0])).unwrap();
+    canvas.draw(Line::new([20.0, 20.0], [40.0, 40.0]).thickness(4.0)).unwrap();
+
+    assert!((0..10).all(|y| row(&canvas, y).iter().all(|pixel| *pixel == BLACK)));
+}
+
+#[test]
+fn sub_pixel_edges_cover_the_pixels_whose_centers_are_inside() {
+    let mut canvas = Canvas::new(4, 2);
+    canvas.draw(Rect::new([0.4, 0.0], [2.6, 1.0])).unwrap();
+    canvas.draw(Rect::new([0.6, 1.0], [2.4, 2.0])).unwrap();
+
+    assert_eq!(row(&canvas, 0), [WHITE, WHITE, WHITE, BLACK]);
+    assert_eq!(row(&canvas, 1), [BLACK, WHITE, BLACK, BLACK]);
+}
+
+#[test]
+fn moving_by_a_fraction_of_a_pixel_only_changes_pixels_whose_centers_are_crossed() {
+    let draw = |offset: f64| {
+        let mut canvas = Canvas::new(8, 1);
+        canvas.draw(Rect::new([2.0 + offset, 0.0], [5.0 + offset, 1.0])).unwrap();
+        row(&canvas, 0)
+    };
+
+    assert_eq!(draw(0.0), draw(0.25));
+    assert_eq!(draw(0.75), [BLACK, BLACK, BLACK, WHITE, WHITE, WHITE, BLACK, BLACK]);
+}
+
+#[test]
+fn image_layers_can_hang_off_the_canvas_and_sit_between_pixels() {
+    let mut layer = Image::new(2, 2);
+    for (x, y) in layer.coordinates() {
+        layer.set_pixel(x, y, Pixel::new(255, 0, 0));
+    }
+
+    let mut bmp = Image::new(4, 4);
+    BmpGenerator::add_image(&mut bmp, &layer, [-1.0, -1.0], 1.0, &Transform::IDENTITY);
+    assert_eq!(bmp.get_pixel(0, 0), Pixel::new(255, 0, 0));
+    assert_eq!(bmp.get_pixel(1, 0), Pixel::new(0, 0, 0));
+    assert_eq!(bmp.get_pixel(0, 1), Pixel::new(0, 0, 0));
+
+    // the layer covers 2.6 to 4.6, so only the pixel centered at 3.5 is inside it across
+    let mut bmp = Image::new(4, 1);
+    BmpGenerator::add_image(&mut bmp, &layer, [2.6, 0.0], 1.0, &Transform::IDENTITY);
+    let row: Vec<Pixel> = (0..4).map(|x| bmp.get_pixel(x, 0)).collect();
+    assert_eq!(row, [Pixel::new(0, 0, 0), Pixel::new(0, 0, 0), Pixel::new(0, 0, 0), Pixel::new(255, 0, 0)]);
+}
@@ -0,0 +1,108 @@
+use test_pattern_generator::{Array, Canvas, Operation, Paint, PatternError, Rect, Symmetry, Text};
+
+const WHITE: [u8; 3] = [255, 255, 255];
+const BLACK: [u8; 3] = [0, 0, 0];
+
+fn lit(canvas: &Canvas, xs: std::ops::Range<u32>, ys: std::ops::Range<u32>) -> usize {
+    xs.flat_map(|x| ys.clone().map(move |y| (x, y)))
+        .filter(|&(x, y)| canvas.pixel(x, y) != BLACK)
+        .count()
+}
+
+#[test]
+fn mirrored_shapes_appear_on_the_other_side() {
+    let mut canvas = Canvas::new(20, 10);
+    canvas.set_symmetry(Some(Symmetry::Horizontal));
+    canvas.draw(Rect::new([2.0, 2.0], [6.0, 6.0])).unwrap();
+
+    assert_eq!(canvas.pixel(3, 3), WHITE);
+    assert_eq!(canvas.pixel(16, 3), WHITE);
+    assert_eq!(canvas.pixel(3, 7), BLACK);
+    assert_eq!(lit(&canvas, 0..20, 0..10), 32);
+}
+
+#[test]
+fn both_ways_fills_every_corner() {
+    let mut canvas = Canvas::new(20, 10);
+    canvas.set_symmetry(Some(Symmetry::Both));
+    canvas.draw(Rect::new([0.0, 0.0], [2.0, 2.0])).unwrap();
+
+    for [x, y] in [[0, 0], [19, 0], [0, 9], [19, 9]] {
+        assert_eq!(canvas.pixel(x, y), WHITE, "{x}, {y}");
+    }
+    assert_eq!(lit(&canvas, 0..20, 0..10), 16);
+}
+
+#[test]
+fn rotational_symmetry_turns_copies_about_the_middle() {
+    let mut canvas = Canvas::new(20, 20);
+    canvas.set_symmetry(Some(Symmetry::Rotational { folds: 4 }));
+    canvas.draw(Rect::new([8.0, 0.0], [12.0, 2.0])).unwrap();
+
+    // top, right, bottom and left edges, turning clockwise
+    for [x, y] in [[10, 0], [19, 10], [10, 19], [0, 10]] {
+        assert_eq!(canvas.pixel(x, y), WHITE, "{x}, {y}");
+    }
+    assert_eq!(canvas.pixel(10, 10), BLACK);
+}
+
+#[test]
+fn text_can_be_mirrored() {
+    let mut canvas = Canvas::new(40, 20);
+    canvas.set_symmetry(Some(Symmetry::Horizontal));
+    canvas.draw(Text::new("F", [2.0, 2.0]).size(14.0)).unwrap();
+
+    assert!(lit(&canvas, 0..20, 0..20) > 0);
+    for x in 0..20 {
+        for y in 0..20 {
+            assert_eq!(canvas.pixel(x, y), canvas.pixel(39 - x, y), "{x}, {y}");
+        }
+    }
+}
+
+#[test]
+fn text_can_be_turned() {
+    let mut canvas = Canvas::new(40, 40);
+    canvas.set_symmetry(Some(Symmetry::Rotational { folds: 2 }));
+    canvas.draw(Text::new("L", [2.0, 2.0]).size(14.0).paint([200, 100, 50])).unwrap();
+
+    assert!(lit(&canvas, 0..20, 0..20) > 0);
+    for x in 0..20 {
+        for y in 0..20 {
+            assert_eq!(canvas.pixel(x, y), canvas.pixel(39 - x, 39 - y), "{x}, {y}");
+        }
+    }
+}
+
+#[test]
+fn text_can_be_arrayed_in_a_circle() {
+    let mut canvas = Canvas::new(40, 40);
+    canvas.draw(Array::radial(Text::new("o", [16.0, 0.0]).size(12.0), 4, [20.0, 20.0], 90.0)).unwrap();
+
+    assert!(lit(&canvas, 0..40, 0..12) > 0, "top");
+    assert!(lit(&canvas, 28..40, 0..40) > 0, "right");
+    assert!(lit(&canvas, 0..40, 28..40) > 0, "bottom");
+    assert!(lit(&canvas, 0..12, 0..40) > 0, "left");
+}
+
+#[test]
+fn operations_that_replace_the_canvas_are_not_mirrored() {
+    let background = Operation::Background {
+        width: 4,
+        height: 4,
+        paint: Paint::Solid(WHITE),
+    };
+
+    let mut canvas = Canvas::new(4, 4);
+    canvas.set_symmetry(Some(Symmetry::Both));
+    canvas.draw(background.clone()).unwrap();
+    assert_eq!(canvas.pixel(0, 0), WHITE);
+
+    let mirrored = Operation::Symmetric {
+        symmetry: Symmetry::Both,
+        operation: Box::new(background),
+    };
+    let err = Canvas::new(4, 4).draw(mirrored).unwrap_err();
+    assert!(matches!(err, PatternError::InvalidParameter { .. }), "{err}");
+    assert!(err.to_string().contains("can't be mirrored"), "{err}");
+}
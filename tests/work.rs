@@ -0,0 +1,76 @@
+use test_pattern_generator::{
+    Array, Ellipse, Operation, Outline, Paint, PaintMode, PatternDescription, Polygon, Rect,
+};
+
+fn work(operation: impl Into<Operation>, width: u32, height: u32) -> u64 {
+    operation.into().estimated_work(width, height)
+}
+
+#[test]
+fn shapes_cost_the_pixels_around_them() {
+    let small = work(Rect::new([0.0, 0.0], [10.0, 10.0]), 100, 100);
+    assert!((100..=144).contains(&small), "{small}");
+
+    // clipped to the canvas however large the shape is
+    assert_eq!(work(Rect::new([-1e9, -1e9], [1e9, 1e9]), 100, 100), 100 * 100);
+    assert_eq!(work(Rect::new([500.0, 500.0], [510.0, 510.0]), 100, 100), 0);
+}
+
+#[test]
+fn copies_off_the_canvas_cost_nothing() {
+    let one = work(Rect::new([0.0, 0.0], [10.0, 10.0]), 100, 100);
+    let arrayed = work(Array::linear(Rect::new([0.0, 0.0], [10.0, 10.0]), 1000, [200.0, 0.0]), 100, 100);
+    assert_eq!(arrayed, one);
+
+    let stacked = work(Array::linear(Rect::new([0.0, 0.0], [10.0, 10.0]), 1000, [0.0, 0.0]), 100, 100);
+    assert_eq!(stacked, one * 1000);
+}
+
+#[test]
+fn polygons_cost_every_edge_at_every_pixel() {
+    let square = Polygon::new([[0.0, 0.0], [50.0, 0.0], [50.0, 50.0], [0.0, 50.0]]);
+    let octagon = Polygon::new([
+        [0.0, 0.0],
+        [25.0, 0.0],
+        [50.0, 0.0],
+        [50.0, 25.0],
+        [50.0, 50.0],
+        [25.0, 50.0],
+        [0.0, 50.0],
+        [0.0, 25.0],
+    ]);
+    assert_eq!(work(octagon, 100, 100), 2 * work(square, 100, 100));
+}
+
+#[test]
+fn strokes_cost_the_pixels_around_each_edge() {
+    let stroke = |width: f64| Outline {
+        mode: PaintMode::Stroke,
+        width,
+        ..Default::default()
+    };
+    let filled = work(Ellipse::new([500.0, 500.0], [300.0, 300.0]), 1000, 1000);
+    let thin = work(Ellipse::new([500.0, 500.0], [300.0, 300.0]).outline(stroke(2.0)), 1000, 1000);
+    let thick = work(Ellipse::new([500.0, 500.0], [300.0, 300.0]).outline(stroke(1e6)), 1000, 1000);
+
+    // a thin stroke only goes near the outline, a huge one goes over the whole canvas for every edge
+    assert!(thin < filled * 3, "{thin} against {filled}");
+    assert!(thick > 1000 * 1000 * 100, "{thick}");
+}
+
+#[test]
+fn patterns_follow_the_canvas_size_through_backgrounds() {
+    let pattern = PatternDescription {
+        width: 10,
+        height: 10,
+        operations: vec![
+            Operation::Background {
+                width: 1000,
+                height: 1000,
+                paint: Paint::Solid([0, 0, 0]),
+            },
+            Rect::new([0.0, 0.0], [1e9, 1e9]).into(),
+        ],
+    };
+    assert_eq!(pattern.estimated_work(), 2 * 1000 * 1000);
+}